@@ -0,0 +1,150 @@
+//! Compares the schedulers on the bundled scenarios and on a hold model, where
+//! each event taken out of a queue of `n` events schedules a new one.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::hint::black_box;
+
+use tdes::{
+    internal::core::{
+        events::{Event, TimerEvent},
+        log::LoggerLevel,
+        scheduler::Scheduler,
+        trace::{TraceRecord, Tracer},
+    },
+    prelude::*,
+    scenarios::{FlowUpdatingPairwise, SimpleMessageScenario},
+};
+
+// sets up a scenario with a number of peers and runs it
+type ScenarioRun = fn(&mut Context, usize);
+
+const SCHEDULERS: [SchedulerKind; 2] = [SchedulerKind::Heap, SchedulerKind::Calendar];
+
+fn context(scheduler: SchedulerKind) -> Context {
+    let mut ctx = Context::new(Some(42), Some(LoggerLevel::Error), true);
+    ctx.set_scheduler(scheduler);
+    ctx
+}
+
+fn run<S: Scenario>(
+    ctx: &mut Context,
+    n_peers: usize,
+    topology: &str,
+    deadline: f64,
+    config: &str,
+) {
+    let opts = ExperimentOptions {
+        topology: TopologyInfo::from_args(Some(n_peers), Some(topology.to_string())),
+        arrival_time: None,
+        deadline: Some(SimTime::from_secs(deadline)),
+    };
+    let config = serde_yaml::from_str(config).expect("config should be valid");
+    S::start(ctx, &Simulator::default(), opts, config);
+}
+
+fn flow_updating(ctx: &mut Context, n_peers: usize) {
+    run::<FlowUpdatingPairwise>(ctx, n_peers, "ring", 0.2, "{}");
+}
+
+fn simple_message(ctx: &mut Context, n_peers: usize) {
+    run::<SimpleMessageScenario>(ctx, n_peers, "full", 10.0, "msg_size: 16");
+}
+
+// every scheduler must process the events in the same order
+fn assert_same_order(name: &str, n_peers: usize, scenario: ScenarioRun) {
+    let traces: Vec<Vec<TraceRecord>> = SCHEDULERS
+        .iter()
+        .map(|&scheduler| {
+            let mut ctx = context(scheduler);
+            ctx.tracer = Some(Tracer::Memory(Vec::new()));
+            scenario(&mut ctx, n_peers);
+            match ctx.tracer.take() {
+                Some(Tracer::Memory(records)) => records,
+                _ => Vec::new(),
+            }
+        })
+        .collect();
+    assert!(
+        traces.windows(2).all(|pair| pair[0] == pair[1]),
+        "the schedulers processed the events of {name} in different orders"
+    );
+}
+
+fn bench_scenarios(c: &mut Criterion) {
+    let cases: [(&str, usize, ScenarioRun); 3] = [
+        ("flow_updating", 100, flow_updating),
+        ("flow_updating", 1000, flow_updating),
+        ("simple_message", 1000, simple_message),
+    ];
+
+    let mut group = c.benchmark_group("scenarios");
+    group.sample_size(10);
+    for (name, n_peers, scenario) in cases {
+        assert_same_order(name, n_peers, scenario);
+        for scheduler in SCHEDULERS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name}/{scheduler}"), n_peers),
+                &n_peers,
+                |b, &n_peers| {
+                    b.iter(|| {
+                        let mut ctx = context(scheduler);
+                        scenario(&mut ctx, n_peers);
+                        black_box(ctx.clock)
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+#[derive(Debug)]
+struct Noop;
+
+impl Timer for Noop {
+    fn fire(&self, _ctx: &mut Context) {}
+}
+
+fn bench_hold(c: &mut Criterion) {
+    const HOLDS: usize = 100_000;
+
+    let mut group = c.benchmark_group("hold");
+    for n_events in [1_000, 100_000] {
+        for scheduler in SCHEDULERS {
+            group.bench_with_input(
+                BenchmarkId::new(scheduler.to_string(), n_events),
+                &n_events,
+                |b, &n_events| {
+                    b.iter(|| {
+                        let mut rng = ChaCha8Rng::seed_from_u64(1);
+                        let mut queue = scheduler.create();
+                        let mut id = 0;
+                        let mut schedule = |queue: &mut Box<dyn Scheduler>, time: f64| {
+                            let mut event =
+                                TimerEvent::create(SimTime::from_secs(time), Box::new(Noop));
+                            event.set_id(id);
+                            id += 1;
+                            queue.push(event);
+                        };
+
+                        for _ in 0..n_events {
+                            schedule(&mut queue, rng.random_range(0.0..1.0));
+                        }
+                        for _ in 0..HOLDS {
+                            let next = queue.pop().expect("the queue should not be empty");
+                            let time = next.timestamp().as_secs() + rng.random_range(0.0..1.0);
+                            schedule(&mut queue, time);
+                        }
+                        black_box(queue.len())
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scenarios, bench_hold);
+criterion_main!(benches);
@@ -1,6 +1,15 @@
 use clap::{ArgGroup, Parser};
 
-use crate::internal::core::log::LoggerLevel;
+use crate::internal::core::{
+    log::{LogFormat, LoggerLevel},
+    log_filter::LogFilter,
+    scheduler::SchedulerKind,
+    sink::MetricsFormat,
+    time::{SimDuration, SimTime, TimeUnit},
+    trace::TraceFormat,
+};
+
+use super::{super::explore::ExploreStrategy, export::ExportFormat};
 
 #[derive(Parser, Debug, Clone)]
 #[command(
@@ -10,7 +19,7 @@ use crate::internal::core::log::LoggerLevel;
     group(
         ArgGroup::new("run_mode")
             .required(true)
-            .args(["config", "scenario", "list_scenarios", "list_topologies", "list_arrival_times"])
+            .args(["config", "scenario", "list_scenarios", "list_topologies", "list_arrival_times", "export_trace"])
     )
 )]
 pub struct Args {
@@ -22,7 +31,15 @@ pub struct Args {
     #[arg(long, value_enum, default_value = "info")]
     pub logger_level: Option<LoggerLevel>,
 
-    /// The simulator does not output the logs to the console
+    /// Least level of the logs written to the log files, which get every log if not set
+    #[arg(long, value_enum)]
+    pub file_level: Option<LoggerLevel>,
+
+    /// Selects the logs of the simulations by peer and module, e.g. "peer=3,discovery=trace,*=warn" keeps the logs of peer 3 and of no peer, with the trace logs of the discovery modules and the warnings of the others on the console. The levels do not apply to the log files, which use --file-level
+    #[arg(long)]
+    pub log_filter: Option<LogFilter>,
+
+    /// The simulator only writes errors to the console
     #[arg(short, long)]
     pub quiet: bool,
 
@@ -58,7 +75,7 @@ pub struct Args {
     #[arg(long, requires = "scenario")]
     pub topology: Option<String>,
 
-    /// If set to true, uses the jitter configuration Weibull(1.064, 2.872) * 0.001 - can only be used if 'scenario' is set
+    /// If set to true, uses the jitter configuration Weibull(1.064, 2.872) in milliseconds - can only be used if 'scenario' is set
     #[arg(long, requires = "scenario")]
     pub use_jitter: bool,
 
@@ -66,6 +83,10 @@ pub struct Args {
     #[arg(long, requires = "scenario", default_value = "1")]
     pub repetitions: u64,
 
+    /// Only runs this repetition of each experiment, using the same seed it has when every repetition is run
+    #[arg(long)]
+    pub rep: Option<u64>,
+
     /// Which topologies can be selected
     #[arg(long)]
     pub list_topologies: bool,
@@ -80,16 +101,132 @@ pub struct Args {
 
     /// Optional value which tells the simulator when to stop (it may stop earlier if there are no events to process)
     #[arg(long)]
-    pub deadline: Option<f64>,
+    pub deadline: Option<SimTime>,
+
+    /// Rounds the times of events to whole ticks of this duration (e.g. 1ns), so that the clock is exact - can only be used if 'scenario' is set
+    #[arg(long, requires = "scenario")]
+    pub time_resolution: Option<SimDuration>,
 
     /// The amount of logs needed to flush to file
     #[arg(long, default_value = "200")]
     pub flush_threshold: usize,
 
+    /// The format of the metrics files, overrides the one in the configuration's output section [default: jsonl]
+    #[arg(long, value_enum)]
+    pub metrics_format: Option<MetricsFormat>,
+
+    /// How much simulation time passes between samples of the metrics registry, overrides the one in the configuration's output section
+    #[arg(long)]
+    pub metrics_interval: Option<SimDuration>,
+
+    /// Records every processed event in a trace of the given format, overrides the one in the configuration's output section
+    #[arg(long, value_enum)]
+    pub trace: Option<TraceFormat>,
+
+    /// The format of the log files, overrides the one in the configuration's output section [default: text]
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Writes the logs of each peer to a file of its own in the results directory, the log file keeps the logs of no peer
+    #[arg(long)]
+    pub per_peer_logs: bool,
+
+    /// Reruns the repetitions in a results directory and reports the first event that differs from their recorded traces
+    #[arg(long, requires = "config")]
+    pub verify_trace: Option<String>,
+
+    /// Saves a snapshot of each run when its clock passes these times, separated by commas. Parquet metrics files of resumed runs only contain what follows the snapshot
+    #[arg(long, value_delimiter = ',')]
+    pub checkpoint_at: Option<Vec<SimTime>>,
+
+    /// Continues the run saved in a snapshot, writing its results to a new directory
+    #[arg(long, requires = "config")]
+    pub resume: Option<String>,
+
+    /// Exports the messages of a recorded trace file as a sequence diagram or a Chrome trace
+    #[arg(long)]
+    pub export_trace: Option<String>,
+
+    /// The format of the exported trace
+    #[arg(long, value_enum, requires = "export_trace", default_value = "mermaid")]
+    pub export_format: ExportFormat,
+
+    /// Only exports the messages sent at or after this time - can only be used if 'export_trace' is set
+    #[arg(long, requires = "export_trace")]
+    pub from: Option<f64>,
+
+    /// Only exports the messages sent at or before this time - can only be used if 'export_trace' is set
+    #[arg(long, requires = "export_trace")]
+    pub until: Option<f64>,
+
+    /// Only exports the messages exchanged between these peers, separated by commas - can only be used if 'export_trace' is set
+    #[arg(long, requires = "export_trace", value_delimiter = ',')]
+    pub peers: Option<Vec<usize>>,
+
+    /// Where the exported trace is written (prints to console if not specified) - can only be used if 'export_trace' is set
+    #[arg(long, requires = "export_trace")]
+    pub output: Option<String>,
+
+    /// Pauses before the first event of each run and reads debugger commands from the terminal, runs one simulation at a time
+    #[arg(long)]
+    pub debug: bool,
+
+    /// How many of the last processed events are reported when an invariant of a scenario is violated
+    #[arg(long, default_value = "32")]
+    pub invariant_history: usize,
+
+    /// Explores the orders in which concurrent messages can be delivered until an invariant is violated, and saves the smallest failing schedule
+    #[arg(long, value_enum, requires = "config")]
+    pub explore: Option<ExploreStrategy>,
+
+    /// Alternative orders are only explored at the first choice points of a run - can only be used if 'explore' is set
+    #[arg(long, requires = "explore", default_value = "20")]
+    pub explore_depth: usize,
+
+    /// Most reordered deliveries of a delay schedule, or the depth of pct - can only be used if 'explore' is set
+    #[arg(long, requires = "explore", default_value = "2")]
+    pub explore_bound: usize,
+
+    /// Most simulations run while exploring - can only be used if 'explore' is set
+    #[arg(long, requires = "explore", default_value = "1000")]
+    pub explore_runs: usize,
+
+    /// Messages due at most this long after the next event are delivered in any order - can only be used if 'explore' is set
+    #[arg(long, requires = "explore", default_value = "0.0")]
+    pub explore_window: SimDuration,
+
+    /// Runs a repetition with the delivery order of a schedule saved while exploring
+    #[arg(long, requires = "config")]
+    pub replay_schedule: Option<String>,
+
+    /// Replays each repetition that violates an invariant with fewer of the faults it injected, and saves the smallest set that still fails as a configuration
+    #[arg(long, requires = "config")]
+    pub shrink: bool,
+
+    /// Most simulations run to shrink the faults of a repetition - can only be used if 'shrink' is set
+    #[arg(long, requires = "shrink", default_value = "500")]
+    pub shrink_runs: usize,
+
+    /// Unit of the times printed in the logs of the simulations, in seconds without a unit if not set
+    #[arg(long, value_enum)]
+    pub time_unit: Option<TimeUnit>,
+
+    /// The queue of the pending events, events are processed in the same order with either
+    #[arg(long, value_enum, default_value_t)]
+    pub scheduler: SchedulerKind,
+
+    /// Shows the simulated time, events per second, queued events and ETA of each run while it runs. The summary of its statistics is always printed and written to the metrics
+    #[arg(long)]
+    pub progress: bool,
+
     /// Where the configuration and logs should be stored (prints to console if not specified)
     #[arg(short, long, requires = "scenario")]
     pub dir: Option<String>,
 
+    /// How many repetitions and experiments run at the same time, 0 uses every available core
+    #[arg(short, long, default_value = "1")]
+    pub jobs: usize,
+
     /// Overwrites the provided a configuration file
     #[arg(short, long, requires = "config")]
     pub write_config: bool,
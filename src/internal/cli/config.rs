@@ -1,6 +1,14 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use crate::internal::core::{distributions::DistributionWrapper, experiment::Jitter};
+use crate::internal::core::{
+    distributions::DistributionWrapper,
+    experiment::Jitter,
+    log::LogFormat,
+    sink::MetricsFormat,
+    time::{SimDuration, TimeBase, TimeUnit},
+    trace::TraceFormat,
+};
 
 use super::{
     super::{
@@ -11,14 +19,38 @@ use super::{
         simulator::Simulator,
     },
     Args,
+    export::{self, ExportFilter},
+    sweep,
 };
 
-use std::{error::Error, fs, path::Path};
+use std::{error::Error, fs, mem, path::Path};
+
+/// How the results of the simulations are written.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub metrics_format: MetricsFormat,
+    /// Simulation time between samples of the metrics registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_interval: Option<SimDuration>,
+    /// Format of the trace of processed events, not recorded if unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceFormat>,
+    /// Format of the log files
+    #[serde(skip_serializing_if = "LogFormat::is_text")]
+    pub log_format: LogFormat,
+    /// The logs of each peer are written to a file of their own, next to the log file
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub per_peer_logs: bool,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SimulationConfig {
     pub experiments: Vec<Experiment>,
 
+    #[serde(default)]
+    pub output: OutputConfig,
+
     #[serde(skip)]
     pub dir: Option<String>,
 
@@ -27,6 +59,58 @@ pub struct SimulationConfig {
     pub should_write_config: bool,
 }
 
+impl SimulationConfig {
+    /// A configuration that runs `experiments` without writing their results.
+    #[must_use]
+    pub fn from_experiments(experiments: Vec<Experiment>) -> Self {
+        Self {
+            experiments,
+            output: OutputConfig::default(),
+            dir: None,
+            should_write_config: false,
+        }
+    }
+
+    /// Reads a configuration file, results are written to the directory that contains it
+    /// and the sweeps of its experiments are expanded.
+    pub fn read(config_file: &str) -> Result<Self, Box<dyn Error>> {
+        let mut config: SimulationConfig = serde_yaml::from_str(&fs::read_to_string(config_file)?)?;
+
+        let canonical_path = Path::new(config_file)
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve config file path: {e}"))?;
+        let parent_dir = canonical_path
+            .parent()
+            .ok_or("Config file has no parent directory")?;
+        let config_dir = parent_dir.to_string_lossy().to_string();
+        log::global_internal(format!("Reading configuration file from '{config_dir}'"));
+        config.dir = Some(config_dir);
+
+        config.expand_sweeps()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Replaces the experiments that have a sweep with the experiments it generates.
+    pub fn expand_sweeps(&mut self) -> Result<(), String> {
+        self.experiments = sweep::expand_sweeps(mem::take(&mut self.experiments))?;
+        Ok(())
+    }
+
+    /// Checks the settings of the experiments that are not checked when they are read.
+    pub fn validate(&self) -> Result<(), String> {
+        for experiment in &self.experiments {
+            TimeBase::from_resolution(experiment.time_resolution).map_err(|e| {
+                format!(
+                    "Invalid time resolution in experiment '{}': {e}",
+                    experiment.name
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
 /*
  * Retrieves configuration from command line arguments.
  * If no config is created, it returns None meaning
@@ -43,6 +127,9 @@ pub fn get_config_from_args(
         for sim in &simulator.scenario_registry.list() {
             let (name, description) = sim;
             println!("> {name} - {description}");
+            for (option, default) in simulator.scenario_registry.options(name) {
+                println!("    {option} (default: {default})");
+            }
         }
         println!();
         return Ok(None);
@@ -60,34 +147,40 @@ pub fn get_config_from_args(
         }
         println!();
         return Ok(None);
+    } else if let Some(trace_file) = &args.export_trace {
+        let filter = ExportFilter {
+            from: args.from,
+            until: args.until,
+            peers: args.peers.clone(),
+        };
+        export::export_trace(
+            trace_file,
+            args.export_format,
+            &filter,
+            args.output.as_deref(),
+        )?;
+        return Ok(None);
     }
 
     if let Some(config_file) = args.config {
-        let path = Path::new(&config_file);
-
-        let mut config: SimulationConfig =
-            serde_yaml::from_str(&fs::read_to_string(&config_file)?)?;
-
-        match path.canonicalize() {
-            Ok(canonical_path) => {
-                if let Some(parent_dir) = canonical_path.parent() {
-                    let config_dir = parent_dir.to_string_lossy().to_string();
-                    log::global_internal(format!("Reading configuration file from '{config_dir}'"));
-                    config.dir = Some(config_dir);
-                } else {
-                    log::global_error("Config file has no parent directory. Aborting execution.");
-                    return Ok(None);
-                }
-            }
-            Err(e) => {
-                log::global_error(format!(
-                    "Failed to resolve config file path: {e}. Aborting execution."
-                ));
-                return Ok(None);
-            }
-        }
+        let mut config = SimulationConfig::read(&config_file)?;
 
         config.should_write_config = args.write_config;
+        if let Some(format) = args.metrics_format {
+            config.output.metrics_format = format;
+        }
+        if args.metrics_interval.is_some() {
+            config.output.metrics_interval = args.metrics_interval;
+        }
+        if args.trace.is_some() {
+            config.output.trace = args.trace;
+        }
+        if let Some(format) = args.log_format {
+            config.output.log_format = format;
+        }
+        if args.per_peer_logs {
+            config.output.per_peer_logs = true;
+        }
 
         return Ok(Some(config));
     } else if let Some(scenario_name) = args.scenario {
@@ -99,7 +192,8 @@ pub fn get_config_from_args(
         let jitter = if args.use_jitter {
             Some(Jitter {
                 distribution: DistributionWrapper::Weibull(1.064, 2.872),
-                multiplier: 0.001,
+                multiplier: 1.0,
+                unit: TimeUnit::Ms,
             })
         } else {
             None
@@ -116,12 +210,24 @@ pub fn get_config_from_args(
                 duplicate_rate: args.duplicate_rate,
                 jitter,
                 deadline: args.deadline,
+                time_resolution: args.time_resolution,
+                faults: None,
                 extra_args: None,
                 repetitions: Some(args.repetitions),
+                sweep: None,
+                seeds: IndexMap::new(),
             }],
+            output: OutputConfig {
+                metrics_format: args.metrics_format.unwrap_or_default(),
+                metrics_interval: args.metrics_interval,
+                trace: args.trace,
+                log_format: args.log_format.unwrap_or_default(),
+                per_peer_logs: args.per_peer_logs,
+            },
             dir: args.dir,
             should_write_config: true,
         };
+        config.validate()?;
 
         return Ok(Some(config));
     }
@@ -0,0 +1,393 @@
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+
+use crate::internal::core::{
+    log,
+    trace::{TraceKind, TraceReader, TraceRecord},
+};
+
+use super::utils::write_file_with_dirs;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Mermaid sequence diagram
+    #[default]
+    Mermaid,
+    /// PlantUML sequence diagram
+    Plantuml,
+    /// Chrome Trace Event JSON, can be opened in Perfetto or chrome://tracing
+    Chrome,
+}
+
+/// Which part of a trace is exported. Messages are kept when they were sent
+/// inside the time window and both their sender and receiver are in `peers`.
+#[derive(Debug, Default, Clone)]
+pub struct ExportFilter {
+    pub from: Option<f64>,
+    pub until: Option<f64>,
+    pub peers: Option<Vec<usize>>,
+}
+
+impl ExportFilter {
+    fn contains_time(&self, time: f64) -> bool {
+        self.from.is_none_or(|from| time >= from) && self.until.is_none_or(|until| time <= until)
+    }
+
+    fn contains_peer(&self, peer: usize) -> bool {
+        self.peers
+            .as_ref()
+            .is_none_or(|peers| peers.contains(&peer))
+    }
+
+    fn keeps(&self, record: &TraceRecord) -> bool {
+        match record.kind {
+            TraceKind::Timer => self.contains_time(record.timestamp),
+            TraceKind::MessageDelivery | TraceKind::MessageDrop => {
+                self.contains_time(record.sent_at.unwrap_or(record.timestamp))
+                    && record.sender.is_some_and(|peer| self.contains_peer(peer))
+                    && record.receiver.is_some_and(|peer| self.contains_peer(peer))
+            }
+        }
+    }
+}
+
+/// Reads a trace and writes its message flows in `format`, to `output` or to the console.
+pub fn export_trace(
+    trace_file: &str,
+    format: ExportFormat,
+    filter: &ExportFilter,
+    output: Option<&str>,
+) -> Result<(), String> {
+    let reader = TraceReader::open(trace_file)
+        .map_err(|e| format!("Failed to open trace file {trace_file}: {e}"))?;
+
+    let mut records = Vec::new();
+    for record in reader {
+        let record = record.map_err(|e| format!("Failed to read trace file {trace_file}: {e}"))?;
+        if filter.keeps(&record) {
+            records.push(record);
+        }
+    }
+
+    let contents = match format {
+        ExportFormat::Mermaid => to_mermaid(&records),
+        ExportFormat::Plantuml => to_plantuml(&records),
+        ExportFormat::Chrome => {
+            serde_json::to_string_pretty(&to_chrome_trace(&records)).map_err(|e| e.to_string())?
+        }
+    };
+
+    match output {
+        Some(path) => {
+            write_file_with_dirs(path, contents).map_err(|e| e.to_string())?;
+            log::global_internal(format!("Trace exported to {path}"));
+        }
+        None => println!("{contents}"),
+    }
+    Ok(())
+}
+
+/// The peers that exchange messages, in ascending order.
+fn participants(records: &[TraceRecord]) -> BTreeSet<usize> {
+    records
+        .iter()
+        .filter(|record| record.kind != TraceKind::Timer)
+        .flat_map(|record| [record.sender, record.receiver])
+        .flatten()
+        .collect()
+}
+
+fn message_label(record: &TraceRecord) -> String {
+    let sent_at = record.sent_at.unwrap_or(record.timestamp);
+    let outcome = match record.kind {
+        TraceKind::MessageDrop => format!(
+            "dropped at {:.6} ({})",
+            record.timestamp,
+            record.reason.as_deref().unwrap_or_default()
+        ),
+        _ => format!("delivered at {:.6}", record.timestamp),
+    };
+    format!(
+        "{} {}B, sent at {sent_at:.6}, {outcome}",
+        record.message_type.as_deref().unwrap_or_default(),
+        record.message_size.unwrap_or_default(),
+    )
+}
+
+fn to_mermaid(records: &[TraceRecord]) -> String {
+    let mut diagram = String::from("sequenceDiagram\n");
+    for peer in participants(records) {
+        diagram.push_str(&format!("    participant P{peer} as peer {peer}\n"));
+    }
+
+    for record in records {
+        let arrow = match record.kind {
+            TraceKind::Timer => continue,
+            TraceKind::MessageDelivery => "->>",
+            TraceKind::MessageDrop => "-x",
+        };
+        if let (Some(sender), Some(receiver)) = (record.sender, record.receiver) {
+            diagram.push_str(&format!(
+                "    P{sender}{arrow}P{receiver}: {}\n",
+                message_label(record)
+            ));
+        }
+    }
+    diagram
+}
+
+fn to_plantuml(records: &[TraceRecord]) -> String {
+    let mut diagram = String::from("@startuml\n");
+    for peer in participants(records) {
+        diagram.push_str(&format!("participant \"peer {peer}\" as P{peer}\n"));
+    }
+
+    for record in records {
+        let arrow = match record.kind {
+            TraceKind::Timer => continue,
+            TraceKind::MessageDelivery => "->",
+            TraceKind::MessageDrop => "->x",
+        };
+        if let (Some(sender), Some(receiver)) = (record.sender, record.receiver) {
+            diagram.push_str(&format!(
+                "P{sender} {arrow} P{receiver} : {}\n",
+                message_label(record)
+            ));
+        }
+    }
+    diagram.push_str("@enduml\n");
+    diagram
+}
+
+// Chrome traces use microseconds
+fn to_micros(seconds: f64) -> f64 {
+    seconds * 1.0e6
+}
+
+/// Each peer is a track of the `peers` process, linked by a flow from the send to the
+/// delivery of every message. Drops are instant events and timers are on their own track.
+fn to_chrome_trace(records: &[TraceRecord]) -> Value {
+    const PEERS_PID: u64 = 0;
+    const TIMERS_PID: u64 = 1;
+
+    let mut events = vec![
+        json!({"ph": "M", "name": "process_name", "pid": PEERS_PID, "args": {"name": "peers"}}),
+        json!({"ph": "M", "name": "process_name", "pid": TIMERS_PID, "args": {"name": "timers"}}),
+    ];
+    for peer in participants(records) {
+        events.push(json!({
+            "ph": "M",
+            "name": "thread_name",
+            "pid": PEERS_PID,
+            "tid": peer,
+            "args": {"name": format!("peer {peer}")}
+        }));
+    }
+
+    for record in records {
+        let message_type = record.message_type.as_deref().unwrap_or_default();
+        let args = json!({
+            "event": record.id,
+            "sender": record.sender,
+            "receiver": record.receiver,
+            "size": record.message_size,
+            "sent_at": record.sent_at,
+        });
+
+        match record.kind {
+            TraceKind::Timer => events.push(json!({
+                "ph": "i",
+                "s": "t",
+                "cat": "timer",
+                "name": record.timer_type.as_deref().unwrap_or_default(),
+                "ts": to_micros(record.timestamp),
+                "pid": TIMERS_PID,
+                "tid": 0,
+                "args": {"event": record.id},
+            })),
+            TraceKind::MessageDelivery => {
+                let sent_at = record.sent_at.unwrap_or(record.timestamp);
+                events.push(json!({
+                    "ph": "X",
+                    "cat": "message",
+                    "name": format!("send {message_type}"),
+                    "ts": to_micros(sent_at),
+                    "dur": 0,
+                    "pid": PEERS_PID,
+                    "tid": record.sender,
+                    "bind_id": record.id,
+                    "flow_out": true,
+                    "args": args,
+                }));
+                events.push(json!({
+                    "ph": "X",
+                    "cat": "message",
+                    "name": format!("receive {message_type}"),
+                    "ts": to_micros(record.timestamp),
+                    "dur": 0,
+                    "pid": PEERS_PID,
+                    "tid": record.receiver,
+                    "bind_id": record.id,
+                    "flow_in": true,
+                    "args": args,
+                }));
+            }
+            TraceKind::MessageDrop => {
+                let reason = record.reason.as_deref().unwrap_or_default();
+                // messages to dead peers reach them, the others are lost when sent
+                let peer = if reason == "dead" {
+                    record.receiver
+                } else {
+                    record.sender
+                };
+                events.push(json!({
+                    "ph": "i",
+                    "s": "t",
+                    "cat": "drop",
+                    "name": format!("drop {message_type} ({reason})"),
+                    "ts": to_micros(record.timestamp),
+                    "pid": PEERS_PID,
+                    "tid": peer,
+                    "args": args,
+                }));
+            }
+        }
+    }
+
+    json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    // a delivery from 0 to 1, a lost message from 1 to 2, a timer
+    // of peer 3 and a message from 0 to 2 which is dead by then
+    fn records() -> Vec<TraceRecord> {
+        let message =
+            |id, sender, receiver, sent_at, timestamp, reason: Option<&'static str>| TraceRecord {
+                id,
+                timestamp,
+                kind: if reason.is_some() {
+                    TraceKind::MessageDrop
+                } else {
+                    TraceKind::MessageDelivery
+                },
+                sender: Some(sender),
+                receiver: Some(receiver),
+                message_type: Some(Cow::Borrowed("Ping")),
+                message_size: Some(64),
+                timer_type: None,
+                sent_at: Some(sent_at),
+                reason: reason.map(Cow::Borrowed),
+            };
+
+        vec![
+            message(3, 0, 1, 0.1, 0.2, None),
+            message(4, 1, 2, 0.25, 0.3, Some("loss")),
+            TraceRecord {
+                id: 5,
+                timestamp: 0.4,
+                kind: TraceKind::Timer,
+                sender: None,
+                receiver: Some(3),
+                message_type: None,
+                message_size: None,
+                timer_type: Some(Cow::Borrowed("StartTimer")),
+                sent_at: None,
+                reason: None,
+            },
+            message(6, 0, 2, 0.45, 0.5, Some("dead")),
+        ]
+    }
+
+    fn kept_ids(filter: &ExportFilter) -> Vec<u64> {
+        records()
+            .iter()
+            .filter(|record| filter.keeps(record))
+            .map(|record| record.id)
+            .collect()
+    }
+
+    #[test]
+    fn messages_are_drawn_between_the_peers_that_exchange_them() {
+        assert_eq!(
+            to_mermaid(&records()),
+            "sequenceDiagram
+    participant P0 as peer 0
+    participant P1 as peer 1
+    participant P2 as peer 2
+    P0->>P1: Ping 64B, sent at 0.100000, delivered at 0.200000
+    P1-xP2: Ping 64B, sent at 0.250000, dropped at 0.300000 (loss)
+    P0-xP2: Ping 64B, sent at 0.450000, dropped at 0.500000 (dead)
+"
+        );
+        assert_eq!(
+            to_plantuml(&records()),
+            "@startuml
+participant \"peer 0\" as P0
+participant \"peer 1\" as P1
+participant \"peer 2\" as P2
+P0 -> P1 : Ping 64B, sent at 0.100000, delivered at 0.200000
+P1 ->x P2 : Ping 64B, sent at 0.250000, dropped at 0.300000 (loss)
+P0 ->x P2 : Ping 64B, sent at 0.450000, dropped at 0.500000 (dead)
+@enduml
+"
+        );
+    }
+
+    #[test]
+    fn chrome_traces_link_sends_to_deliveries() {
+        let trace = to_chrome_trace(&records());
+        let events = trace["traceEvents"].as_array().unwrap();
+        let named = |name: &str| -> Vec<&Value> {
+            events
+                .iter()
+                .filter(|event| event["name"] == name)
+                .collect()
+        };
+
+        // the two processes and a track for each of the three peers
+        assert_eq!(named("process_name").len(), 2);
+        assert_eq!(named("thread_name").len(), 3);
+
+        let (send, receive) = (named("send Ping"), named("receive Ping"));
+        assert_eq!((send.len(), receive.len()), (1, 1));
+        assert_eq!(send[0]["ts"], json!(to_micros(0.1)));
+        assert_eq!(send[0]["tid"], 0);
+        assert_eq!(receive[0]["ts"], json!(to_micros(0.2)));
+        assert_eq!(receive[0]["tid"], 1);
+        assert_eq!(send[0]["bind_id"], receive[0]["bind_id"]);
+
+        // lost messages are on the track of their sender, the others of their receiver
+        assert_eq!(named("drop Ping (loss)")[0]["tid"], 1);
+        assert_eq!(named("drop Ping (dead)")[0]["tid"], 2);
+        assert_eq!(named("StartTimer")[0]["pid"], 1);
+    }
+
+    #[test]
+    fn filters_keep_messages_sent_in_the_window_between_the_peers() {
+        assert_eq!(kept_ids(&ExportFilter::default()), vec![3, 4, 5, 6]);
+
+        // messages are kept by when they were sent, timers by when they fire
+        let window = ExportFilter {
+            from: Some(0.2),
+            until: Some(0.45),
+            peers: None,
+        };
+        assert_eq!(kept_ids(&window), vec![4, 5, 6]);
+
+        // both ends of a message must be kept, timers do not belong to the peers
+        let peers = ExportFilter {
+            peers: Some(vec![0, 1]),
+            ..ExportFilter::default()
+        };
+        assert_eq!(kept_ids(&peers), vec![3, 5]);
+    }
+}
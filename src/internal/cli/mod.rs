@@ -1,7 +1,8 @@
 pub mod args;
 mod config;
+mod export;
+mod sweep;
 pub mod utils;
 
 pub use args::Args;
-pub use config::{SimulationConfig, get_config_from_args};
-
+pub use config::{OutputConfig, SimulationConfig, get_config_from_args};
@@ -0,0 +1,232 @@
+use indexmap::{IndexMap, IndexSet};
+use serde_yaml::{Mapping, Value};
+
+use crate::internal::core::{
+    experiment::{Experiment, Sweep, SweepMode},
+    log,
+};
+
+/// Replaces every experiment that has a `sweep` block with the
+/// experiments it expands into, keeping the order of the configuration.
+/// Generated names must be unique, as the results of experiments with the same
+/// name would overwrite each other.
+pub fn expand_sweeps(experiments: Vec<Experiment>) -> Result<Vec<Experiment>, String> {
+    let mut expanded = Vec::with_capacity(experiments.len());
+    // the name of each generated experiment and the experiment it comes from
+    let mut generated_names = IndexMap::new();
+
+    for mut experiment in experiments {
+        let Some(sweep) = experiment.sweep.take() else {
+            expanded.push(experiment);
+            continue;
+        };
+
+        let combinations = combinations(&sweep)
+            .map_err(|err| format!("Invalid sweep in experiment '{}': {err}", experiment.name))?;
+
+        let n_combinations = combinations.len();
+        let base = serde_yaml::to_value(&experiment).map_err(|err| err.to_string())?;
+
+        for combination in combinations {
+            let mut value = base.clone();
+            let mut suffix = Vec::with_capacity(combination.len());
+
+            for (path, field_value) in combination {
+                set_field(&mut value, &path, field_value.clone()).map_err(|err| {
+                    format!("Invalid sweep in experiment '{}': {err}", experiment.name)
+                })?;
+
+                let field_name = path.rsplit('.').next().unwrap_or(&path);
+                suffix.push(format!("{field_name}-{}", value_to_name(&field_value)));
+            }
+
+            let mut generated: Experiment = serde_yaml::from_value(value).map_err(|err| {
+                format!(
+                    "Sweep of experiment '{}' generated an invalid experiment: {err}",
+                    experiment.name
+                )
+            })?;
+            generated.name = format!("{}_{}", experiment.name, suffix.join("_"));
+
+            generated_names.insert(generated.name.clone(), experiment.name.clone());
+            expanded.push(generated);
+        }
+
+        log::global_internal(format!(
+            "Experiment '{}' expanded into {n_combinations} experiments",
+            experiment.name,
+        ));
+    }
+
+    let mut names = IndexSet::new();
+    for experiment in &expanded {
+        if !names.insert(experiment.name.as_str())
+            && let Some(source) = generated_names.get(&experiment.name)
+        {
+            return Err(format!(
+                "Sweep of experiment '{source}' generated the name '{}' more than once, \
+                 rename the experiments or sweep over values with distinct names",
+                experiment.name
+            ));
+        }
+    }
+
+    Ok(expanded)
+}
+
+// optional fields of an experiment that are left out of it when they are not set
+const OPTIONAL_FIELDS: [&str; 2] = ["time_resolution", "faults"];
+
+/// Lists the (path, value) pairs of every experiment the sweep generates.
+fn combinations(sweep: &Sweep) -> Result<Vec<Vec<(String, Value)>>, String> {
+    if sweep.params.is_empty() {
+        return Err("no params were provided".to_string());
+    }
+    if let Some((path, _)) = sweep.params.iter().find(|(_, values)| values.is_empty()) {
+        return Err(format!("'{path}' has no values"));
+    }
+
+    match sweep.mode {
+        SweepMode::Product => {
+            let mut combinations: Vec<Vec<(String, Value)>> = vec![Vec::new()];
+
+            for (path, values) in &sweep.params {
+                combinations = combinations
+                    .into_iter()
+                    .flat_map(|combination| {
+                        values.iter().map(move |value| {
+                            let mut combination = combination.clone();
+                            combination.push((path.clone(), value.clone()));
+                            combination
+                        })
+                    })
+                    .collect();
+            }
+
+            Ok(combinations)
+        }
+        SweepMode::Zip => {
+            let len = sweep.params[0].len();
+            if let Some((path, values)) = sweep.params.iter().find(|(_, v)| v.len() != len) {
+                return Err(format!(
+                    "zipped lists must have the same length, '{path}' has {} values instead of {len}",
+                    values.len()
+                ));
+            }
+
+            Ok((0..len)
+                .map(|i| {
+                    sweep
+                        .params
+                        .iter()
+                        .map(|(path, values)| (path.clone(), values[i].clone()))
+                        .collect()
+                })
+                .collect())
+        }
+    }
+}
+
+/// Sets the field found by following a dotted path. Only fields that already
+/// exist in the experiment or are optional can be set, except the ones inside
+/// `extra_args` which are validated by the scenario.
+fn set_field(root: &mut Value, path: &str, field_value: Value) -> Result<(), String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let mut current = root;
+
+    for (i, segment) in segments.iter().enumerate() {
+        let free_form = segments[..i].first() == Some(&"extra_args");
+        let optional = i == 0 && OPTIONAL_FIELDS.contains(segment);
+
+        if current.is_null() && free_form {
+            *current = Value::Mapping(Mapping::new());
+        }
+
+        let Value::Mapping(mapping) = current else {
+            return Err(format!(
+                "'{}' is not a mapping, cannot set '{path}'",
+                segments[..i].join(".")
+            ));
+        };
+
+        let key = Value::String((*segment).to_string());
+        if !mapping.contains_key(&key) {
+            if !free_form && !optional {
+                return Err(format!("unknown field '{path}'"));
+            }
+            mapping.insert(key.clone(), Value::Null);
+        }
+
+        current = mapping.get_mut(&key).expect("key was inserted");
+    }
+
+    *current = field_value;
+    Ok(())
+}
+
+fn value_to_name(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::core::time::SimDuration;
+
+    fn experiment(name: &str, fields: &str) -> Experiment {
+        serde_yaml::from_str(&format!(
+            "{{name: {name}, scenario: simple_message, topology: {{n_peers: 4, name: ring}}, {fields}}}"
+        ))
+        .expect("experiment should be valid")
+    }
+
+    #[test]
+    fn sweeps_are_named_after_their_values() {
+        let expanded = expand_sweeps(vec![experiment(
+            "exp",
+            "sweep: {params: {topology.n_peers: [2, 8], extra_args.msg_size: [1]}}",
+        )])
+        .unwrap();
+
+        let names: Vec<&str> = expanded.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["exp_n_peers-2_msg_size-1", "exp_n_peers-8_msg_size-1"]
+        );
+        assert_eq!(expanded[1].topology.n_peers, 8);
+    }
+
+    #[test]
+    fn generated_names_must_be_unique() {
+        let same_values = experiment("exp", "sweep: {params: {extra_args.msg_size: [1, '1']}}");
+        assert!(expand_sweeps(vec![same_values]).is_err());
+
+        let same_as_another = vec![
+            experiment("exp_msg_size-1", "seed: 1"),
+            experiment("exp", "sweep: {params: {extra_args.msg_size: [1]}}"),
+        ];
+        assert!(expand_sweeps(same_as_another).is_err());
+    }
+
+    #[test]
+    fn optional_fields_can_be_swept_when_unset() {
+        let expanded = expand_sweeps(vec![experiment(
+            "exp",
+            "sweep: {params: {time_resolution: [1ns, 1us]}}",
+        )])
+        .unwrap();
+        assert_eq!(
+            expanded[1].time_resolution,
+            Some(SimDuration::from_secs(1.0e-6))
+        );
+
+        let unknown = experiment("exp", "sweep: {params: {resolution: [1ns]}}");
+        assert!(expand_sweeps(vec![unknown]).is_err());
+    }
+}
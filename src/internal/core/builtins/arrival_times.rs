@@ -1,11 +1,10 @@
-use ordered_float::OrderedFloat;
-
 use crate::internal::core::{
     Context, macros::define_custom_arrival_time_callback, options::ArrivalTimeCallback,
+    time::SimDuration,
 };
 
 define_custom_arrival_time_callback!(ConstantArrivalTime, "constant", |_ctx, _from, _to| {
-    Some(OrderedFloat(1.0))
+    Some(SimDuration::from_secs(1.0))
 });
 
 define_custom_arrival_time_callback!(DistanceBasedArrivalTime, "distance", |ctx, from, to| {
@@ -15,12 +14,12 @@ define_custom_arrival_time_callback!(DistanceBasedArrivalTime, "distance", |ctx,
 // optical fiber latency per kilometer in seconds
 const OPTICAL_FIBER: f64 = 0.350e-6;
 
-fn distance(ctx: &mut Context, from: usize, to: usize) -> Option<OrderedFloat<f64>> {
+fn distance(ctx: &mut Context, from: usize, to: usize) -> Option<SimDuration> {
     let (from_peer, to_peer) = (ctx.peers.get(from)?, ctx.peers.get(to)?);
 
     let dist = distance_between_points(from_peer.get_peer().position, to_peer.get_peer().position);
 
-    Some(OrderedFloat(dist * OPTICAL_FIBER))
+    Some(SimDuration::from_secs(dist * OPTICAL_FIBER))
 }
 
 // In kilometers
@@ -39,6 +39,10 @@ fn twowaycustom_topology(
 }
 
 fn full_topology(ctx: &mut Context, n_peers: usize, _custom_list: Option<Vec<ConnectionInfo>>) {
+    // computed instead of stored when nothing is linked yet
+    if ctx.links.set_full(n_peers) {
+        return;
+    }
     for i in 0..n_peers {
         for j in i + 1..n_peers {
             engine::add_twoway_link(ctx, i, j, None);
@@ -57,7 +61,10 @@ fn ring_topology(ctx: &mut Context, n_peers: usize, _custom_list: Option<Vec<Con
     for i in 1..n_peers {
         engine::add_twoway_link(ctx, i - 1, i, None);
     }
-    engine::add_twoway_link(ctx, n_peers - 1, 0, None);
+    // two peers are already linked, fewer have nothing to close
+    if n_peers > 2 {
+        engine::add_twoway_link(ctx, n_peers - 1, 0, None);
+    }
 }
 
 define_custom_topology!(OneWayCustomTopology, "onewaycustom", onewaycustomtopology);
@@ -65,3 +72,71 @@ define_custom_topology!(TwoWayCustomTopology, "twowaycustom", twowaycustom_topol
 define_custom_topology!(FullTopology, "full", full_topology);
 define_custom_topology!(StarTopology, "star", star_topology);
 define_custom_topology!(RingTopology, "ring", ring_topology);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::core::{experiment::LinkKind, time::SimDuration};
+    use crate::testing::TestSimulation;
+
+    fn connected<T: Topology>(n_peers: usize) -> Vec<Vec<usize>> {
+        let mut sim = TestSimulation::new(0);
+        sim.add_test_peers(n_peers).connect::<T>();
+        neighbours(&sim.ctx)
+    }
+
+    fn neighbours(ctx: &Context) -> Vec<Vec<usize>> {
+        (0..ctx.links.len())
+            .map(|from| {
+                let mut ids: Vec<usize> = engine::neighbors(ctx, from).collect();
+                ids.sort_unstable();
+                ids
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_links_every_pair() {
+        assert_eq!(
+            connected::<FullTopology>(4),
+            vec![vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]]
+        );
+    }
+
+    #[test]
+    fn star_links_peers_to_the_first() {
+        assert_eq!(
+            connected::<StarTopology>(4),
+            vec![vec![1, 2, 3], vec![0], vec![0], vec![0]]
+        );
+    }
+
+    #[test]
+    fn ring_links_each_peer_to_two_neighbours() {
+        assert_eq!(
+            connected::<RingTopology>(4),
+            vec![vec![1, 3], vec![0, 2], vec![1, 3], vec![0, 2]]
+        );
+    }
+
+    #[test]
+    fn small_rings() {
+        assert!(connected::<RingTopology>(0).is_empty());
+        assert_eq!(connected::<RingTopology>(1), vec![Vec::<usize>::new()]);
+        assert_eq!(connected::<RingTopology>(2), vec![vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn custom_links_keep_their_info() {
+        let latency = Some(LinkKind::Latency(SimDuration::from_secs(2.0)));
+        let mut sim = TestSimulation::new(0);
+        sim.add_test_peers(3);
+
+        onewaycustomtopology(&mut sim.ctx, 3, Some(vec![(0, 1, latency)]));
+        assert_eq!(neighbours(&sim.ctx), vec![vec![1], vec![], vec![]]);
+        assert_eq!(sim.ctx.links.get(0, 1), Some(latency));
+
+        twowaycustom_topology(&mut sim.ctx, 3, Some(vec![(1, 2, None)]));
+        assert_eq!(neighbours(&sim.ctx), vec![vec![1], vec![2], vec![1]]);
+    }
+}
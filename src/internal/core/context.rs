@@ -1,37 +1,74 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
-
 use indexmap::IndexMap;
-use ordered_float::OrderedFloat;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use rand_distr::num_traits::Zero;
 
 use super::{
-    builtins, distributions,
+    builtins,
+    debugger::Debugger,
+    distributions,
     events::{Event, EventType},
-    experiment::{Jitter, LinkInfo},
+    experiment::Jitter,
+    faults::FaultPlan,
+    graph::Links,
+    interleaving::Interleaving,
+    invariants::{EventHistory, InvariantViolation},
     log,
     log::{Logger, LoggerLevel},
+    metrics::MetricsRegistry,
     options::ArrivalTimeCallback,
     peer::CustomPeer,
+    progress::Progress,
+    rng::RngStream,
+    scheduler::{HeapScheduler, Scheduler, SchedulerKind},
+    snapshot::{
+        CheckpointPlan, ContextSnapshot, Resume, RngState, SavedFile, SavedFiles, SnapshotRegistry,
+    },
+    stats::NetworkStats,
+    time::{SimDuration, SimTime, TimeBase},
+    trace::Tracer,
 };
 
-pub type MessageDelayCallback = fn(&mut Context, usize, usize) -> Option<OrderedFloat<f64>>;
+pub type MessageDelayCallback = fn(&mut Context, usize, usize) -> Option<SimDuration>;
 
 pub struct Context {
     event_id: u64,
-    event_q: BinaryHeap<Reverse<EventType>>,
-    pub clock: OrderedFloat<f64>,
+    current_event_id: u64,
+    scheduler: Box<dyn Scheduler>,
+    pub clock: SimTime,
     pub peers: Vec<Box<dyn CustomPeer>>,
-    // Rust's HashMap is non-deterministic.
-    pub links: Vec<IndexMap<usize, LinkInfo>>,
+    pub links: Links,
+    /// Shared stream, prefer `rng_for` so that draws do not affect other subsystems.
     pub rng: ChaCha8Rng,
+    rng_streams: IndexMap<RngStream, ChaCha8Rng>,
     pub seed: u64,
     pub message_delay_cb: MessageDelayCallback,
     pub logger: Logger,
+    /// Message counters and latencies collected by the engine.
+    pub network_stats: NetworkStats,
+    pub metrics: MetricsRegistry,
+    /// Records or verifies every processed event when set.
+    pub tracer: Option<Tracer>,
+    /// Pauses the simulation loop to take commands when set.
+    pub debugger: Option<Debugger>,
+    /// Identifies the run in snapshots and says when to save them.
+    pub checkpoints: Option<CheckpointPlan>,
+    /// Replaces the state of the context when the simulation loop starts.
+    pub resume: Option<Box<Resume>>,
+    /// The last events processed, kept while the simulation has invariants.
+    pub event_history: EventHistory,
+    /// The invariant that stopped the simulation.
+    pub violation: Option<InvariantViolation>,
+    /// Chooses the order of concurrent deliveries when set.
+    pub interleaving: Option<Box<Interleaving>>,
+    /// Injects planned faults and records the injected ones when set.
+    pub faults: Option<FaultPlan>,
+    /// Reports the statistics of the simulation loop when set, showing its progress if asked.
+    pub progress: Option<Progress>,
     drop_rate: f64,
     duplicate_rate: f64,
     jitter: Jitter,
+    time_base: TimeBase,
 }
 
 impl Context {
@@ -45,17 +82,31 @@ impl Context {
 
         Self {
             event_id: 0,
-            event_q: BinaryHeap::new(),
-            clock: OrderedFloat(0.0),
+            current_event_id: 0,
+            scheduler: Box::new(HeapScheduler::default()),
+            clock: SimTime::ZERO,
             peers: Vec::new(),
-            links: Vec::new(),
+            links: Links::default(),
             rng: ChaCha8Rng::seed_from_u64(seed),
+            rng_streams: IndexMap::new(),
             seed,
             message_delay_cb: builtins::arrival_times::ConstantArrivalTime::callback,
             logger: Logger::new(logger_level, quiet),
+            network_stats: NetworkStats::default(),
+            metrics: MetricsRegistry::default(),
+            tracer: None,
+            debugger: None,
+            checkpoints: None,
+            resume: None,
+            event_history: EventHistory::default(),
+            violation: None,
+            interleaving: None,
+            faults: None,
+            progress: None,
             drop_rate: 0.0,
             duplicate_rate: 0.0,
             jitter: Jitter::default(),
+            time_base: TimeBase::Continuous,
         }
     }
 
@@ -64,6 +115,17 @@ impl Context {
         self.seed
     }
 
+    /// Returns the random number generator of a stream,
+    /// creating it from the seed when first used.
+    pub fn rng_for(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        let seed = self.seed;
+        self.rng_streams.entry(stream).or_insert_with(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream.stream_id());
+            rng
+        })
+    }
+
     #[inline]
     #[must_use]
     pub fn get_drop_rate(&self) -> f64 {
@@ -83,10 +145,13 @@ impl Context {
 
     #[inline]
     pub fn push_event(&mut self, mut event: EventType) {
+        if self.time_base != TimeBase::Continuous {
+            event.set_timestamp(self.time_base.snap(event.timestamp()));
+        }
         event.set_id(self.event_id);
         self.event_id += 1;
 
-        self.event_q.push(Reverse(event));
+        self.scheduler.push(event);
     }
 
     #[inline]
@@ -106,19 +171,34 @@ impl Context {
         self.duplicate_rate = new_rate.clamp(0.0, 1.0);
     }
 
+    #[inline]
+    #[must_use]
+    pub fn time_base(&self) -> TimeBase {
+        self.time_base
+    }
+
+    /// Rounds the times of the events to whole ticks of `resolution`, the
+    /// clock then only takes times that are a whole number of ticks. Fails if a
+    /// second is not a whole number of ticks.
+    pub fn set_time_resolution(&mut self, resolution: SimDuration) -> Result<(), String> {
+        self.time_base = TimeBase::from_resolution(Some(resolution))?;
+        Ok(())
+    }
+
     /// Returns a jitter value by sampling from
     /// self.jitter.distribution and multiplying
-    /// it by self.jitter.multiplier.
+    /// it by self.jitter.multiplier, in self.jitter.unit.
     #[inline]
-    pub fn get_jitter_value(&mut self) -> OrderedFloat<f64> {
+    pub fn get_jitter_value(&mut self) -> SimDuration {
         if self.jitter.multiplier.is_zero() {
-            return OrderedFloat(0.0);
+            return SimDuration::ZERO;
         }
 
-        let from_sample =
-            distributions::get_value(self, self.jitter.distribution).unwrap_or(OrderedFloat(0.0));
+        let distribution = self.jitter.distribution;
+        let from_sample = distributions::get_value(self.rng_for(RngStream::Jitter), distribution)
+            .map_or(0.0, |sample| *sample);
 
-        from_sample * self.jitter.multiplier
+        SimDuration::from_unit(from_sample * self.jitter.multiplier, self.jitter.unit)
     }
 
     #[inline]
@@ -128,11 +208,206 @@ impl Context {
 
     #[inline]
     pub fn get_next_event(&mut self) -> Option<EventType> {
-        self.event_q.pop().map(|Reverse(ev)| ev)
+        let event = self.scheduler.pop();
+        if let Some(event) = &event {
+            self.current_event_id = event.id();
+        }
+        event
+    }
+
+    /// Takes an event out of the queue instead of the next one.
+    pub fn take_event(&mut self, id: u64) -> Option<EventType> {
+        let taken = self.scheduler.remove(id);
+        if taken.is_some() {
+            self.current_event_id = id;
+        }
+        taken
+    }
+
+    /// The event that `get_next_event` returns next.
+    #[inline]
+    #[must_use]
+    pub fn peek_next_event(&self) -> Option<&EventType> {
+        self.scheduler.peek()
+    }
+
+    /// Moves the pending events to a scheduler of another kind.
+    pub fn set_scheduler(&mut self, kind: SchedulerKind) {
+        let mut scheduler = kind.create();
+        for event in self.scheduler.drain() {
+            scheduler.push(event);
+        }
+        self.scheduler = scheduler;
+    }
+
+    /// Id of the last event taken from the queue, the one being processed.
+    #[inline]
+    #[must_use]
+    pub fn current_event_id(&self) -> u64 {
+        self.current_event_id
+    }
+
+    /// The events waiting in the queue, in no particular order.
+    pub fn pending_events(&self) -> impl Iterator<Item = &EventType> {
+        self.scheduler.iter()
     }
 
     #[inline]
     pub fn events_left(&self) -> usize {
-        self.event_q.len()
+        self.scheduler.len()
+    }
+
+    /// Saves the state of the context, `next` being an event that was taken
+    /// from the queue but not processed. Fails if a peer, message or timer
+    /// cannot be saved or the output files cannot be flushed.
+    pub fn snapshot(&mut self, next: Option<&EventType>) -> Result<ContextSnapshot, String> {
+        let Some(plan) = &self.checkpoints else {
+            return Err("the run has no checkpoint plan identifying it".to_string());
+        };
+        let (experiment, repetition) = (plan.experiment.clone(), plan.repetition);
+
+        let mut pending: Vec<&EventType> = self.pending_events().chain(next).collect();
+        pending.sort();
+        let events = pending
+            .into_iter()
+            .map(Event::save)
+            .collect::<Result<_, _>>()?;
+
+        let peers = self
+            .peers
+            .iter()
+            .enumerate()
+            .map(|(id, peer)| {
+                peer.snapshot()
+                    .ok_or_else(|| format!("Peer {id} does not support snapshots"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let saved_file = |file: Option<(&std::fs::File, &str)>| {
+            file.map(|(file, path)| SavedFile::of(path, file))
+                .transpose()
+                .map_err(|e| e.to_string())
+        };
+        // Parquet files are not saved, resumed runs only write what follows the snapshot
+        let files = SavedFiles {
+            metrics: saved_file(self.logger.metrics_file().map_err(|e| e.to_string())?)?,
+            log: saved_file(self.logger.log_file().map_err(|e| e.to_string())?)?,
+            registry: saved_file(self.metrics.file().map_err(|e| e.to_string())?)?,
+            trace: match self.tracer.as_mut() {
+                Some(Tracer::Writer(writer)) => {
+                    saved_file(Some(writer.file().map_err(|e| e.to_string())?))?
+                }
+                _ => None,
+            },
+        };
+
+        Ok(ContextSnapshot {
+            experiment,
+            repetition,
+            seed: self.seed,
+            clock: self.clock.as_secs(),
+            next_event_id: self.event_id,
+            current_event_id: self.current_event_id,
+            events,
+            rng: RngState::of(&self.rng),
+            rng_streams: self
+                .rng_streams
+                .iter()
+                .map(|(stream, rng)| (*stream, RngState::of(rng)))
+                .collect(),
+            links: self.links.clone(),
+            peers,
+            drop_rate: self.drop_rate,
+            duplicate_rate: self.duplicate_rate,
+            jitter: self.jitter,
+            faults: self.faults.clone(),
+            network_stats: self.network_stats.clone().into(),
+            metrics: self.metrics.save(),
+            files,
+        })
+    }
+
+    /// Replaces the state of the context with a snapshot. The context must have been
+    /// set up by the same scenario, its peers keep their callbacks and its output
+    /// files are started with what had been written when the snapshot was taken.
+    pub fn restore(
+        &mut self,
+        snapshot: ContextSnapshot,
+        registry: &SnapshotRegistry,
+    ) -> Result<(), String> {
+        if snapshot.peers.len() != self.peers.len() {
+            return Err(format!(
+                "the snapshot has {} peers but the scenario created {}",
+                snapshot.peers.len(),
+                self.peers.len()
+            ));
+        }
+        if snapshot.seed != self.seed {
+            log::global_warn(format!(
+                "The snapshot was taken with seed {} but the context uses {}",
+                snapshot.seed, self.seed
+            ));
+        }
+
+        // everything is restored before the context is changed
+        let mut peers = Vec::with_capacity(snapshot.peers.len());
+        for (saved, current) in snapshot.peers.into_iter().zip(&self.peers) {
+            let mut peer = registry.peer(saved)?;
+            peer.get_peer_mut().on_message_receive = current.get_peer().on_message_receive;
+            peers.push(peer);
+        }
+        let events = registry.events(snapshot.events)?;
+        let network_stats =
+            NetworkStats::restore(snapshot.network_stats, |name| registry.message_type(name))?;
+
+        let files = &snapshot.files;
+        let copy =
+            |saved: &Option<SavedFile>, file: Option<(&std::fs::File, &str)>| match (saved, file) {
+                (Some(saved), Some((file, _))) => saved.copy_into(file).map_err(|e| e.to_string()),
+                _ => Ok(()),
+            };
+        copy(
+            &files.log,
+            self.logger.log_file().map_err(|e| e.to_string())?,
+        )?;
+        copy(
+            &files.metrics,
+            self.logger.metrics_file().map_err(|e| e.to_string())?,
+        )?;
+        copy(
+            &files.registry,
+            self.metrics.file().map_err(|e| e.to_string())?,
+        )?;
+        if let Some(Tracer::Writer(writer)) = self.tracer.as_mut() {
+            copy(
+                &files.trace,
+                Some(writer.file().map_err(|e| e.to_string())?),
+            )?;
+        }
+
+        self.peers = peers;
+        self.scheduler.drain();
+        // the snapshot keeps times in seconds, they are back to ticks here
+        for mut event in events {
+            event.set_timestamp(self.time_base.snap(event.timestamp()));
+            self.scheduler.push(event);
+        }
+        self.event_id = snapshot.next_event_id;
+        self.current_event_id = snapshot.current_event_id;
+        self.clock = self.time_base.snap(SimTime::from_secs(snapshot.clock));
+        self.rng = snapshot.rng.restore(self.seed);
+        self.rng_streams = snapshot
+            .rng_streams
+            .iter()
+            .map(|(stream, state)| (*stream, state.restore(self.seed)))
+            .collect();
+        self.links = snapshot.links;
+        self.drop_rate = snapshot.drop_rate;
+        self.duplicate_rate = snapshot.duplicate_rate;
+        self.jitter = snapshot.jitter;
+        self.faults = snapshot.faults;
+        self.network_stats = network_stats;
+        self.metrics.restore(snapshot.metrics);
+        Ok(())
     }
 }
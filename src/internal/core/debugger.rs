@@ -0,0 +1,434 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use super::{
+    Context,
+    events::{Event, EventType},
+    snapshot,
+    time::SimTime,
+    trace::{TraceKind, TraceRecord},
+};
+
+const HELP: &str = "\
+Commands:
+  step [n], s [n]             process n events (default 1) and pause
+  continue, c                 run until a breakpoint is hit
+  until <time>, u <time>      run until the next event is after <time>
+  break peer <id>             pause on messages sent to or by a peer
+  break message <type>        pause on deliveries and drops of a message type
+  break timer <type>          pause on timers of a type
+  breakpoints, b              list the breakpoints
+  delete <n>                  remove breakpoint n, 'delete all' removes every one
+  queue [n], q [n]            show the next n pending events (default 10)
+  peers                       list the peers and whether they are alive
+  peer <id>, p <id>           show the state of a peer
+  kill <id>                   kill a peer
+  revive <id>                 revive a peer
+  save <file>                 save a snapshot that can be resumed with --resume
+  detach                      run to the end without pausing
+  quit                        stop the simulation
+  help, h                     show this message
+An empty line repeats 'step 1'.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    Peer(usize),
+    Message(String),
+    Timer(String),
+}
+
+impl Breakpoint {
+    // the receiver of a timer is the peer it acts for
+    fn matches(&self, record: &TraceRecord) -> bool {
+        match self {
+            Breakpoint::Peer(peer) => {
+                record.sender == Some(*peer) || record.receiver == Some(*peer)
+            }
+            Breakpoint::Message(name) => {
+                record.kind != TraceKind::Timer && record.message_type.as_deref() == Some(name)
+            }
+            Breakpoint::Timer(name) => {
+                record.kind == TraceKind::Timer && record.timer_type.as_deref() == Some(name)
+            }
+        }
+    }
+}
+
+/// A line typed at the debugger prompt.
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Step(u64),
+    Continue,
+    Until(f64),
+    Break(Breakpoint),
+    Breakpoints,
+    /// Removes a breakpoint, or all of them
+    Delete(Option<usize>),
+    Queue(usize),
+    Peers,
+    Peer(usize),
+    Kill(usize),
+    Revive(usize),
+    Save(String),
+    Detach,
+    Quit,
+    Help,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// An empty line is `step 1`.
+    fn from_str(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        Ok(match words.as_slice() {
+            [] => Command::Step(1),
+            ["step" | "s", rest @ ..] => Command::Step(parse_optional(rest, 1)?.max(1) as u64),
+            ["continue" | "c"] => Command::Continue,
+            ["until" | "u", time] => Command::Until(
+                time.parse()
+                    .map_err(|e| format!("Invalid time '{time}': {e}"))?,
+            ),
+            ["break", "peer", id] => Command::Break(Breakpoint::Peer(parse_peer(id)?)),
+            ["break", "message", name] => Command::Break(Breakpoint::Message(name.to_string())),
+            ["break", "timer", name] => Command::Break(Breakpoint::Timer(name.to_string())),
+            ["breakpoints" | "b"] => Command::Breakpoints,
+            ["delete", "all"] => Command::Delete(None),
+            ["delete", i] => {
+                Command::Delete(Some(i.parse().map_err(|_| format!("No breakpoint '{i}'"))?))
+            }
+            ["queue" | "q", rest @ ..] => Command::Queue(parse_optional(rest, 10)?),
+            ["peers"] => Command::Peers,
+            ["peer" | "p", id] => Command::Peer(parse_peer(id)?),
+            ["kill", id] => Command::Kill(parse_peer(id)?),
+            ["revive", id] => Command::Revive(parse_peer(id)?),
+            ["save", path] => Command::Save(path.to_string()),
+            ["detach"] => Command::Detach,
+            ["quit"] => Command::Quit,
+            ["help" | "h"] => Command::Help,
+            _ => {
+                return Err(format!(
+                    "Unknown command '{}', type 'help' for help",
+                    line.trim()
+                ));
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunMode {
+    /// Pauses once this many events were processed
+    Step(u64),
+    Until(f64),
+    Continue,
+    Detached,
+}
+
+/// What the simulation loop should do with the next event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    Process,
+    Stop,
+}
+
+/// Interactive debugger of the simulation loop, it pauses before an event
+/// is processed and reads commands from the terminal until told to resume.
+#[derive(Debug)]
+pub struct Debugger {
+    mode: RunMode,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debugger {
+    /// Creates a debugger that pauses before the first event.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mode: RunMode::Step(1),
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    /// Called by the engine before `event` is processed, pausing if a step
+    /// finished, the time limit was passed or a breakpoint matches the event.
+    pub fn before_event(&mut self, ctx: &mut Context, event: &EventType) -> DebugAction {
+        let record = event.trace_record(ctx);
+
+        match self.pause_reason(ctx.clock, &record) {
+            Some(reason) => {
+                println!("[DEBUG] {reason}");
+                self.repl(ctx, event)
+            }
+            None => DebugAction::Process,
+        }
+    }
+
+    /// Why the debugger pauses before the event of `record`, if it does.
+    fn pause_reason(&mut self, clock: SimTime, record: &TraceRecord) -> Option<String> {
+        let paused = match self.mode {
+            RunMode::Detached => return None,
+            RunMode::Step(n) if n > 1 => {
+                self.mode = RunMode::Step(n - 1);
+                false
+            }
+            RunMode::Step(_) => true,
+            RunMode::Until(time) => record.timestamp > time,
+            RunMode::Continue => false,
+        };
+
+        if paused {
+            return Some(format!("paused at {clock} before event {record}"));
+        }
+        let i = self.breakpoints.iter().position(|b| b.matches(record))?;
+        Some(format!(
+            "breakpoint {i} ({:?}) hit at {clock} before event {record}",
+            self.breakpoints[i]
+        ))
+    }
+
+    fn repl(&mut self, ctx: &mut Context, event: &EventType) -> DebugAction {
+        let stdin = io::stdin();
+        let mut line = String::new();
+
+        loop {
+            print!("(tdes) ");
+            let _ = io::stdout().flush();
+
+            line.clear();
+            match stdin.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    // without input the simulation can only run to the end
+                    println!("[DEBUG] no more input, detaching");
+                    self.mode = RunMode::Detached;
+                    return DebugAction::Process;
+                }
+                Ok(_) => {}
+            }
+
+            let command = match line.parse::<Command>() {
+                Ok(command) => command,
+                Err(e) => {
+                    println!("{e}");
+                    continue;
+                }
+            };
+            match command {
+                Command::Step(n) => {
+                    self.mode = RunMode::Step(n);
+                    return DebugAction::Process;
+                }
+                Command::Continue => {
+                    self.mode = RunMode::Continue;
+                    return DebugAction::Process;
+                }
+                Command::Until(time) => {
+                    self.mode = RunMode::Until(time);
+                    return DebugAction::Process;
+                }
+                Command::Break(breakpoint) => self.add_breakpoint(breakpoint),
+                Command::Breakpoints => {
+                    for (i, breakpoint) in self.breakpoints.iter().enumerate() {
+                        println!("{i}: {breakpoint:?}");
+                    }
+                }
+                Command::Delete(None) => self.breakpoints.clear(),
+                Command::Delete(Some(i)) if i < self.breakpoints.len() => {
+                    self.breakpoints.remove(i);
+                }
+                Command::Delete(Some(i)) => println!("No breakpoint '{i}'"),
+                Command::Queue(n) => Self::print_queue(ctx, event, n),
+                Command::Peers => {
+                    for peer in &ctx.peers {
+                        println!(
+                            "peer {}: {}",
+                            peer.get_id(),
+                            if peer.is_alive() { "alive" } else { "dead" }
+                        );
+                    }
+                }
+                Command::Peer(id) => match Self::peer_id(ctx, id) {
+                    Ok(id) => println!("{}", ctx.peers[id].inspect()),
+                    Err(e) => println!("{e}"),
+                },
+                Command::Kill(id) => match Self::peer_id(ctx, id) {
+                    Ok(id) => {
+                        ctx.peers[id].kill();
+                        println!("Peer {id} killed");
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                Command::Revive(id) => match Self::peer_id(ctx, id) {
+                    Ok(id) => {
+                        ctx.peers[id].revive();
+                        println!("Peer {id} revived");
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                Command::Save(path) => match snapshot::save_snapshot(ctx, &path, Some(event)) {
+                    Ok(()) => println!("Snapshot saved to {path}"),
+                    Err(e) => println!("Failed to save the snapshot: {e}"),
+                },
+                Command::Detach => {
+                    self.mode = RunMode::Detached;
+                    return DebugAction::Process;
+                }
+                Command::Quit => return DebugAction::Stop,
+                Command::Help => println!("{HELP}"),
+            }
+        }
+    }
+
+    /// Prints the current event followed by the next pending events in processing order.
+    fn print_queue(ctx: &Context, current: &EventType, n: usize) {
+        let mut pending: Vec<&EventType> = ctx.pending_events().collect();
+        pending.sort();
+
+        println!("{} events pending", pending.len());
+        println!("  > {}", current.trace_record(ctx));
+        for event in pending.iter().take(n) {
+            println!("    {}", event.trace_record(ctx));
+        }
+        if pending.len() > n {
+            println!("    ... {} more", pending.len() - n);
+        }
+    }
+
+    fn peer_id(ctx: &Context, id: usize) -> Result<usize, String> {
+        if id < ctx.peers.len() {
+            Ok(id)
+        } else {
+            Err(format!("Invalid peer ID: {id}"))
+        }
+    }
+}
+
+fn parse_peer(id: &str) -> Result<usize, String> {
+    id.parse().map_err(|e| format!("Invalid peer '{id}': {e}"))
+}
+
+fn parse_optional(words: &[&str], default: usize) -> Result<usize, String> {
+    match words {
+        [] => Ok(default),
+        [n] => n.parse().map_err(|e| format!("Invalid number '{n}': {e}")),
+        _ => Err("Too many arguments".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::core::events::{Timer, TimerEvent};
+
+    #[derive(Debug)]
+    struct Wake {
+        peer: usize,
+    }
+
+    impl Timer for Wake {
+        fn fire(&self, _ctx: &mut Context) {}
+
+        fn peer(&self) -> Option<usize> {
+            Some(self.peer)
+        }
+    }
+
+    fn timer_record(time: f64, peer: usize) -> TraceRecord {
+        let ctx = Context::new(Some(1), None, true);
+        TimerEvent::create(SimTime::from_secs(time), Box::new(Wake { peer })).trace_record(&ctx)
+    }
+
+    // whether the debugger pauses before each of the timers, at 0.1 second intervals
+    fn pauses(debugger: &mut Debugger, timers: usize) -> Vec<bool> {
+        (1..=timers)
+            .map(|i| {
+                let record = timer_record(i as f64 * 0.1, 0);
+                debugger.pause_reason(SimTime::ZERO, &record).is_some()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        let parse = |line: &str| line.parse::<Command>();
+
+        assert_eq!(parse(""), Ok(Command::Step(1)));
+        assert_eq!(parse("step 3"), Ok(Command::Step(3)));
+        assert_eq!(parse("s 0"), Ok(Command::Step(1)));
+        assert_eq!(parse("u 0.5"), Ok(Command::Until(0.5)));
+        assert_eq!(
+            parse("break peer 2"),
+            Ok(Command::Break(Breakpoint::Peer(2)))
+        );
+        assert_eq!(
+            parse("break timer Wake"),
+            Ok(Command::Break(Breakpoint::Timer("Wake".to_string())))
+        );
+        assert_eq!(parse("kill 1"), Ok(Command::Kill(1)));
+        assert_eq!(parse("revive 1"), Ok(Command::Revive(1)));
+        assert_eq!(parse("delete all"), Ok(Command::Delete(None)));
+
+        assert!(parse("step 1 2").is_err());
+        assert!(parse("until later").is_err());
+        assert!(parse("break peer me").is_err());
+        assert!(parse("kill").is_err());
+        assert!(parse("revive -1").is_err());
+        assert!(parse("jump 3").is_err());
+    }
+
+    #[test]
+    fn steps_and_until_pause_before_the_right_event() {
+        let mut debugger = Debugger::new();
+        debugger.mode = RunMode::Step(3);
+        assert_eq!(pauses(&mut debugger, 3), vec![false, false, true]);
+
+        debugger.mode = RunMode::Until(0.25);
+        assert_eq!(pauses(&mut debugger, 3), vec![false, false, true]);
+
+        debugger.mode = RunMode::Detached;
+        debugger.add_breakpoint(Breakpoint::Peer(0));
+        assert_eq!(pauses(&mut debugger, 1), vec![false]);
+    }
+
+    #[test]
+    fn breakpoints_match_the_timers_of_their_peer_and_type() {
+        let mut debugger = Debugger::new();
+        debugger.mode = RunMode::Continue;
+        debugger.add_breakpoint(Breakpoint::Peer(2));
+
+        assert_eq!(
+            debugger.pause_reason(SimTime::ZERO, &timer_record(0.1, 1)),
+            None
+        );
+        let reason = debugger.pause_reason(SimTime::ZERO, &timer_record(0.2, 2));
+        assert_eq!(
+            reason.as_deref(),
+            Some("breakpoint 0 (Peer(2)) hit at 0 before event #0 at 0.2 timer Wake of peer 2")
+        );
+
+        debugger.breakpoints = vec![Breakpoint::Timer("Wake".to_string())];
+        assert!(
+            debugger
+                .pause_reason(SimTime::ZERO, &timer_record(0.3, 1))
+                .is_some()
+        );
+        debugger.breakpoints = vec![Breakpoint::Message("Wake".to_string())];
+        assert!(
+            debugger
+                .pause_reason(SimTime::ZERO, &timer_record(0.4, 1))
+                .is_none()
+        );
+    }
+}
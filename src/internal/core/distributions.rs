@@ -1,9 +1,8 @@
 use ordered_float::OrderedFloat;
+use rand::Rng;
 use rand_distr::{Distribution, Exp, LogNormal, Normal, Uniform, Weibull};
 use serde::{Deserialize, Serialize};
 
-use crate::internal::core::Context;
-
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Copy)]
 pub enum DistributionWrapper {
     #[default]
@@ -16,27 +15,27 @@ pub enum DistributionWrapper {
     LogNormal(f64, f64), // mu, sigma
 }
 
-pub fn get_value(
-    ctx: &mut Context,
+pub fn get_value<R: Rng + ?Sized>(
+    rng: &mut R,
     distribution: DistributionWrapper,
 ) -> Option<OrderedFloat<f64>> {
     match distribution {
         DistributionWrapper::Nothing => Some(OrderedFloat(0.0)),
         DistributionWrapper::Constant(c) => Some(OrderedFloat(c)),
         DistributionWrapper::Exponential(lambda) => Exp::new(lambda)
-            .map(|exp| OrderedFloat(exp.sample(&mut ctx.rng)))
+            .map(|exp| OrderedFloat(exp.sample(rng)))
             .ok(),
         DistributionWrapper::Gaussian(mean, std_dev) => Normal::new(mean, std_dev)
-            .map(|exp| OrderedFloat(exp.sample(&mut ctx.rng)))
+            .map(|exp| OrderedFloat(exp.sample(rng)))
             .ok(),
         DistributionWrapper::Uniform(min, max) => Uniform::new(min, max)
-            .map(|exp| OrderedFloat(exp.sample(&mut ctx.rng)))
+            .map(|exp| OrderedFloat(exp.sample(rng)))
             .ok(),
         DistributionWrapper::Weibull(shape, scale) => Weibull::new(scale, shape)
-            .map(|exp| OrderedFloat(exp.sample(&mut ctx.rng)))
+            .map(|exp| OrderedFloat(exp.sample(rng)))
             .ok(),
         DistributionWrapper::LogNormal(mu, sigma) => LogNormal::new(mu, sigma)
-            .map(|exp| OrderedFloat(exp.sample(&mut ctx.rng)))
+            .map(|exp| OrderedFloat(exp.sample(rng)))
             .ok(),
     }
 }
@@ -1,9 +1,17 @@
-use ordered_float::OrderedFloat;
 use rand::Rng;
 use rand_distr::num_traits::Zero;
+use std::borrow::Cow;
 
 use crate::internal::core::{
-    Context, Message, engine, events::MessageDeliveryEvent, experiment::LinkKind, log,
+    Context, Message, engine,
+    events::MessageDeliveryEvent,
+    experiment::LinkKind,
+    faults::Fault,
+    log,
+    rng::RngStream,
+    stats::MessageOutcome,
+    time::SimDuration,
+    trace::{TraceKind, TraceRecord},
 };
 
 /// Verifies if peer 'from' can send a message to peer 'to' and
@@ -17,49 +25,83 @@ pub fn send_message_to(
     from: usize,
     to: usize,
     msg: impl Message + 'static,
-) -> Option<OrderedFloat<f64>> {
+) -> Option<SimDuration> {
     // Gets link, will be None if no link exists between peers
-    let link_info = ctx.links.get(from).and_then(|map| map.get(&to)).copied();
+    let link_info = ctx.links.get(from, to);
+
+    let msg_type = msg.type_name();
+    let msg_bytes = msg.size_bytes();
+    let outcome = if link_info.is_some() {
+        MessageOutcome::Sent
+    } else {
+        MessageOutcome::NotConnected
+    };
+    ctx.network_stats.record(from, msg_type, outcome, msg_bytes);
+
+    // planned faults are checked after the rates so that they draw the same numbers
+    let nth = ctx
+        .faults
+        .as_mut()
+        .map_or(0, |plan| plan.count_message(from, to));
 
     let drop_rate = ctx.get_drop_rate();
     // only generate random number if not zero
-    if link_info.is_some() && !drop_rate.is_zero() && drop_rate >= ctx.rng.random_range(0.0..1.0) {
-        log::trace(
+    let lost = link_info.is_some()
+        && !drop_rate.is_zero()
+        && drop_rate >= ctx.rng_for(RngStream::NetworkLoss).random_range(0.0..1.0);
+    if link_info.is_some()
+        && let Some(cause) = inject(ctx, Fault::Drop { from, to, nth }, lost, "drop_rate")
+    {
+        log::peer_trace(
             ctx,
-            format!("Message from {from} to {to} dropped due to drop_rate"),
+            from,
+            format!("Message from {from} to {to} dropped due to {cause}"),
         );
+        ctx.network_stats
+            .record(from, msg_type, MessageOutcome::DroppedLoss, msg_bytes);
+        trace_drop(ctx, from, to, &msg, "loss");
         return None;
     }
 
     let mut latency = match link_info {
         // if has latency defined
-        Some(Some(LinkKind::Latency(latency))) => OrderedFloat(latency),
+        Some(Some(LinkKind::Latency(latency))) => latency,
         // if latency and bandwidth defined
         Some(Some(LinkKind::Full { bandwidth, latency })) => {
-            OrderedFloat(latency + (msg.size_bits() as f64) / bandwidth)
+            latency + bandwidth.transmission_time(msg.size_bits())
         }
         // if latency undefined, use arrival_time_callback
         Some(bandwith_opt) => {
             let Some(mut delay) = (ctx.message_delay_cb)(ctx, from, to) else {
-                log::warn(
+                log::peer_warn(
                     ctx,
+                    from,
                     format!(
                         "Failed to send message from peer {from} to {to} because latency couldn't be calculated"
                     ),
                 );
+                ctx.network_stats.record(
+                    from,
+                    msg_type,
+                    MessageOutcome::DroppedNoLatency,
+                    msg_bytes,
+                );
+                trace_drop(ctx, from, to, &msg, "no_latency");
                 return None;
             };
 
             // if has bandwidth defined
             if let Some(LinkKind::Bandwidth(bandwidth)) = &bandwith_opt {
-                delay += (msg.size_bits() as f64) / bandwidth;
+                delay += bandwidth.transmission_time(msg.size_bits());
             }
 
             delay
         }
         None => {
-            log::warn(
+            trace_drop(ctx, from, to, &msg, "not_connected");
+            log::peer_warn(
                 ctx,
+                from,
                 format!(
                     "Failed to send message from peer {from} to {to} because they are not connected"
                 ),
@@ -82,15 +124,19 @@ pub fn send_message_to(
 
     let duplicate_rate = ctx.get_duplicate_rate();
     // only generate random number if not zero
-    if link_info.is_some()
-        && !duplicate_rate.is_zero()
-        && duplicate_rate >= ctx.rng.random_range(0.0..1.0)
-    {
+    let duplicated = !duplicate_rate.is_zero()
+        && duplicate_rate >= ctx.rng_for(RngStream::Duplication).random_range(0.0..1.0);
+    if let Some(cause) = inject(
+        ctx,
+        Fault::Duplicate { from, to, nth },
+        duplicated,
+        "duplicate_rate",
+    ) {
         let mut duplicate_latency = latency + ctx.get_jitter_value();
 
         // ensure delay isn't negative
-        if duplicate_latency < OrderedFloat(0.0) {
-            duplicate_latency = OrderedFloat(0.0);
+        if duplicate_latency.is_negative() {
+            duplicate_latency = SimDuration::ZERO;
             log::global_warn(
                 "Delay was set to 0 because after applying jitter the message delay was negative.",
             );
@@ -100,22 +146,28 @@ pub fn send_message_to(
             ctx,
             MessageDeliveryEvent::create_boxed(
                 ctx.clock + duplicate_latency,
+                ctx.clock,
                 from,
                 to,
                 msg.clone_box(),
             ),
         );
-        log::trace(
+        ctx.network_stats
+            .record(from, msg_type, MessageOutcome::Duplicated, msg_bytes);
+        ctx.network_stats
+            .record_latency(msg_type, duplicate_latency.as_secs());
+        log::peer_trace(
             ctx,
-            format!("Message from {from} to {to} duplicated due to duplicate_rate"),
+            from,
+            format!("Message from {from} to {to} duplicated due to {cause}"),
         );
     }
 
     latency += ctx.get_jitter_value();
 
     // ensure delay isn't negative
-    if latency < OrderedFloat(0.0) {
-        latency = OrderedFloat(0.0);
+    if latency.is_negative() {
+        latency = SimDuration::ZERO;
         log::global_warn(
             "Delay was set to 0 because after applying jitter the message delay was negative.",
         );
@@ -123,8 +175,56 @@ pub fn send_message_to(
 
     engine::add_event(
         ctx,
-        MessageDeliveryEvent::create(ctx.clock + latency, from, to, msg),
+        MessageDeliveryEvent::create(ctx.clock + latency, ctx.clock, from, to, msg),
     );
+    ctx.network_stats
+        .record_latency(msg_type, latency.as_secs());
 
     Some(latency)
 }
+
+/// Returns why a drop or duplicate happens, the rate or a planned fault, and
+/// records it in the fault plan.
+fn inject(
+    ctx: &mut Context,
+    fault: Fault,
+    by_rate: bool,
+    rate: &'static str,
+) -> Option<&'static str> {
+    let cause = if by_rate {
+        rate
+    } else if ctx
+        .faults
+        .as_ref()
+        .is_some_and(|plan| plan.is_planned(&fault))
+    {
+        "a planned fault"
+    } else {
+        return None;
+    };
+
+    if let Some(plan) = ctx.faults.as_mut() {
+        plan.record(fault);
+    }
+    Some(cause)
+}
+
+/// Records a message that is dropped when sent, with the id of the event sending it.
+fn trace_drop(ctx: &mut Context, from: usize, to: usize, msg: &dyn Message, reason: &'static str) {
+    let id = ctx.current_event_id();
+    let clock = ctx.clock.as_secs();
+    if let Some(tracer) = ctx.tracer.as_mut() {
+        tracer.record(&TraceRecord {
+            id,
+            timestamp: clock,
+            kind: TraceKind::MessageDrop,
+            sender: Some(from),
+            receiver: Some(to),
+            message_type: Some(Cow::Borrowed(msg.type_name())),
+            message_size: Some(msg.size_bytes()),
+            timer_type: None,
+            sent_at: Some(clock),
+            reason: Some(Cow::Borrowed(reason)),
+        });
+    }
+}
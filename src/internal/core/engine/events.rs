@@ -1,33 +1,93 @@
 use crate::internal::core::{
     Context,
+    debugger::DebugAction,
     events::{Event, EventType, Timer, TimerEvent},
     hooks::SimulationHooks,
-    log,
+    invariants, log, snapshot,
+    time::SimTime,
 };
 
-use ordered_float::OrderedFloat;
-
 pub fn add_event(ctx: &mut Context, event: EventType) {
     ctx.push_event(event);
 }
 
-pub fn add_timer(ctx: &mut Context, time: OrderedFloat<f64>, timer: impl Timer + 'static) {
+pub fn add_timer(ctx: &mut Context, time: SimTime, timer: impl Timer + 'static) {
     ctx.push_event(EventType::TimerEvent(TimerEvent::new(
         time,
         Box::new(timer),
     )));
 }
 
-pub fn run(ctx: &mut Context, hooks: &SimulationHooks, deadline_opt: Option<f64>) {
+/// Processes the next event if it is not after `until`, returns false if none was processed.
+/// Unlike `run`, the simulation is not finished and its outputs are not closed.
+pub fn step(ctx: &mut Context, until: Option<SimTime>) -> bool {
+    let is_due = ctx
+        .peek_next_event()
+        .is_some_and(|ev| until.is_none_or(|until| ev.timestamp() <= until));
+    if !is_due {
+        return false;
+    }
+
+    if let Some(ev) = next_event(ctx) {
+        process_event(ctx, ev);
+    }
+    true
+}
+
+fn process_event(ctx: &mut Context, mut ev: EventType) {
+    ctx.metrics.sample_until(ev.timestamp().as_secs());
+
+    ctx.clock = ev.timestamp();
+
+    if ctx.tracer.is_some() {
+        let record = ev.trace_record(ctx);
+        if let Some(tracer) = ctx.tracer.as_mut() {
+            tracer.record(&record);
+        }
+    }
+
+    ev.process(ctx);
+}
+
+// the interleaving chooses the next event when it is set
+fn next_event(ctx: &mut Context) -> Option<EventType> {
+    let Some(mut interleaving) = ctx.interleaving.take() else {
+        return ctx.get_next_event();
+    };
+    let event = interleaving.next_event(ctx);
+    ctx.interleaving = Some(interleaving);
+    event
+}
+
+pub fn run(ctx: &mut Context, hooks: &SimulationHooks, deadline_opt: Option<SimTime>) {
     log::global_internal("STARTING SIMULATION LOOP");
     log::internal(ctx, "SIMULATION LOOP STARTED");
 
     let (has_deadline, deadline) = match deadline_opt {
-        Some(dedln) => (dedln >= 0.0, OrderedFloat(dedln)),
-        None => (false, OrderedFloat(0.0)),
+        Some(dedln) => (dedln >= SimTime::ZERO, dedln),
+        None => (false, SimTime::ZERO),
     };
 
-    while let Some(mut ev) = ctx.get_next_event() {
+    let mut processed: u64 = 0;
+    let mut resumed = true;
+    if let Some(resume) = ctx.resume.take() {
+        match ctx.restore(resume.snapshot, &resume.registry) {
+            Ok(()) => log::global_internal(format!("Resumed the simulation at {}", ctx.clock)),
+            Err(e) => {
+                log::global_error(format!("Failed to resume the simulation: {e}"));
+                resumed = false;
+            }
+        }
+    }
+
+    let queue_len = ctx.events_left();
+    if let Some(progress) = ctx.progress.as_mut() {
+        progress.start(ctx.clock, deadline_opt, queue_len);
+    }
+
+    while resumed && let Some(ev) = next_event(ctx) {
+        snapshot::save_due_checkpoints(ctx, &ev);
+
         // Do not process events after the deadline
         if has_deadline && ev.timestamp() > deadline {
             ctx.clock = deadline;
@@ -40,9 +100,38 @@ pub fn run(ctx: &mut Context, hooks: &SimulationHooks, deadline_opt: Option<f64>
             continue;
         }
 
-        ctx.clock = ev.timestamp();
+        if let Some(mut debugger) = ctx.debugger.take() {
+            let action = debugger.before_event(ctx, &ev);
+            ctx.debugger = Some(debugger);
+            if action == DebugAction::Stop {
+                log::global_internal("The simulation was stopped by the debugger");
+                break;
+            }
+        }
+
+        if !hooks.invariants.is_empty() {
+            ctx.event_history.push(ev.trace_record(ctx));
+        }
+
+        process_event(ctx, ev);
+        processed += 1;
+
+        let queue_len = ctx.events_left();
+        if let Some(progress) = ctx.progress.as_mut() {
+            progress.record(ctx.clock, queue_len);
+        }
 
-        ev.process(ctx);
+        if let Some(violation) = invariants::check(ctx, &hooks.invariants, processed) {
+            log::error(
+                ctx,
+                format!(
+                    "Invariant '{}' was violated, stopping the simulation: {}",
+                    violation.invariant, violation.reason
+                ),
+            );
+            ctx.violation = Some(violation);
+            break;
+        }
 
         if (hooks.finish_condition)(ctx) {
             break;
@@ -53,6 +142,19 @@ pub fn run(ctx: &mut Context, hooks: &SimulationHooks, deadline_opt: Option<f64>
 
     (hooks.on_simulation_finish)(ctx);
 
+    let network_stats = ctx.network_stats.to_json();
+    log::metrics(ctx, "network_stats", &network_stats);
+    if let Some(mut progress) = ctx.progress.take() {
+        let stats = progress.finish();
+        log::global_internal(format!("{}: {stats}", progress.label()));
+        log::metrics(ctx, "run_stats", &stats.to_json());
+        ctx.progress = Some(progress);
+    }
+    ctx.metrics.finish(ctx.clock.as_secs());
+    if let Some(tracer) = ctx.tracer.as_mut() {
+        tracer.finish();
+    }
+
     log::global_internal(format!("FINISHED SIMULATION, SEED IS \"{:?}\"", ctx.seed()));
 
     ctx.logger.close_log_file();
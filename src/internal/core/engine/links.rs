@@ -14,7 +14,7 @@ pub fn add_oneway_link(ctx: &mut Context, from: usize, to: usize, info: LinkInfo
     }
 
     if from < ctx.links.len() && to < ctx.links.len() {
-        ctx.links[from].insert(to, info);
+        ctx.links.insert(from, to, info);
     } else {
         log::global_warn(format!(
             "Failed to create a one way link between peers {from} and {to} because at least one of them does not exist."
@@ -32,8 +32,8 @@ pub fn add_twoway_link(ctx: &mut Context, from: usize, to: usize, info: LinkInfo
     }
 
     if from < ctx.links.len() && to < ctx.links.len() {
-        ctx.links[from].insert(to, info);
-        ctx.links[to].insert(from, info);
+        ctx.links.insert(from, to, info);
+        ctx.links.insert(to, from, info);
     } else {
         log::global_warn(format!(
             "Failed to create a two way link between peers {from} and {to} because at least one of them does not exist."
@@ -41,11 +41,25 @@ pub fn add_twoway_link(ctx: &mut Context, from: usize, to: usize, info: LinkInfo
     }
 }
 
+/// Removes the links between two peers, in both directions.
+pub fn remove_twoway_link(ctx: &mut Context, from: usize, to: usize) {
+    if from < ctx.links.len() && to < ctx.links.len() {
+        ctx.links.remove(from, to);
+        ctx.links.remove(to, from);
+    } else {
+        log::global_warn(format!(
+            "Failed to remove the links between peers {from} and {to} because at least one of them does not exist."
+        ));
+    }
+}
+
 fn validate_link_info(info_opt: Option<LinkKind>) -> Result<(), String> {
     let (bandwidth, latency) = match info_opt {
-        Some(LinkKind::Bandwidth(b)) => (Some(b), None),
-        Some(LinkKind::Latency(l)) => (None, Some(l)),
-        Some(LinkKind::Full { bandwidth, latency }) => (Some(bandwidth), Some(latency)),
+        Some(LinkKind::Bandwidth(b)) => (Some(b.as_bps()), None),
+        Some(LinkKind::Latency(l)) => (None, Some(l.as_secs())),
+        Some(LinkKind::Full { bandwidth, latency }) => {
+            (Some(bandwidth.as_bps()), Some(latency.as_secs()))
+        }
         None => (None, None),
     };
 
@@ -5,6 +5,6 @@ mod peers;
 
 pub use communication::send_message_to;
 use events::add_event;
-pub use events::{add_timer, run};
-pub use links::{add_oneway_link, add_twoway_link};
-pub use peers::{add_peer, get_neighbors, get_neighbors_alive};
+pub use events::{add_timer, run, step};
+pub use links::{add_oneway_link, add_twoway_link, remove_twoway_link};
+pub use peers::{add_peer, get_neighbors, get_neighbors_alive, neighbors, neighbors_alive};
@@ -1,32 +1,27 @@
-use indexmap::IndexMap;
-
 use crate::internal::core::{Context, peer::CustomPeer};
 
 pub fn add_peer(ctx: &mut Context, mut custom_peer: impl CustomPeer + 'static) -> usize {
     let new_id = custom_peer.instantiate(ctx.peers.len());
 
     ctx.peers.push(Box::new(custom_peer));
-    ctx.links.push(IndexMap::new());
+    ctx.links.push_peer();
     new_id
 }
 
+/// The peers `peer_id` has a link to, without allocating. Empty if the peer does not exist.
+pub fn neighbors(ctx: &Context, peer_id: usize) -> impl Iterator<Item = usize> + '_ {
+    ctx.links.neighbors(peer_id).map(|(id, _)| id)
+}
+
+/// The alive peers `peer_id` has a link to, without allocating. Empty if the peer does not exist.
+pub fn neighbors_alive(ctx: &Context, peer_id: usize) -> impl Iterator<Item = usize> + '_ {
+    neighbors(ctx, peer_id).filter(|id| ctx.peers.get(*id).is_some_and(|p| p.is_alive()))
+}
+
 pub fn get_neighbors(ctx: &mut Context, peer_id: usize) -> Option<Vec<usize>> {
-    Some(
-        ctx.links
-            .get(peer_id)?
-            .keys()
-            .copied()
-            .collect::<Vec<usize>>(),
-    )
+    (peer_id < ctx.links.len()).then(|| neighbors(ctx, peer_id).collect())
 }
 
 pub fn get_neighbors_alive(ctx: &mut Context, peer_id: usize) -> Option<Vec<usize>> {
-    Some(
-        ctx.links
-            .get(peer_id)?
-            .keys()
-            .copied()
-            .filter(|id| ctx.peers.get(*id).is_some_and(|p| p.is_alive()))
-            .collect::<Vec<usize>>(),
-    )
+    (peer_id < ctx.links.len()).then(|| neighbors_alive(ctx, peer_id).collect())
 }
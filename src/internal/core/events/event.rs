@@ -1,14 +1,17 @@
 use enum_dispatch::enum_dispatch;
-use ordered_float::OrderedFloat;
 
 use super::Context;
+use crate::internal::core::{snapshot::SavedEvent, time::SimTime, trace::TraceRecord};
 
 #[enum_dispatch]
 pub trait Event {
     fn id(&self) -> u64;
     fn set_id(&mut self, id: u64);
-    fn timestamp(&self) -> OrderedFloat<f64>;
+    fn timestamp(&self) -> SimTime;
     fn process(&mut self, ctx: &mut Context);
+    /// How the event is traced, given the state it is processed in.
+    fn trace_record(&self, ctx: &Context) -> TraceRecord;
+    fn save(&self) -> Result<SavedEvent, String>;
 }
 
 macro_rules! impl_timestamp_id_ordering {
@@ -22,7 +25,7 @@ macro_rules! impl_timestamp_id_ordering {
         impl Ord for $struct_name {
             fn cmp(&self, other: &Self) -> Ordering {
                 // First compare by timestamp
-                match self.timestamp.total_cmp(&other.timestamp) {
+                match self.timestamp.cmp(&other.timestamp) {
                     Ordering::Equal => {
                         // If timestamps are equal, compare by id
                         self.id.cmp(&other.id)
@@ -1,10 +1,12 @@
 use enum_dispatch::enum_dispatch;
-use ordered_float::OrderedFloat;
 use std::cmp::Ordering;
 
 use crate::internal::core::{
     Context,
     events::{Event, MessageDeliveryEvent, TimerEvent},
+    snapshot::SavedEvent,
+    time::SimTime,
+    trace::TraceRecord,
 };
 
 #[enum_dispatch(EventType)]
@@ -14,9 +16,19 @@ pub enum EventType {
     MessageDeliveryEvent,
 }
 
+impl EventType {
+    /// Moves the event to another time, it must not be in the queue.
+    pub(crate) fn set_timestamp(&mut self, timestamp: SimTime) {
+        match self {
+            EventType::TimerEvent(event) => event.set_timestamp(timestamp),
+            EventType::MessageDeliveryEvent(event) => event.set_timestamp(timestamp),
+        }
+    }
+}
+
 impl Ord for EventType {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.timestamp().total_cmp(&other.timestamp()) {
+        match self.timestamp().cmp(&other.timestamp()) {
             Ordering::Equal => self.id().cmp(&other.id()),
             other_ordering => other_ordering,
         }
@@ -51,7 +63,7 @@ impl Event for EventType {
         event.set_id(id)
     }
 
-    fn timestamp(&self) -> OrderedFloat<f64> {
+    fn timestamp(&self) -> SimTime {
         let event: &dyn Event = match self {
             EventType::TimerEvent(event) => event,
             EventType::MessageDeliveryEvent(event) => event,
@@ -66,4 +78,20 @@ impl Event for EventType {
         };
         event.process(ctx);
     }
+
+    fn trace_record(&self, ctx: &Context) -> TraceRecord {
+        let event: &dyn Event = match self {
+            EventType::TimerEvent(event) => event,
+            EventType::MessageDeliveryEvent(event) => event,
+        };
+        event.trace_record(ctx)
+    }
+
+    fn save(&self) -> Result<SavedEvent, String> {
+        let event: &dyn Event = match self {
+            EventType::TimerEvent(event) => event,
+            EventType::MessageDeliveryEvent(event) => event,
+        };
+        event.save()
+    }
 }
@@ -1,17 +1,20 @@
-use std::cmp::Ordering;
-
-use ordered_float::OrderedFloat;
+use std::{borrow::Cow, cmp::Ordering};
 
 use crate::internal::core::{
     Context, Message,
     events::{Event, event::impl_timestamp_id_ordering, types::EventType},
     log,
+    snapshot::SavedEvent,
+    stats::MessageOutcome,
+    time::SimTime,
+    trace::{TraceKind, TraceRecord},
 };
 
 #[derive(Debug)]
 pub struct MessageDeliveryEvent {
     id: u64,
-    timestamp: OrderedFloat<f64>,
+    timestamp: SimTime,
+    sent_at: SimTime,
     sender: usize,
     receiver: usize,
     message: Box<dyn Message>,
@@ -22,7 +25,8 @@ impl_timestamp_id_ordering!(MessageDeliveryEvent);
 impl MessageDeliveryEvent {
     #[must_use]
     pub fn new(
-        timestamp: OrderedFloat<f64>,
+        timestamp: SimTime,
+        sent_at: SimTime,
         sender: usize,
         receiver: usize,
         message: Box<dyn Message>,
@@ -30,6 +34,7 @@ impl MessageDeliveryEvent {
         Self {
             id: 0,
             timestamp,
+            sent_at,
             sender,
             receiver,
             message,
@@ -38,13 +43,15 @@ impl MessageDeliveryEvent {
 
     #[must_use]
     pub fn create(
-        timestamp: OrderedFloat<f64>,
+        timestamp: SimTime,
+        sent_at: SimTime,
         sender: usize,
         receiver: usize,
         message: impl Message + 'static,
     ) -> EventType {
         EventType::MessageDeliveryEvent(MessageDeliveryEvent::new(
             timestamp,
+            sent_at,
             sender,
             receiver,
             Box::new(message),
@@ -53,15 +60,21 @@ impl MessageDeliveryEvent {
 
     #[must_use]
     pub fn create_boxed(
-        timestamp: OrderedFloat<f64>,
+        timestamp: SimTime,
+        sent_at: SimTime,
         sender: usize,
         receiver: usize,
         message: Box<dyn Message>,
     ) -> EventType {
         EventType::MessageDeliveryEvent(MessageDeliveryEvent::new(
-            timestamp, sender, receiver, message,
+            timestamp, sent_at, sender, receiver, message,
         ))
     }
+
+    /// Delivers the message at another time, used when deliveries are reordered.
+    pub(crate) fn set_timestamp(&mut self, timestamp: SimTime) {
+        self.timestamp = timestamp;
+    }
 }
 
 impl Event for MessageDeliveryEvent {
@@ -73,22 +86,36 @@ impl Event for MessageDeliveryEvent {
         self.id = id
     }
 
-    fn timestamp(&self) -> OrderedFloat<f64> {
+    fn timestamp(&self) -> SimTime {
         self.timestamp
     }
 
     fn process(&mut self, ctx: &mut Context) {
+        let msg_type = self.message.type_name();
+        let msg_bytes = self.message.size_bytes();
+
         if let Some(receiver) = ctx.peers.get(self.receiver) {
             if receiver.is_alive() {
-                (receiver.get_peer().on_message_receive)(
-                    ctx,
-                    self.sender,
+                ctx.network_stats.record(
                     self.receiver,
-                    self.message.as_ref(),
+                    msg_type,
+                    MessageOutcome::Delivered,
+                    msg_bytes,
                 );
+                let on_message_receive = receiver.get_peer().on_message_receive;
+                log::as_peer(ctx, self.receiver, |ctx| {
+                    on_message_receive(ctx, self.sender, self.receiver, self.message.as_ref());
+                });
             } else {
-                log::warn(
+                ctx.network_stats.record(
+                    self.receiver,
+                    msg_type,
+                    MessageOutcome::DroppedDead,
+                    msg_bytes,
+                );
+                log::peer_warn(
                     ctx,
+                    self.receiver,
                     format!(
                         "MessageDeliveryEvent not processed because receiver {} is dead",
                         self.receiver
@@ -96,8 +123,9 @@ impl Event for MessageDeliveryEvent {
                 );
             }
         } else {
-            log::warn(
+            log::peer_warn(
                 ctx,
+                self.receiver,
                 format!(
                     "MessageDeliveryEvent receiver {} does not exist",
                     self.receiver
@@ -105,4 +133,44 @@ impl Event for MessageDeliveryEvent {
             );
         }
     }
+
+    /// A message to a dead receiver is traced as dropped, as it is not delivered.
+    fn trace_record(&self, ctx: &Context) -> TraceRecord {
+        let dead = ctx
+            .peers
+            .get(self.receiver)
+            .is_some_and(|receiver| !receiver.is_alive());
+        TraceRecord {
+            id: self.id,
+            timestamp: self.timestamp.as_secs(),
+            kind: if dead {
+                TraceKind::MessageDrop
+            } else {
+                TraceKind::MessageDelivery
+            },
+            sender: Some(self.sender),
+            receiver: Some(self.receiver),
+            message_type: Some(Cow::Borrowed(self.message.type_name())),
+            message_size: Some(self.message.size_bytes()),
+            timer_type: None,
+            sent_at: Some(self.sent_at.as_secs()),
+            reason: dead.then_some(Cow::Borrowed("dead")),
+        }
+    }
+
+    fn save(&self) -> Result<SavedEvent, String> {
+        Ok(SavedEvent::MessageDelivery {
+            id: self.id,
+            timestamp: self.timestamp.as_secs(),
+            sent_at: self.sent_at.as_secs(),
+            sender: self.sender,
+            receiver: self.receiver,
+            message: self.message.snapshot().ok_or_else(|| {
+                format!(
+                    "The message {} does not support snapshots",
+                    self.message.type_name()
+                )
+            })?,
+        })
+    }
 }
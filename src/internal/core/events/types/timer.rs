@@ -1,22 +1,42 @@
-use std::{cmp::Ordering, fmt::Debug};
+use std::{borrow::Cow, cmp::Ordering, fmt::Debug};
 
 use downcast_rs::{Downcast, impl_downcast};
-use ordered_float::OrderedFloat;
 
 use crate::internal::core::{
     Context,
     events::{Event, event::impl_timestamp_id_ordering, types::EventType},
+    log,
+    snapshot::{SavedEvent, SavedObject},
+    time::SimTime,
+    trace::{TraceKind, TraceRecord},
+    utils,
 };
 
-pub trait Timer: Debug + Downcast {
+pub trait Timer: Debug + Downcast + Send {
     fn fire(&self, ctx: &mut Context);
+
+    /// Name used to identify the timer in traces.
+    fn type_name(&self) -> &'static str {
+        utils::short_type_name(std::any::type_name::<Self>())
+    }
+
+    /// The peer the timer acts for, its logs are attributed to it and
+    /// its trace records have it as the receiver.
+    fn peer(&self) -> Option<usize> {
+        None
+    }
+
+    /// Saves the timer in snapshots, `None` if it cannot be saved. Use `impl_snapshot!`.
+    fn snapshot(&self) -> Option<SavedObject> {
+        None
+    }
 }
 impl_downcast!(Timer);
 
 #[derive(Debug)]
 pub struct TimerEvent {
     id: u64,
-    timestamp: OrderedFloat<f64>,
+    timestamp: SimTime,
     timer: Box<dyn Timer>,
 }
 
@@ -24,7 +44,7 @@ impl_timestamp_id_ordering!(TimerEvent);
 
 impl TimerEvent {
     #[must_use]
-    pub fn new(timestamp: OrderedFloat<f64>, timer: Box<dyn Timer>) -> Self {
+    pub fn new(timestamp: SimTime, timer: Box<dyn Timer>) -> Self {
         Self {
             id: 0,
             timestamp,
@@ -33,9 +53,13 @@ impl TimerEvent {
     }
 
     #[must_use]
-    pub fn create(timestamp: OrderedFloat<f64>, timer: Box<dyn Timer>) -> EventType {
+    pub fn create(timestamp: SimTime, timer: Box<dyn Timer>) -> EventType {
         EventType::TimerEvent(TimerEvent::new(timestamp, timer))
     }
+
+    pub(crate) fn set_timestamp(&mut self, timestamp: SimTime) {
+        self.timestamp = timestamp;
+    }
 }
 
 impl Event for TimerEvent {
@@ -47,11 +71,42 @@ impl Event for TimerEvent {
         self.id = id
     }
 
-    fn timestamp(&self) -> OrderedFloat<f64> {
+    fn timestamp(&self) -> SimTime {
         self.timestamp
     }
 
     fn process(&mut self, ctx: &mut Context) {
-        self.timer.fire(ctx);
+        match self.timer.peer() {
+            Some(peer) => log::as_peer(ctx, peer, |ctx| self.timer.fire(ctx)),
+            None => self.timer.fire(ctx),
+        }
+    }
+
+    fn trace_record(&self, _ctx: &Context) -> TraceRecord {
+        TraceRecord {
+            id: self.id,
+            timestamp: self.timestamp.as_secs(),
+            kind: TraceKind::Timer,
+            sender: None,
+            receiver: self.timer.peer(),
+            message_type: None,
+            message_size: None,
+            timer_type: Some(Cow::Borrowed(self.timer.type_name())),
+            sent_at: None,
+            reason: None,
+        }
+    }
+
+    fn save(&self) -> Result<SavedEvent, String> {
+        Ok(SavedEvent::Timer {
+            id: self.id,
+            timestamp: self.timestamp.as_secs(),
+            timer: self.timer.snapshot().ok_or_else(|| {
+                format!(
+                    "The timer {} does not support snapshots",
+                    self.timer.type_name()
+                )
+            })?,
+        })
     }
 }
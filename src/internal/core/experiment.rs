@@ -1,13 +1,21 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 
-use super::distributions::DistributionWrapper;
+use super::{
+    distributions::DistributionWrapper,
+    faults::Fault,
+    time::{Bandwidth, SimDuration, SimTime, TimeUnit},
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum LinkKind {
-    Bandwidth(f64),
-    Latency(f64),
-    Full { bandwidth: f64, latency: f64 },
+    Bandwidth(Bandwidth),
+    Latency(SimDuration),
+    Full {
+        bandwidth: Bandwidth,
+        latency: SimDuration,
+    },
 }
 
 pub type LinkInfo = Option<LinkKind>;
@@ -16,6 +24,9 @@ pub type LinkInfo = Option<LinkKind>;
 pub struct Jitter {
     pub distribution: DistributionWrapper,
     pub multiplier: f64,
+    /// Unit of the samples, after they are multiplied
+    #[serde(default)]
+    pub unit: TimeUnit,
 }
 
 // from, to, option(bandwidth or latency)
@@ -44,7 +55,26 @@ impl TopologyInfo {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepMode {
+    /// Every combination of the values of each field
+    #[default]
+    Product,
+    /// The i-th value of every field together, all lists must have the same length
+    Zip,
+}
+
+/// Expands an experiment into several, one for each set of values.
+/// Fields are given by their path, e.g. `topology.n_peers` or `extra_args.kill_peer`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Sweep {
+    #[serde(default)]
+    pub mode: SweepMode,
+    pub params: IndexMap<String, Vec<Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Experiment {
     pub name: String,
     pub scenario: String,
@@ -54,7 +84,22 @@ pub struct Experiment {
     pub drop_rate: Option<f64>,
     pub duplicate_rate: Option<f64>,
     pub jitter: Option<Jitter>,
-    pub deadline: Option<f64>,
+    pub deadline: Option<SimTime>,
+    /// Event times are rounded to whole ticks of this duration, e.g. `1ns`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_resolution: Option<SimDuration>,
+    /// Faults injected in every repetition, besides those of the rates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faults: Option<Vec<Fault>>,
     pub extra_args: Option<Value>,
     pub repetitions: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sweep: Option<Sweep>,
+    /// Seed used by each repetition that was run, derived from `seed`.
+    #[serde(
+        default,
+        skip_deserializing,
+        skip_serializing_if = "IndexMap::is_empty"
+    )]
+    pub seeds: IndexMap<u64, u64>,
 }
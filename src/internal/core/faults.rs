@@ -0,0 +1,131 @@
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use super::{Context, engine, events::Timer, log, macros::impl_snapshot, time::SimTime};
+
+/// A fault injected into a run. Messages are identified by how many were sent
+/// before them between the same peers, so the faults of a seed are deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// The `nth` message sent from `from` to `to`, counting from 0, is lost
+    Drop { from: usize, to: usize, nth: u64 },
+    /// The `nth` message sent from `from` to `to` is delivered twice
+    Duplicate { from: usize, to: usize, nth: u64 },
+    /// The peer dies at `at`
+    Kill { peer: usize, at: SimTime },
+    /// The links between the peers are removed at `at`
+    Cut { from: usize, to: usize, at: SimTime },
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Fault::Drop { from, to, nth } => write!(f, "drop of message {nth} from {from} to {to}"),
+            Fault::Duplicate { from, to, nth } => {
+                write!(f, "duplicate of message {nth} from {from} to {to}")
+            }
+            Fault::Kill { peer, at } => write!(f, "kill of peer {peer} at {at}"),
+            Fault::Cut { from, to, at } => {
+                write!(f, "cut of the link between {from} and {to} at {at}")
+            }
+        }
+    }
+}
+
+/// The faults planned for a run and the ones it injected, including those caused by
+/// the drop and duplicate rates. Kills and cuts are planned with [`FaultTimer`]s.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FaultPlan {
+    drops: IndexSet<(usize, usize, u64)>,
+    duplicates: IndexSet<(usize, usize, u64)>,
+    // messages sent so far, by sender and receiver
+    sent: IndexMap<usize, IndexMap<usize, u64>>,
+    injected: Vec<Fault>,
+}
+
+impl FaultPlan {
+    /// Plans the drops and duplicates of `faults` and adds timers for the others.
+    pub fn install(ctx: &mut Context, faults: &[Fault]) {
+        let mut plan = FaultPlan::default();
+        for &fault in faults {
+            match fault {
+                Fault::Drop { from, to, nth } => {
+                    plan.drops.insert((from, to, nth));
+                }
+                Fault::Duplicate { from, to, nth } => {
+                    plan.duplicates.insert((from, to, nth));
+                }
+                Fault::Kill { at, .. } | Fault::Cut { at, .. } => {
+                    engine::add_timer(ctx, at, FaultTimer { fault });
+                }
+            }
+        }
+        ctx.faults = Some(plan);
+    }
+
+    /// Counts a message sent from `from` to `to`, returns how many were sent before it.
+    pub fn count_message(&mut self, from: usize, to: usize) -> u64 {
+        let sent = self.sent.entry(from).or_default().entry(to).or_default();
+        *sent += 1;
+        *sent - 1
+    }
+
+    #[must_use]
+    pub fn is_planned(&self, fault: &Fault) -> bool {
+        match *fault {
+            Fault::Drop { from, to, nth } => self.drops.contains(&(from, to, nth)),
+            Fault::Duplicate { from, to, nth } => self.duplicates.contains(&(from, to, nth)),
+            Fault::Kill { .. } | Fault::Cut { .. } => false,
+        }
+    }
+
+    pub fn record(&mut self, fault: Fault) {
+        self.injected.push(fault);
+    }
+
+    /// The faults that happened, in the order they were injected.
+    #[must_use]
+    pub fn injected(&self) -> &[Fault] {
+        &self.injected
+    }
+}
+
+/// Injects a kill or a link cut when it fires.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaultTimer {
+    pub fault: Fault,
+}
+
+impl Timer for FaultTimer {
+    fn peer(&self) -> Option<usize> {
+        match self.fault {
+            Fault::Kill { peer, .. } => Some(peer),
+            _ => None,
+        }
+    }
+
+    fn fire(&self, ctx: &mut Context) {
+        match self.fault {
+            Fault::Kill { peer, .. } => {
+                let Some(target) = ctx.peers.get_mut(peer) else {
+                    log::global_warn(format!(
+                        "Failed to kill peer {peer} because it does not exist"
+                    ));
+                    return;
+                };
+                target.kill();
+            }
+            Fault::Cut { from, to, .. } => engine::remove_twoway_link(ctx, from, to),
+            Fault::Drop { .. } | Fault::Duplicate { .. } => return,
+        }
+
+        log::debug(ctx, format!("Injected the {}", self.fault));
+        if let Some(plan) = ctx.faults.as_mut() {
+            plan.record(self.fault);
+        }
+    }
+
+    impl_snapshot!();
+}
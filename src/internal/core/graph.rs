@@ -0,0 +1,383 @@
+use indexmap::{IndexMap, map};
+use serde::{Deserialize, Serialize};
+use std::{iter::Zip, ops::Range, slice};
+
+use super::experiment::LinkInfo;
+
+/// The links every peer starts with, kept without a map per peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Base {
+    /// The links of each peer one row after the other (compressed sparse rows),
+    /// the row of peer `i` is `offsets[i]..offsets[i + 1]`.
+    Rows {
+        offsets: Vec<usize>,
+        targets: Vec<usize>,
+        infos: Vec<LinkInfo>,
+        // the positions in each row sorted by target, to look links up
+        sorted: Vec<usize>,
+    },
+    /// Every pair of the first `n_peers` peers is linked without an info, nothing is stored.
+    Full { n_peers: usize },
+}
+
+impl Default for Base {
+    fn default() -> Self {
+        Base::Rows {
+            offsets: vec![0],
+            targets: Vec::new(),
+            infos: Vec::new(),
+            sorted: Vec::new(),
+        }
+    }
+}
+
+impl Base {
+    fn row(offsets: &[usize], from: usize) -> Range<usize> {
+        match (offsets.get(from), offsets.get(from + 1)) {
+            (Some(&start), Some(&end)) => start..end,
+            _ => 0..0,
+        }
+    }
+
+    fn get(&self, from: usize, to: usize) -> Option<LinkInfo> {
+        match self {
+            Base::Rows {
+                offsets,
+                targets,
+                infos,
+                sorted,
+            } => {
+                let row = Base::row(offsets, from);
+                let positions = &sorted[row.clone()];
+                let found = positions
+                    .binary_search_by_key(&to, |&position| targets[row.start + position])
+                    .ok()?;
+                Some(infos[row.start + positions[found]])
+            }
+            Base::Full { n_peers } => {
+                (from < *n_peers && to < *n_peers && from != to).then_some(None)
+            }
+        }
+    }
+
+    fn neighbors(&self, from: usize) -> BaseNeighbors<'_> {
+        match self {
+            Base::Rows {
+                offsets,
+                targets,
+                infos,
+                ..
+            } => {
+                let row = Base::row(offsets, from);
+                BaseNeighbors::Rows(targets[row.clone()].iter().zip(infos[row].iter()))
+            }
+            Base::Full { n_peers } => BaseNeighbors::Full {
+                ids: 0..if from < *n_peers { *n_peers } else { 0 },
+                skip: from,
+            },
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Base::Rows { targets, .. } => targets.is_empty(),
+            Base::Full { n_peers } => *n_peers < 2,
+        }
+    }
+}
+
+enum BaseNeighbors<'a> {
+    Rows(Zip<slice::Iter<'a, usize>, slice::Iter<'a, LinkInfo>>),
+    Full { ids: Range<usize>, skip: usize },
+}
+
+impl Iterator for BaseNeighbors<'_> {
+    type Item = (usize, LinkInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            BaseNeighbors::Rows(links) => links.next().map(|(&to, &info)| (to, info)),
+            BaseNeighbors::Full { ids, skip } => ids.find(|id| id != skip).map(|id| (id, None)),
+        }
+    }
+}
+
+/// The changes made to the base links of a peer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Overlay {
+    // base links that were removed, sorted
+    removed: Vec<usize>,
+    // base links whose info was changed
+    changed: IndexMap<usize, LinkInfo>,
+    // links that come after the base ones
+    added: IndexMap<usize, LinkInfo>,
+}
+
+impl Overlay {
+    fn is_removed(&self, to: usize) -> bool {
+        self.removed.binary_search(&to).is_ok()
+    }
+}
+
+/// The outgoing links of every peer. The links are kept in compressed rows, or
+/// computed for a full topology, with the changes made since in an overlay that
+/// `compact` merges back. Links are listed in the order they were added, a
+/// changed link keeps its place and a removed one that is added again comes last.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Links {
+    base: Base,
+    // the peers whose links did not change have none
+    overlays: Vec<Option<Box<Overlay>>>,
+}
+
+impl Links {
+    /// The number of peers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.overlays.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    /// Adds a peer without links.
+    pub fn push_peer(&mut self) {
+        self.overlays.push(None);
+    }
+
+    /// Whether no peer has a link.
+    #[must_use]
+    pub fn is_unlinked(&self) -> bool {
+        self.base.is_empty()
+            && self
+                .overlays
+                .iter()
+                .flatten()
+                .all(|overlay| overlay.added.is_empty())
+    }
+
+    #[must_use]
+    pub fn get(&self, from: usize, to: usize) -> Option<LinkInfo> {
+        if let Some(overlay) = self.overlays.get(from)?.as_deref() {
+            if let Some(info) = overlay.added.get(&to) {
+                return Some(*info);
+            }
+            if overlay.is_removed(to) {
+                return None;
+            }
+            if let Some(info) = overlay.changed.get(&to) {
+                return Some(*info);
+            }
+        }
+        self.base.get(from, to)
+    }
+
+    /// Adds a link or changes the info of an existing one, `from` must be a peer.
+    pub fn insert(&mut self, from: usize, to: usize, info: LinkInfo) {
+        let in_base = self.base.get(from, to).is_some();
+        let overlay = self.overlays[from].get_or_insert_default();
+
+        if let Some(existing) = overlay.added.get_mut(&to) {
+            *existing = info;
+        } else if in_base && !overlay.is_removed(to) {
+            overlay.changed.insert(to, info);
+        } else {
+            overlay.added.insert(to, info);
+        }
+    }
+
+    /// Removes a link and returns its info, `from` must be a peer.
+    pub fn remove(&mut self, from: usize, to: usize) -> Option<LinkInfo> {
+        let base_info = self.base.get(from, to);
+        let overlay = self.overlays[from].get_or_insert_default();
+
+        if let Some(info) = overlay.added.shift_remove(&to) {
+            return Some(info);
+        }
+        let base_info = base_info?;
+        let position = overlay.removed.binary_search(&to).err()?;
+        overlay.removed.insert(position, to);
+        Some(overlay.changed.shift_remove(&to).unwrap_or(base_info))
+    }
+
+    /// The peers `from` has a link to and the info of each link, none if `from` is not a peer.
+    #[must_use]
+    pub fn neighbors(&self, from: usize) -> Neighbors<'_> {
+        let overlay = self.overlays.get(from).and_then(Option::as_deref);
+        Neighbors {
+            base: self.base.neighbors(from),
+            overlay,
+            added: overlay.map(|overlay| overlay.added.iter()),
+        }
+    }
+
+    /// Links every pair of the first `n_peers` peers without storing the links.
+    /// Only applies when no peer has a link yet, returns whether it did.
+    pub fn set_full(&mut self, n_peers: usize) -> bool {
+        if n_peers > self.len() || !self.is_unlinked() {
+            return false;
+        }
+        self.base = Base::Full { n_peers };
+        self.overlays.fill(None);
+        true
+    }
+
+    /// Merges the changes into compressed rows, a full topology is kept as it is.
+    pub fn compact(&mut self) {
+        if matches!(self.base, Base::Full { .. }) || self.overlays.iter().all(Option::is_none) {
+            return;
+        }
+
+        let mut offsets = Vec::with_capacity(self.len() + 1);
+        let mut targets = Vec::new();
+        let mut infos = Vec::new();
+        let mut sorted = Vec::new();
+        offsets.push(0);
+        for from in 0..self.len() {
+            let start = targets.len();
+            for (to, info) in self.neighbors(from) {
+                targets.push(to);
+                infos.push(info);
+            }
+            let mut positions: Vec<usize> = (0..targets.len() - start).collect();
+            positions.sort_unstable_by_key(|&position| targets[start + position]);
+            sorted.extend(positions);
+            offsets.push(targets.len());
+        }
+
+        self.base = Base::Rows {
+            offsets,
+            targets,
+            infos,
+            sorted,
+        };
+        self.overlays.fill(None);
+    }
+}
+
+/// Iterates over the links of a peer without allocating.
+pub struct Neighbors<'a> {
+    base: BaseNeighbors<'a>,
+    overlay: Option<&'a Overlay>,
+    added: Option<map::Iter<'a, usize, LinkInfo>>,
+}
+
+impl Iterator for Neighbors<'_> {
+    type Item = (usize, LinkInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (to, info) in self.base.by_ref() {
+            match self.overlay {
+                Some(overlay) if overlay.is_removed(to) => {}
+                Some(overlay) => {
+                    return Some((to, overlay.changed.get(&to).copied().unwrap_or(info)));
+                }
+                None => return Some((to, info)),
+            }
+        }
+        self.added.as_mut()?.next().map(|(&to, &info)| (to, info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::internal::core::{experiment::LinkKind, time::SimDuration};
+
+    fn info(secs: u32) -> LinkInfo {
+        Some(LinkKind::Latency(SimDuration::from_secs(f64::from(secs))))
+    }
+
+    fn assert_same(links: &Links, maps: &[IndexMap<usize, LinkInfo>]) {
+        for (from, map) in maps.iter().enumerate() {
+            let listed: Vec<(usize, LinkInfo)> = links.neighbors(from).collect();
+            let expected: Vec<(usize, LinkInfo)> =
+                map.iter().map(|(&to, &info)| (to, info)).collect();
+            assert_eq!(listed, expected, "links of peer {from}");
+            for to in 0..maps.len() {
+                assert_eq!(links.get(from, to), map.get(&to).copied());
+            }
+        }
+    }
+
+    #[test]
+    fn links_keep_the_order_of_a_map_per_peer() {
+        const N_PEERS: usize = 12;
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut links = Links::default();
+        let mut maps = vec![IndexMap::new(); N_PEERS];
+        for _ in 0..N_PEERS {
+            links.push_peer();
+        }
+        assert!(links.set_full(N_PEERS - 2));
+        for (from, map) in maps.iter_mut().enumerate().take(N_PEERS - 2) {
+            map.extend(
+                (0..N_PEERS - 2)
+                    .filter(|&to| to != from)
+                    .map(|to| (to, None)),
+            );
+        }
+
+        for round in 0..3_000_u32 {
+            let from = rng.random_range(0..N_PEERS);
+            let to = rng.random_range(0..N_PEERS);
+            if rng.random_bool(0.5) {
+                links.insert(from, to, info(round % 4));
+                maps[from].insert(to, info(round % 4));
+            } else {
+                assert_eq!(links.remove(from, to), maps[from].shift_remove(&to));
+            }
+            if round == 1_000 {
+                // the changes to a full topology stay in the overlay
+                links.compact();
+                assert!(matches!(links.base, Base::Full { .. }));
+                links = Links {
+                    base: Base::default(),
+                    overlays: vec![None; N_PEERS],
+                };
+                for (from, map) in maps.iter().enumerate() {
+                    for (&to, &info) in map {
+                        links.insert(from, to, info);
+                    }
+                }
+            }
+            if round % 500 == 0 {
+                links.compact();
+            }
+            assert_same(&links, &maps);
+        }
+    }
+
+    #[test]
+    fn full_links_are_computed_only_while_there_are_no_links() {
+        let mut links = Links::default();
+        for _ in 0..4 {
+            links.push_peer();
+        }
+        links.insert(0, 1, None);
+        assert!(!links.set_full(4));
+        links.remove(0, 1);
+        assert!(links.set_full(4));
+        links.push_peer();
+
+        let listed: Vec<Vec<usize>> = (0..5)
+            .map(|from| links.neighbors(from).map(|(to, _)| to).collect())
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                vec![1, 2, 3],
+                vec![0, 2, 3],
+                vec![0, 1, 3],
+                vec![0, 1, 2],
+                vec![]
+            ]
+        );
+        assert_eq!(links.neighbors(7).count(), 0);
+    }
+}
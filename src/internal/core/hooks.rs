@@ -1,4 +1,7 @@
-use super::Context;
+use super::{
+    Context,
+    invariants::{Invariant, InvariantCheck},
+};
 
 pub type CustomOnFinishHook = Box<dyn Fn(&mut Context)>;
 pub type CustomFinishConditionHook = Box<dyn Fn(&mut Context) -> bool>;
@@ -8,6 +11,8 @@ pub struct SimulationHooks {
     pub on_simulation_finish: CustomOnFinishHook,
     /// If it returns true, the simulation loop finishes.
     pub finish_condition: CustomFinishConditionHook,
+    /// Checked while the simulation runs, it stops at the first violation.
+    pub invariants: Vec<Invariant>,
 }
 
 impl Default for SimulationHooks {
@@ -15,6 +20,7 @@ impl Default for SimulationHooks {
         Self {
             on_simulation_finish: Box::new(|_ctx| {}),
             finish_condition: Box::new(|_ctx| false),
+            invariants: Vec::new(),
         }
     }
 }
@@ -29,4 +35,19 @@ impl SimulationHooks {
         self.finish_condition = hook;
         self
     }
+
+    /// Adds an invariant checked after every `every` processed events.
+    pub fn add_invariant(
+        &mut self,
+        name: impl Into<String>,
+        every: u64,
+        check: InvariantCheck,
+    ) -> &mut Self {
+        self.invariants.push(Invariant {
+            name: name.into(),
+            every,
+            check,
+        });
+        self
+    }
 }
@@ -0,0 +1,199 @@
+use indexmap::IndexMap;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+use super::{
+    Context,
+    events::{Event, EventType},
+    time::SimDuration,
+    trace::TraceRecord,
+};
+
+/// A moment where deliveries could have happened in another order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChoicePoint {
+    pub clock: f64,
+    /// The events that could be processed next, the first one is processed by default
+    pub alternatives: Vec<TraceRecord>,
+}
+
+/// Processes another alternative than the default one at a choice point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deviation {
+    /// Index of the choice point in the run
+    pub choice_point: usize,
+    pub alternative: usize,
+}
+
+/// The deviations of a run from the default order of events, by choice point.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub deviations: Vec<Deviation>,
+}
+
+impl Schedule {
+    /// The schedule that takes these alternatives, one per choice point.
+    #[must_use]
+    pub fn from_taken(taken: &[usize]) -> Self {
+        Self {
+            deviations: taken
+                .iter()
+                .enumerate()
+                .filter(|&(_, &alternative)| alternative != 0)
+                .map(|(choice_point, &alternative)| Deviation {
+                    choice_point,
+                    alternative,
+                })
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn alternative(&self, choice_point: usize) -> usize {
+        self.deviations
+            .iter()
+            .find(|deviation| deviation.choice_point == choice_point)
+            .map_or(0, |deviation| deviation.alternative)
+    }
+}
+
+// Probabilistic concurrency testing: the message of the sender with the highest priority
+// is delivered first, and the sender chosen at a change point gets the lowest priority.
+// Timers have no sender and share a priority.
+#[derive(Debug)]
+struct Pct {
+    rng: ChaCha8Rng,
+    depth: u64,
+    priorities: IndexMap<usize, u64>,
+    change_points: Vec<usize>,
+}
+
+#[derive(Debug)]
+enum Strategy {
+    Schedule(Schedule),
+    Pct(Box<Pct>),
+}
+
+/// Chooses the order of deliveries that are due at about the same time, the
+/// simulation loop asks it for the next event when it is set in the context.
+#[derive(Debug)]
+pub struct Interleaving {
+    /// Deliveries due at most this long after the next event can be processed before it
+    pub window: SimDuration,
+    strategy: Strategy,
+    /// The choice points reached by the run
+    pub choice_points: Vec<ChoicePoint>,
+    /// The alternative processed at each choice point
+    pub taken: Vec<usize>,
+}
+
+impl Interleaving {
+    /// Follows a schedule, the default alternative is taken where it has no deviation.
+    #[must_use]
+    pub fn new(window: SimDuration, schedule: Schedule) -> Self {
+        Self {
+            window,
+            strategy: Strategy::Schedule(schedule),
+            choice_points: Vec::new(),
+            taken: Vec::new(),
+        }
+    }
+
+    /// Chooses with random priorities, changed at `depth - 1` of the first `length` choice points.
+    #[must_use]
+    pub fn pct(window: SimDuration, seed: u64, depth: u64, length: usize) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let change_points = (1..depth)
+            .map(|_| rng.random_range(0..length.max(1)))
+            .collect();
+
+        Self {
+            window,
+            strategy: Strategy::Pct(Box::new(Pct {
+                rng,
+                depth,
+                priorities: IndexMap::new(),
+                change_points,
+            })),
+            choice_points: Vec::new(),
+            taken: Vec::new(),
+        }
+    }
+
+    /// The schedule of the run so far.
+    #[must_use]
+    pub fn schedule(&self) -> Schedule {
+        Schedule::from_taken(&self.taken)
+    }
+
+    /// Takes the next event out of the queue, a delivery due within the window
+    /// can be taken instead and is then delivered at the time of the next event.
+    pub fn next_event(&mut self, ctx: &mut Context) -> Option<EventType> {
+        let next = ctx.peek_next_event()?;
+        let next_timestamp = next.timestamp();
+        let next_id = next.id();
+        let limit = next_timestamp + self.window;
+
+        let mut alternatives: Vec<&EventType> = ctx
+            .pending_events()
+            .filter(|ev| {
+                ev.id() != next_id
+                    && matches!(ev, EventType::MessageDeliveryEvent(_))
+                    && ev.timestamp() <= limit
+            })
+            .collect();
+        if alternatives.is_empty() {
+            return ctx.get_next_event();
+        }
+        alternatives.sort();
+        alternatives.insert(0, next);
+
+        let point = ChoicePoint {
+            clock: next_timestamp.as_secs(),
+            alternatives: alternatives.iter().map(|ev| ev.trace_record(ctx)).collect(),
+        };
+        let ids: Vec<u64> = alternatives.iter().map(|ev| ev.id()).collect();
+
+        let chosen = self.choose(&point).min(ids.len() - 1);
+        self.choice_points.push(point);
+        self.taken.push(chosen);
+
+        let mut event = ctx.take_event(ids[chosen])?;
+        if let EventType::MessageDeliveryEvent(delivery) = &mut event {
+            delivery.set_timestamp(next_timestamp);
+        }
+        Some(event)
+    }
+
+    fn choose(&mut self, point: &ChoicePoint) -> usize {
+        let index = self.choice_points.len();
+
+        match &mut self.strategy {
+            Strategy::Schedule(schedule) => schedule.alternative(index),
+            Strategy::Pct(pct) => {
+                let sender = |record: &TraceRecord| record.sender.unwrap_or(usize::MAX);
+                for record in &point.alternatives {
+                    let depth = pct.depth;
+                    let rng = &mut pct.rng;
+                    pct.priorities
+                        .entry(sender(record))
+                        .or_insert_with(|| depth + u64::from(rng.random::<u32>()));
+                }
+
+                let chosen = (0..point.alternatives.len())
+                    .max_by_key(|&i| (pct.priorities[&sender(&point.alternatives[i])], Reverse(i)))
+                    .unwrap_or(0);
+
+                if let Some(change) = pct.change_points.iter().position(|&at| at == index) {
+                    pct.priorities.insert(
+                        sender(&point.alternatives[chosen]),
+                        pct.depth - 1 - change as u64,
+                    );
+                }
+                chosen
+            }
+        }
+    }
+}
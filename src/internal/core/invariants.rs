@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+};
+
+use super::{Context, trace::TraceRecord};
+
+pub type InvariantCheck = Box<dyn Fn(&Context) -> Result<(), String>>;
+
+/// A property of the simulation that must hold while it runs.
+pub struct Invariant {
+    pub name: String,
+    /// It is checked after every `every` processed events
+    pub every: u64,
+    /// Returns why the property does not hold
+    pub check: InvariantCheck,
+}
+
+/// The last events processed by a run with invariants, reported when one is violated.
+#[derive(Debug, Clone)]
+pub struct EventHistory {
+    records: VecDeque<TraceRecord>,
+    capacity: usize,
+}
+
+impl EventHistory {
+    pub const DEFAULT_CAPACITY: usize = 32;
+
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, record: TraceRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// The kept events, from the oldest to the last processed.
+    pub fn records(&self) -> impl Iterator<Item = &TraceRecord> {
+        self.records.iter()
+    }
+}
+
+impl Default for EventHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// Describes the first invariant that did not hold, the run stops after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    pub reason: String,
+    /// Seed of the run, it is enough to reproduce the violation
+    pub seed: u64,
+    pub clock: f64,
+    /// How many events were processed, the last one broke the invariant
+    pub events_processed: u64,
+    /// The last events processed, from the oldest
+    pub recent_events: Vec<TraceRecord>,
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Invariant '{}' violated at {} after {} events: {}",
+            self.invariant, self.clock, self.events_processed, self.reason
+        )?;
+        write!(
+            f,
+            "Seed is \"{}\", the last {} events were:",
+            self.seed,
+            self.recent_events.len()
+        )?;
+        for record in &self.recent_events {
+            write!(f, "\n  {record}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the invariants due after the `processed`th event, returns the first one that fails.
+#[must_use]
+pub fn check(
+    ctx: &Context,
+    invariants: &[Invariant],
+    processed: u64,
+) -> Option<InvariantViolation> {
+    invariants
+        .iter()
+        .filter(|invariant| processed.is_multiple_of(invariant.every.max(1)))
+        .find_map(|invariant| {
+            (invariant.check)(ctx)
+                .err()
+                .map(|reason| InvariantViolation {
+                    invariant: invariant.name.clone(),
+                    reason,
+                    seed: ctx.seed(),
+                    clock: ctx.clock.as_secs(),
+                    events_processed: processed,
+                    recent_events: ctx.event_history.records().cloned().collect(),
+                })
+        })
+}
@@ -1,16 +1,24 @@
 use clap::ValueEnum;
-use ordered_float::OrderedFloat;
+use indexmap::{IndexMap, IndexSet};
 use paste::paste;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::Value;
 use std::{
     fmt::{self, Display, Formatter},
     fs::{self, File, OpenOptions},
     io::{self, BufWriter, Write},
-    path::Path,
+    mem,
+    panic::Location,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use super::Context;
+use super::{
+    Context,
+    log_filter::{self, LogFilter},
+    sink::{MetricsFormat, MetricsSink},
+    time::TimeUnit,
+};
 
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy, ValueEnum, Serialize, Deserialize)]
 pub enum LoggerLevel {
@@ -36,15 +44,90 @@ impl Display for LoggerLevel {
     }
 }
 
+/// How the logs are written to the log files, the console always gets text.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `[clock] [LEVEL] text` lines
+    #[default]
+    Text,
+    /// A JSON object per line with the clock in seconds, level, peer, target and message
+    Json,
+}
+
+impl LogFormat {
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LogFormat::Text => "log",
+            LogFormat::Json => "log.jsonl",
+        }
+    }
+
+    #[must_use]
+    pub fn is_text(&self) -> bool {
+        *self == LogFormat::Text
+    }
+}
+
+/// A log written by the JSON format.
+#[derive(Serialize)]
+struct LogRecord<'a> {
+    clock: f64,
+    level: String,
+    peer: Option<usize>,
+    target: &'a str,
+    message: &'a str,
+}
+
+/// The logs of each peer, kept in memory until they are appended to the file of
+/// the peer so that runs with many peers do not keep a file open for each.
+struct PeerLogs {
+    dir: PathBuf,
+    extension: &'static str,
+    pending: IndexMap<usize, String>,
+    // the peers whose file was created by this run, the others are truncated first
+    created: IndexSet<usize>,
+}
+
+impl PeerLogs {
+    fn push(&mut self, peer: usize, line: &str) {
+        let lines = self.pending.entry(peer).or_default();
+        lines.push_str(line);
+        lines.push('\n');
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for (peer, lines) in mem::take(&mut self.pending) {
+            let path = self.dir.join(format!("peer_{peer}.{}", self.extension));
+            let mut file = if self.created.insert(peer) {
+                File::create(path)?
+            } else {
+                OpenOptions::new().append(true).open(path)?
+            };
+            file.write_all(lines.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
 pub struct Logger {
     level: LoggerLevel,
+    file_level: LoggerLevel,
+    filter: LogFilter,
+    // the peer whose code is running, set while it receives a message
+    peer: Option<usize>,
     log_writer: Option<BufWriter<File>>,
     log_file_location: String,
-    metrics_writer: Option<BufWriter<File>>,
+    log_format: LogFormat,
+    peer_logs: Option<PeerLogs>,
+    metrics_sink: Option<MetricsSink>,
     metrics_file_location: String,
     flush_threshold: usize,
     log_unflushed_count: usize,
     quiet: bool,
+    captured: Option<Vec<String>>,
+    time_unit: Option<TimeUnit>,
 }
 
 impl Logger {
@@ -52,13 +135,20 @@ impl Logger {
     pub fn new(level: Option<LoggerLevel>, quiet: bool) -> Self {
         Self {
             level: level.unwrap_or(LoggerLevel::Info),
+            file_level: LoggerLevel::Trace,
+            filter: LogFilter::default(),
+            peer: None,
             log_writer: None,
             log_file_location: String::new(),
-            metrics_writer: None,
+            log_format: LogFormat::Text,
+            peer_logs: None,
+            metrics_sink: None,
             metrics_file_location: String::new(),
             flush_threshold: 200,
             log_unflushed_count: 0,
             quiet,
+            captured: None,
+            time_unit: None,
         }
     }
 
@@ -66,6 +156,56 @@ impl Logger {
         self.flush_threshold = new_threshold;
     }
 
+    /// Prints the times of the logs in `unit`, or in seconds without a unit if `None`.
+    pub fn set_time_unit(&mut self, unit: Option<TimeUnit>) {
+        self.time_unit = unit;
+    }
+
+    /// The least level of the logs written to the log file, every log is written by default.
+    pub fn set_file_level(&mut self, level: LoggerLevel) {
+        self.file_level = level;
+    }
+
+    /// Selects the logs written by their peer, and the level of those written to
+    /// the console by their module. The log file keeps its own level.
+    pub fn set_filter(&mut self, filter: LogFilter) {
+        self.filter = filter;
+    }
+
+    /// The format of the logs written to the log files from now on.
+    pub fn set_log_format(&mut self, format: LogFormat) {
+        self.log_format = format;
+    }
+
+    /// Writes the logs of each peer to a file of its own in `dir` instead of the log
+    /// file, which keeps the logs of no peer. They are not saved in snapshots.
+    pub fn set_peer_log_dir<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        self.peer_logs = Some(PeerLogs {
+            dir: dir.as_ref().to_path_buf(),
+            extension: self.log_format.extension(),
+            pending: IndexMap::new(),
+            created: IndexSet::new(),
+        });
+        Ok(())
+    }
+
+    /// The logs without a peer are attributed to `peer` until it is set again.
+    pub fn set_peer(&mut self, peer: Option<usize>) {
+        self.peer = peer;
+    }
+
+    /// Keeps every log line in memory from now on, like the log file they do not depend on the level.
+    pub fn capture(&mut self) {
+        self.captured.get_or_insert_with(Vec::new);
+    }
+
+    /// The log lines kept since `capture` was called.
+    #[must_use]
+    pub fn captured(&self) -> &[String] {
+        self.captured.as_deref().unwrap_or_default()
+    }
+
     // TODO: Move this elsewhere
     fn open_file<P: AsRef<Path>>(file_path: P) -> io::Result<File> {
         // Create parent directories if they don't exist
@@ -93,10 +233,12 @@ impl Logger {
         Ok(())
     }
 
-    pub fn set_metrics_file<P: AsRef<Path>>(&mut self, file_path: P) -> io::Result<()> {
-        let file = Self::open_file(&file_path)?;
-
-        self.metrics_writer = Some(BufWriter::new(file));
+    pub fn set_metrics_file<P: AsRef<Path>>(
+        &mut self,
+        file_path: P,
+        format: MetricsFormat,
+    ) -> io::Result<()> {
+        self.metrics_sink = Some(MetricsSink::create(&file_path, format)?);
         self.metrics_file_location = match file_path.as_ref().canonicalize() {
             Ok(path) => path.to_string_lossy().to_string(),
             // if cannot get canonicalize path, use relative
@@ -109,7 +251,39 @@ impl Logger {
         Ok(())
     }
 
+    /// Flushes the log file and returns it with its location.
+    pub fn log_file(&mut self) -> io::Result<Option<(&File, &str)>> {
+        match self.log_writer.as_mut() {
+            Some(writer) => {
+                writer.flush()?;
+                self.log_unflushed_count = 0;
+                Ok(Some((writer.get_ref(), self.log_file_location.as_str())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Flushes the metrics file and returns it with its location, `None` if
+    /// there is no metrics file or it can only be read once closed.
+    pub fn metrics_file(&mut self) -> io::Result<Option<(&File, &str)>> {
+        match self.metrics_sink.as_mut() {
+            Some(sink) => Ok(sink
+                .file()?
+                .map(|file| (file, self.metrics_file_location.as_str()))),
+            None => Ok(None),
+        }
+    }
+
     pub fn close_log_file(&mut self) {
+        if let Some(mut peer_logs) = self.peer_logs.take() {
+            if let Err(e) = peer_logs.flush() {
+                global_error(format!("Failed to write the logs of the peers: {e}"));
+            }
+            global_internal(format!(
+                "The logs of the peers are located at: {}",
+                peer_logs.dir.display()
+            ));
+        }
         if let Some(mut writer) = self.log_writer.take() {
             writer.flush().ok();
             global_internal(format!(
@@ -122,8 +296,10 @@ impl Logger {
     }
 
     pub fn close_metrics_file(&mut self) {
-        if let Some(mut writer) = self.metrics_writer.take() {
-            writer.flush().ok();
+        if let Some(sink) = self.metrics_sink.take() {
+            if let Err(e) = sink.close() {
+                global_error(format!("Failed to close the metrics file: {e}"));
+            }
             global_internal(format!(
                 "The metrics file is located at: {}",
                 self.metrics_file_location
@@ -133,78 +309,171 @@ impl Logger {
         self.log_unflushed_count = 0;
     }
 
-    fn enabled(&self, level: LoggerLevel) -> bool {
-        self.level <= level
+    /// Whether a log goes to the console and to the log file, `target` is
+    /// the module of the log when the filter has rules for modules.
+    fn outputs(
+        &self,
+        level: LoggerLevel,
+        peer: Option<usize>,
+        target: Option<&str>,
+    ) -> (bool, bool) {
+        if !self.filter.allows_peer(peer) {
+            return (false, false);
+        }
+        let console_level = self
+            .filter
+            .level_for(target.unwrap_or_default())
+            .unwrap_or(self.level);
+        (console_level <= level, self.file_level <= level)
     }
 
-    fn write_to_log_file(&mut self, message: &str, level: LoggerLevel) {
-        if let Some(ref mut writer) = self.log_writer {
-            if writeln!(writer, "{message}").is_ok() {
-                self.log_unflushed_count += 1;
+    fn writes_logs(&self) -> bool {
+        self.log_writer.is_some() || self.peer_logs.is_some()
+    }
 
-                let should_flush_immediately =
-                    matches!(level, LoggerLevel::Error | LoggerLevel::Internal);
+    fn write_to_log_file(&mut self, message: &str, level: LoggerLevel, peer: Option<usize>) {
+        let written = match (self.peer_logs.as_mut(), peer) {
+            (Some(peer_logs), Some(peer)) => {
+                peer_logs.push(peer, message);
+                true
+            }
+            _ => self
+                .log_writer
+                .as_mut()
+                .is_some_and(|writer| writeln!(writer, "{message}").is_ok()),
+        };
+        if !written {
+            return;
+        }
+        self.log_unflushed_count += 1;
+
+        let should_flush_immediately = matches!(level, LoggerLevel::Error | LoggerLevel::Internal);
 
-                let should_flush_threshold = self.log_unflushed_count >= self.flush_threshold;
+        let should_flush_threshold = self.log_unflushed_count >= self.flush_threshold;
 
-                if should_flush_immediately || should_flush_threshold {
-                    writer.flush().ok();
-                    self.log_unflushed_count = 0;
-                }
+        if should_flush_immediately || should_flush_threshold {
+            if let Some(writer) = self.log_writer.as_mut() {
+                writer.flush().ok();
+            }
+            if let Some(peer_logs) = self.peer_logs.as_mut()
+                && let Err(e) = peer_logs.flush()
+            {
+                global_error(format!("Failed to write the logs of the peers: {e}"));
             }
+            self.log_unflushed_count = 0;
         }
     }
 
-    fn write_to_metrics_file(&mut self, metrics: &Value) {
-        if let Some(ref mut metrics_writer) = self.metrics_writer {
-            if writeln!(metrics_writer, "{metrics}").is_ok() {
-                metrics_writer.flush().ok();
-            }
+    fn write_to_metrics_file(&mut self, timestamp: f64, title: &str, metrics: &Value) {
+        if let Some(ref mut sink) = self.metrics_sink
+            && let Err(e) = sink.write_metrics(timestamp, title, metrics)
+        {
+            global_error(format!("Failed to write metrics '{title}': {e}"));
         }
     }
 }
 
-fn log_format(clock: OrderedFloat<f64>, level: LoggerLevel, text: impl AsRef<str>) -> String {
+fn log_format(clock: &str, level: LoggerLevel, text: impl AsRef<str>) -> String {
     format!("[{}] [{}] {}", clock, level, text.as_ref())
 }
 
-fn ctx_log(ctx: &mut Context, level: LoggerLevel, text: impl AsRef<str>) {
-    // outputs logs to file independently of the logger_level
-    if ctx.logger.log_writer.is_some() {
-        ctx.logger
-            .write_to_log_file(&log_format(ctx.clock, level, &text), level);
-    }
+fn json_log_format(
+    clock: f64,
+    level: LoggerLevel,
+    peer: Option<usize>,
+    target: &str,
+    text: impl AsRef<str>,
+) -> String {
+    let record = LogRecord {
+        clock,
+        level: level.to_string(),
+        peer,
+        target,
+        message: text.as_ref(),
+    };
+    serde_json::to_string(&record).unwrap_or_default()
+}
 
-    if !ctx.logger.quiet {
-        let msg = log_format(ctx.clock, level, &text);
-        if ctx.logger.enabled(level) {
-            match level {
-                LoggerLevel::Warn | LoggerLevel::Error => eprintln!("{}", &msg),
-                _ => println!("{}", &msg),
+fn ctx_log(
+    ctx: &mut Context,
+    level: LoggerLevel,
+    peer: Option<usize>,
+    location: &Location,
+    text: impl AsRef<str>,
+) {
+    let clock = ctx.clock.display_in(ctx.logger.time_unit);
+    let peer = peer.or(ctx.logger.peer);
+    // finding the module of a log allocates, it is only done for the logs that need it
+    let target = ctx
+        .logger
+        .filter
+        .has_targets()
+        .then(|| log_filter::target_of(location));
+    let (to_console, to_file) = ctx.logger.outputs(level, peer, target.as_deref());
+
+    if to_file && ctx.logger.writes_logs() {
+        let line = match ctx.logger.log_format {
+            LogFormat::Text => log_format(&clock, level, &text),
+            LogFormat::Json => {
+                let target = target.unwrap_or_else(|| log_filter::target_of(location));
+                json_log_format(ctx.clock.as_secs(), level, peer, &target, &text)
             }
+        };
+        ctx.logger.write_to_log_file(&line, level, peer);
+    }
+    if let Some(lines) = ctx.logger.captured.as_mut() {
+        lines.push(log_format(&clock, level, &text));
+    }
+
+    if !ctx.logger.quiet && to_console {
+        let msg = log_format(&clock, level, &text);
+        match level {
+            LoggerLevel::Warn | LoggerLevel::Error => eprintln!("{}", &msg),
+            _ => println!("{}", &msg),
         }
     }
 }
 
+static GLOBAL_MUTED: AtomicBool = AtomicBool::new(false);
+
+/// Hides the global logs that are not errors, e.g. while the same simulation is run many times.
+pub fn mute_global(muted: bool) {
+    GLOBAL_MUTED.store(muted, Ordering::Relaxed);
+}
+
 fn global_log(level: LoggerLevel, text: impl AsRef<str>) {
+    if level != LoggerLevel::Error && GLOBAL_MUTED.load(Ordering::Relaxed) {
+        return;
+    }
     println!("[GLOBAL] [{}] {}", level, text.as_ref());
 }
 
-pub fn metrics(ctx: &mut Context, title: impl AsRef<str>, metrics: &Value) {
-    let metrics_with_timestamp = json!({
-        "title": title.as_ref(),
-        "timestamp": *ctx.clock,
-        "metrics": metrics
-    });
+/// Runs `f` with the logs that have no peer attributed to `peer`, for code that acts
+/// for a peer, like its timers or a loop over the peers.
+pub fn as_peer<T>(ctx: &mut Context, peer: usize, f: impl FnOnce(&mut Context) -> T) -> T {
+    let previous = ctx.logger.peer.replace(peer);
+    let result = f(ctx);
+    ctx.logger.peer = previous;
+    result
+}
 
-    ctx.logger.write_to_metrics_file(&metrics_with_timestamp);
+pub fn metrics(ctx: &mut Context, title: impl AsRef<str>, metrics: &Value) {
+    ctx.logger
+        .write_to_metrics_file(ctx.clock.as_secs(), title.as_ref(), metrics);
 }
 
 macro_rules! define_log_fn {
     ($name:ident, $level:expr) => {
         paste! {
+            #[track_caller]
             pub fn $name(ctx: &mut Context, text: impl AsRef<str>) {
-                ctx_log(ctx, $level, text);
+                ctx_log(ctx, $level, None, Location::caller(), text);
+            }
+
+            /// Logs on behalf of a peer, filters can keep the logs of some peers only.
+            #[track_caller]
+            pub fn [<peer_ $name>](ctx: &mut Context, peer: usize, text: impl AsRef<str>) {
+                ctx_log(ctx, $level, Some(peer), Location::caller(), text);
             }
 
             pub fn [<global_ $name>](text: impl AsRef<str>) {
@@ -220,3 +489,60 @@ define_log_fn!(info, LoggerLevel::Info);
 define_log_fn!(warn, LoggerLevel::Warn);
 define_log_fn!(error, LoggerLevel::Error);
 define_log_fn!(internal, LoggerLevel::Internal);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_logs_are_created_by_the_run_then_appended() {
+        let dir = std::env::temp_dir().join(format!("tdes_peer_logs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("peer_1.log"), "from an earlier run\n").unwrap();
+
+        let mut peer_logs = PeerLogs {
+            dir: dir.clone(),
+            extension: LogFormat::Text.extension(),
+            pending: IndexMap::new(),
+            created: IndexSet::new(),
+        };
+        peer_logs.push(1, "first");
+        peer_logs.push(2, "other");
+        peer_logs.flush().unwrap();
+        peer_logs.push(1, "second");
+        peer_logs.flush().unwrap();
+
+        let read = |peer| fs::read_to_string(dir.join(format!("peer_{peer}.log"))).unwrap();
+        assert_eq!(read(1), "first\nsecond\n");
+        assert_eq!(read(2), "other\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn filter_levels_only_apply_to_the_console() {
+        let mut logger = Logger::new(Some(LoggerLevel::Info), true);
+        logger.set_file_level(LoggerLevel::Trace);
+        logger.set_filter("peer=1,*=warn".parse().unwrap());
+
+        assert_eq!(
+            logger.outputs(LoggerLevel::Debug, None, None),
+            (false, true)
+        );
+        assert_eq!(
+            logger.outputs(LoggerLevel::Warn, Some(1), None),
+            (true, true)
+        );
+        assert_eq!(
+            logger.outputs(LoggerLevel::Error, Some(2), None),
+            (false, false)
+        );
+
+        logger.set_file_level(LoggerLevel::Info);
+        logger.set_filter("discovery=trace".parse().unwrap());
+        let discovery = Some("scenarios::proposed_dglm::discovery");
+        assert_eq!(
+            logger.outputs(LoggerLevel::Debug, None, discovery),
+            (true, false)
+        );
+    }
+}
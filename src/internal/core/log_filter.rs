@@ -0,0 +1,177 @@
+use clap::ValueEnum;
+use std::{
+    fmt::{self, Display, Formatter},
+    panic::Location,
+    str::FromStr,
+};
+
+use super::log::LoggerLevel;
+
+/// Selects the logs written by the context, parsed from comma separated rules:
+/// `peer=3` only keeps the logs of peer 3 and those of no peer, `discovery=trace`
+/// sets the level of the logs of the modules named `discovery`, and `*=warn` sets
+/// the level of the other logs. The levels of the rules replace the level of the
+/// console, the log files keep theirs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFilter {
+    // empty keeps the logs of every peer
+    peers: Vec<usize>,
+    targets: Vec<(String, LoggerLevel)>,
+    default: Option<LoggerLevel>,
+}
+
+impl LogFilter {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[must_use]
+    pub fn allows_peer(&self, peer: Option<usize>) -> bool {
+        self.peers.is_empty() || peer.is_none_or(|peer| self.peers.contains(&peer))
+    }
+
+    #[must_use]
+    pub fn has_targets(&self) -> bool {
+        !self.targets.is_empty()
+    }
+
+    /// The level of the logs of `target`, from its most specific rule. `None` if no
+    /// rule applies and the level of the output is kept.
+    #[must_use]
+    pub fn level_for(&self, target: &str) -> Option<LoggerLevel> {
+        self.targets
+            .iter()
+            .filter(|(name, _)| matches_target(name, target))
+            .max_by_key(|(name, _)| name.split("::").count())
+            .map(|(_, level)| *level)
+            .or(self.default)
+    }
+}
+
+/// Whether the modules of `name` appear one after the other in `target`.
+fn matches_target(name: &str, target: &str) -> bool {
+    target.match_indices(name).any(|(start, _)| {
+        let end = start + name.len();
+        (start == 0 || target[..start].ends_with("::"))
+            && (end == target.len() || target[end..].starts_with("::"))
+    })
+}
+
+/// The module path of the file a log comes from, e.g. `scenarios::proposed_dglm::discovery`.
+#[must_use]
+pub fn target_of(location: &Location) -> String {
+    let path = location.file().replace('\\', "/");
+    let path = path
+        .rsplit_once("src/")
+        .map_or(path.as_str(), |(_, path)| path);
+    let path = path.strip_suffix(".rs").unwrap_or(path);
+    let path = path.strip_suffix("/mod").unwrap_or(path);
+    path.replace('/', "::")
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for rule in text
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+        {
+            let Some((name, value)) = rule.split_once('=') else {
+                return Err(format!("'{rule}' is not a rule, expected name=value"));
+            };
+            let (name, value) = (name.trim(), value.trim());
+
+            if name == "peer" {
+                let peer = value
+                    .parse()
+                    .map_err(|_| format!("'{value}' is not a peer id"))?;
+                filter.peers.push(peer);
+                continue;
+            }
+            let level = LoggerLevel::from_str(value, true)
+                .map_err(|_| format!("'{value}' is not a log level"))?;
+            if name == "*" {
+                filter.default = Some(level);
+            } else {
+                filter.targets.push((name.to_string(), level));
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl Display for LogFilter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut rules: Vec<String> = self
+            .peers
+            .iter()
+            .map(|peer| format!("peer={peer}"))
+            .collect();
+        rules.extend(
+            self.targets
+                .iter()
+                .map(|(name, level)| format!("{name}={}", level_name(*level))),
+        );
+        if let Some(level) = self.default {
+            rules.push(format!("*={}", level_name(level)));
+        }
+        write!(f, "{}", rules.join(","))
+    }
+}
+
+fn level_name(level: LoggerLevel) -> String {
+    level
+        .to_possible_value()
+        .map_or_else(|| level.to_string(), |value| value.get_name().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_parsed_and_printed_back() {
+        let filter: LogFilter = "peer=3, discovery=trace,*=warn".parse().unwrap();
+        assert_eq!(filter.to_string(), "peer=3,discovery=trace,*=warn");
+        assert!(filter.allows_peer(Some(3)));
+        assert!(filter.allows_peer(None));
+        assert!(!filter.allows_peer(Some(4)));
+
+        assert!("peer=three".parse::<LogFilter>().is_err());
+        assert!("discovery=loud".parse::<LogFilter>().is_err());
+        assert!("discovery".parse::<LogFilter>().is_err());
+        assert!("".parse::<LogFilter>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn the_most_specific_target_sets_the_level() {
+        let filter: LogFilter = "proposed_dglm=info,proposed_dglm::discovery=trace,*=error"
+            .parse()
+            .unwrap();
+        let level = |target| filter.level_for(target);
+
+        assert_eq!(
+            level("scenarios::proposed_dglm::discovery"),
+            Some(LoggerLevel::Trace)
+        );
+        assert_eq!(
+            level("scenarios::proposed_dglm::algorithms"),
+            Some(LoggerLevel::Info)
+        );
+        assert_eq!(
+            level("scenarios::proposed_dglm_v2"),
+            Some(LoggerLevel::Error)
+        );
+        assert_eq!(LogFilter::default().level_for("engine"), None);
+    }
+
+    #[test]
+    fn targets_are_the_module_of_the_file() {
+        let location = Location::caller();
+        assert_eq!(target_of(location), "internal::core::log_filter");
+    }
+}
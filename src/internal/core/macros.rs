@@ -1,3 +1,4 @@
+#[macro_export]
 macro_rules! get_peer_of_type {
     ($ctx:expr, $peer_id:expr, $peer_type:ty) => {{
         if $peer_id >= $ctx.peers.len() {
@@ -16,10 +17,11 @@ macro_rules! get_peer_of_type {
     }};
 }
 
-pub(crate) use get_peer_of_type;
+pub use crate::get_peer_of_type;
 
+#[macro_export]
 macro_rules! define_custom_peer {
-    ($structname: ident) => {
+    ($structname: ident $(, $option: ident)*) => {
         impl CustomPeer for $structname {
             fn get_peer(&self) -> &PeerInfo {
                 &self.peer_info
@@ -28,12 +30,45 @@ macro_rules! define_custom_peer {
             fn get_peer_mut(&mut self) -> &mut PeerInfo {
                 &mut self.peer_info
             }
+
+            $($crate::internal::core::macros::custom_peer_option!($option);)*
+        }
+    };
+}
+
+// Methods added by the options of define_custom_peer
+#[doc(hidden)]
+#[macro_export]
+macro_rules! custom_peer_option {
+    // the debugger inspects the peer through its Debug implementation
+    (debug) => {
+        fn inspect(&self) -> String {
+            format!("{self:#?}")
+        }
+    };
+    (snapshot) => {
+        $crate::internal::core::macros::impl_snapshot!();
+    };
+}
+
+pub use crate::custom_peer_option;
+
+/// Implements `snapshot` for a peer, message or timer that can be serialized,
+/// its type must also be registered by the scenario to be restored.
+#[macro_export]
+macro_rules! impl_snapshot {
+    () => {
+        fn snapshot(&self) -> Option<$crate::internal::core::snapshot::SavedObject> {
+            $crate::internal::core::snapshot::SavedObject::of(self)
         }
     };
 }
 
-pub(crate) use define_custom_peer;
+pub use crate::impl_snapshot;
+
+pub use crate::define_custom_peer;
 
+#[macro_export]
 macro_rules! define_custom_arrival_time_callback {
     ($name:ident, $topology_name:expr, |$ctx:ident, $from:ident, $to:ident| $connect_fn:block) => {
         pub struct $name;
@@ -46,13 +81,14 @@ macro_rules! define_custom_arrival_time_callback {
                 $topology_name
             }
 
-            fn callback($ctx: &mut Context, $from: usize, $to: usize) -> Option<OrderedFloat<f64>> $connect_fn
+            fn callback($ctx: &mut Context, $from: usize, $to: usize) -> Option<SimDuration> $connect_fn
         }
     };
 }
 
-pub(crate) use define_custom_arrival_time_callback;
+pub use crate::define_custom_arrival_time_callback;
 
+#[macro_export]
 macro_rules! define_custom_topology {
     ($name:ident, $topology_name:expr, $connect_fn:path) => {
         pub struct $name;
@@ -73,4 +109,4 @@ macro_rules! define_custom_topology {
     };
 }
 
-pub(crate) use define_custom_topology;
+pub use crate::define_custom_topology;
@@ -1,6 +1,8 @@
 use downcast_rs::{Downcast, impl_downcast};
 use std::fmt::Debug;
 
+use super::{snapshot::SavedObject, utils};
+
 pub trait MessageClone {
     fn clone_box(&self) -> Box<dyn Message>;
 }
@@ -14,11 +16,21 @@ where
     }
 }
 
-pub trait Message: Debug + Downcast + MessageClone {
+pub trait Message: Debug + Downcast + MessageClone + Send {
     fn size_bits(&self) -> u64 {
         self.size_bytes() * 8 // Default: convert bytes to bits
     }
 
     fn size_bytes(&self) -> u64;
+
+    /// Name used to group the message in the network statistics and traces.
+    fn type_name(&self) -> &'static str {
+        utils::short_type_name(std::any::type_name::<Self>())
+    }
+
+    /// Saves the message in snapshots, `None` if it cannot be saved. Use `impl_snapshot!`.
+    fn snapshot(&self) -> Option<SavedObject> {
+        None
+    }
 }
 impl_downcast!(Message);
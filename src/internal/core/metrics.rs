@@ -0,0 +1,380 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fs::File, io, iter, path::Path};
+
+use super::{
+    log,
+    sink::{MetricRecord, MetricsFormat, MetricsSink},
+    stats::Histogram,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counter {
+    value: u64,
+}
+
+impl Counter {
+    pub fn inc(&mut self) {
+        self.value += 1;
+    }
+
+    pub fn add(&mut self, amount: u64) {
+        self.value += amount;
+    }
+
+    #[must_use]
+    pub fn get(&self) -> u64 {
+        self.value
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Gauge {
+    value: f64,
+}
+
+impl Gauge {
+    pub fn set(&mut self, value: f64) {
+        self.value = value;
+    }
+
+    pub fn add(&mut self, amount: f64) {
+        self.value += amount;
+    }
+
+    #[must_use]
+    pub fn get(&self) -> f64 {
+        self.value
+    }
+}
+
+// Metrics are grouped by name and then by peer, `None` being the metrics not tied to a peer.
+type Metrics<T> = IndexMap<String, IndexMap<Option<usize>, T>>;
+
+/// The values of the metrics and when the next sample is due, saved in snapshots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedMetrics {
+    counters: Vec<(String, Option<usize>, Counter)>,
+    gauges: Vec<(String, Option<usize>, Gauge)>,
+    histograms: Vec<(String, Option<usize>, Histogram)>,
+    samples_due: u64,
+    last_sample: Option<f64>,
+}
+
+/// Counters, gauges and histograms that can be tagged by peer. When an
+/// interval is set they are sampled every interval of simulation time,
+/// producing a time series, and they are always sampled when the run ends.
+#[derive(Default)]
+pub struct MetricsRegistry {
+    counters: Metrics<Counter>,
+    gauges: Metrics<Gauge>,
+    histograms: Metrics<Histogram>,
+    interval: Option<f64>,
+    // the next sample is taken at (samples_due + 1) * interval
+    samples_due: u64,
+    last_sample: Option<f64>,
+    sink: Option<MetricsSink>,
+    file_location: String,
+}
+
+impl MetricsRegistry {
+    pub fn set_output<P: AsRef<Path>>(
+        &mut self,
+        file_path: P,
+        format: MetricsFormat,
+    ) -> io::Result<()> {
+        self.sink = Some(MetricsSink::create(&file_path, format)?);
+        self.file_location = match file_path.as_ref().canonicalize() {
+            Ok(path) => path.to_string_lossy().to_string(),
+            // if cannot get canonicalize path, use relative
+            Err(_) => file_path.as_ref().to_string_lossy().to_string(),
+        };
+        log::global_internal(format!(
+            "Metrics registry file created at: {}",
+            self.file_location
+        ));
+        Ok(())
+    }
+
+    /// Sets how much simulation time passes between samples, `None` only samples at the end.
+    pub fn set_interval(&mut self, interval: Option<f64>) {
+        self.interval = match interval {
+            Some(interval) if interval <= 0.0 => {
+                log::global_warn(format!(
+                    "Metrics interval should be greater than 0.0, not {interval}. Metrics will only be sampled at the end."
+                ));
+                None
+            }
+            interval => interval,
+        };
+    }
+
+    pub fn counter(&mut self, name: &str) -> &mut Counter {
+        Self::entry(&mut self.counters, name, None, Counter::default)
+    }
+
+    pub fn peer_counter(&mut self, name: &str, peer_id: usize) -> &mut Counter {
+        Self::entry(&mut self.counters, name, Some(peer_id), Counter::default)
+    }
+
+    pub fn gauge(&mut self, name: &str) -> &mut Gauge {
+        Self::entry(&mut self.gauges, name, None, Gauge::default)
+    }
+
+    pub fn peer_gauge(&mut self, name: &str, peer_id: usize) -> &mut Gauge {
+        Self::entry(&mut self.gauges, name, Some(peer_id), Gauge::default)
+    }
+
+    /// Histograms are created with exponential buckets from 1e-6
+    /// unless they were registered with `register_histogram`.
+    pub fn histogram(&mut self, name: &str) -> &mut Histogram {
+        Self::entry(&mut self.histograms, name, None, Self::default_histogram)
+    }
+
+    pub fn peer_histogram(&mut self, name: &str, peer_id: usize) -> &mut Histogram {
+        Self::entry(
+            &mut self.histograms,
+            name,
+            Some(peer_id),
+            Self::default_histogram,
+        )
+    }
+
+    /// Sets the histogram used for a metric, allowing custom buckets.
+    pub fn register_histogram(&mut self, name: &str, peer_id: Option<usize>, histogram: Histogram) {
+        self.histograms
+            .entry(name.to_string())
+            .or_default()
+            .insert(peer_id, histogram);
+    }
+
+    #[must_use]
+    pub fn get_counter(&self, name: &str, peer_id: Option<usize>) -> Option<&Counter> {
+        self.counters
+            .get(name)
+            .and_then(|peers| peers.get(&peer_id))
+    }
+
+    #[must_use]
+    pub fn get_gauge(&self, name: &str, peer_id: Option<usize>) -> Option<&Gauge> {
+        self.gauges.get(name).and_then(|peers| peers.get(&peer_id))
+    }
+
+    #[must_use]
+    pub fn get_histogram(&self, name: &str, peer_id: Option<usize>) -> Option<&Histogram> {
+        self.histograms
+            .get(name)
+            .and_then(|peers| peers.get(&peer_id))
+    }
+
+    /// Takes every sample that is due by `clock`. Values do not change between
+    /// events, so the samples due since the last event have the same values.
+    pub fn sample_until(&mut self, clock: f64) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let due = (self.samples_due + 1..)
+            .map(|sample| sample as f64 * interval)
+            .take_while(|&sample_time| sample_time <= clock);
+        self.samples_due += self.sample_at(due);
+    }
+
+    /// Writes the value of every metric at `timestamp`, the title
+    /// of each record is the name of the metric and its key the field.
+    pub fn sample(&mut self, timestamp: f64) {
+        self.sample_at(iter::once(timestamp));
+    }
+
+    // writes the current values once for each of `timestamps` and returns how many there were
+    fn sample_at(&mut self, timestamps: impl Iterator<Item = f64>) -> u64 {
+        let mut records = if self.sink.is_some() {
+            self.records()
+        } else {
+            Vec::new()
+        };
+
+        let mut samples = 0;
+        for timestamp in timestamps {
+            samples += 1;
+            self.last_sample = Some(timestamp);
+            let Some(sink) = self.sink.as_mut() else {
+                continue;
+            };
+
+            for record in &mut records {
+                record.timestamp = timestamp;
+                if let Err(e) = sink.write_record(record) {
+                    log::global_error(format!("Failed to write metric '{}': {e}", record.title));
+                    break;
+                }
+            }
+        }
+        samples
+    }
+
+    // the current value of every metric, without a timestamp yet
+    fn records(&self) -> Vec<MetricRecord<'static>> {
+        let mut records: Vec<MetricRecord> = Vec::new();
+        let mut push = |name: &str, peer: Option<usize>, key: &'static str, value: f64| {
+            records.push(MetricRecord {
+                timestamp: 0.0,
+                title: Cow::Owned(name.to_string()),
+                peer,
+                key: Cow::Borrowed(key),
+                value,
+            });
+        };
+
+        for (name, peers) in &self.counters {
+            for (peer, counter) in peers {
+                push(name, *peer, "value", counter.get() as f64);
+            }
+        }
+        for (name, peers) in &self.gauges {
+            for (peer, gauge) in peers {
+                push(name, *peer, "value", gauge.get());
+            }
+        }
+        for (name, peers) in &self.histograms {
+            for (peer, histogram) in peers {
+                let fields = [
+                    ("count", Some(histogram.count() as f64)),
+                    ("sum", Some(histogram.sum())),
+                    ("min", histogram.min()),
+                    ("max", histogram.max()),
+                    ("mean", histogram.mean()),
+                    ("p50", histogram.quantile(0.5)),
+                    ("p90", histogram.quantile(0.9)),
+                    ("p99", histogram.quantile(0.99)),
+                ];
+                for (key, value) in fields {
+                    if let Some(value) = value {
+                        push(name, *peer, key, value);
+                    }
+                }
+            }
+        }
+        records
+    }
+
+    /// Takes the last sample, unless one was already taken at `clock`, and closes the file.
+    pub fn finish(&mut self, clock: f64) {
+        self.sample_until(clock);
+        if self.last_sample != Some(clock) {
+            self.sample(clock);
+        }
+
+        if let Some(sink) = self.sink.take() {
+            if let Err(e) = sink.close() {
+                log::global_error(format!("Failed to close the metrics registry file: {e}"));
+            }
+            log::global_internal(format!(
+                "The metrics registry file is located at: {}",
+                self.file_location
+            ));
+            self.file_location = String::new();
+        }
+    }
+
+    /// Flushes the registry file and returns it with its location.
+    pub fn file(&mut self) -> io::Result<Option<(&File, &str)>> {
+        match self.sink.as_mut() {
+            Some(sink) => Ok(sink.file()?.map(|file| (file, self.file_location.as_str()))),
+            None => Ok(None),
+        }
+    }
+
+    #[must_use]
+    pub fn save(&self) -> SavedMetrics {
+        fn flatten<T: Clone>(metrics: &Metrics<T>) -> Vec<(String, Option<usize>, T)> {
+            metrics
+                .iter()
+                .flat_map(|(name, peers)| {
+                    peers
+                        .iter()
+                        .map(|(peer, metric)| (name.clone(), *peer, metric.clone()))
+                })
+                .collect()
+        }
+
+        SavedMetrics {
+            counters: flatten(&self.counters),
+            gauges: flatten(&self.gauges),
+            histograms: flatten(&self.histograms),
+            samples_due: self.samples_due,
+            last_sample: self.last_sample,
+        }
+    }
+
+    /// Replaces the values of the metrics, the output and interval are kept.
+    pub fn restore(&mut self, saved: SavedMetrics) {
+        fn group<T>(metrics: Vec<(String, Option<usize>, T)>) -> Metrics<T> {
+            let mut grouped: Metrics<T> = IndexMap::new();
+            for (name, peer, metric) in metrics {
+                grouped.entry(name).or_default().insert(peer, metric);
+            }
+            grouped
+        }
+
+        self.counters = group(saved.counters);
+        self.gauges = group(saved.gauges);
+        self.histograms = group(saved.histograms);
+        self.samples_due = saved.samples_due;
+        self.last_sample = saved.last_sample;
+    }
+
+    fn default_histogram() -> Histogram {
+        Histogram::exponential(1.0e-6, 2.0, 64)
+    }
+
+    fn entry<'a, T>(
+        metrics: &'a mut Metrics<T>,
+        name: &str,
+        peer_id: Option<usize>,
+        default: impl FnOnce() -> T,
+    ) -> &'a mut T {
+        // avoids allocating the name when the metric already exists
+        if !metrics.contains_key(name) {
+            metrics.insert(name.to_string(), IndexMap::new());
+        }
+
+        metrics
+            .get_mut(name)
+            .expect("metric was inserted")
+            .entry(peer_id)
+            .or_insert_with(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sample_due_between_two_events_is_written() {
+        let path = std::env::temp_dir().join(format!("tdes_samples_{}.jsonl", std::process::id()));
+        let mut metrics = MetricsRegistry::default();
+        metrics.set_output(&path, MetricsFormat::Jsonl).unwrap();
+        metrics.set_interval(Some(0.1));
+
+        metrics.counter("events").inc();
+        metrics.sample_until(0.0);
+        metrics.counter("events").inc();
+        metrics.sample_until(1.0);
+        metrics.finish(1.0);
+
+        let timestamps: Vec<f64> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["timestamp"]
+                    .as_f64()
+                    .unwrap()
+            })
+            .collect();
+        let expected: Vec<f64> = (1..=10).map(|sample| sample as f64 * 0.1).collect();
+        assert_eq!(timestamps, expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,15 +1,31 @@
 pub mod builtins;
-pub mod experiment;
 pub mod context;
-pub mod hooks;
+pub mod debugger;
+pub mod distributions;
 pub mod engine;
 pub mod events;
+pub mod experiment;
+pub mod faults;
+pub mod graph;
+pub mod hooks;
+pub mod interleaving;
+pub mod invariants;
 pub mod log;
+pub mod log_filter;
 pub mod macros;
 mod message;
+pub mod metrics;
 pub mod options;
 pub mod peer;
-pub mod distributions;
+pub mod progress;
+pub mod rng;
+pub mod scheduler;
+pub mod sink;
+pub mod snapshot;
+pub mod stats;
+pub mod time;
+pub mod trace;
+pub mod utils;
 
 pub use context::Context;
 pub use message::Message;
@@ -1,11 +1,8 @@
-use serde_yaml::Value;
-
-use super::super::experiment::TopologyInfo;
+use super::super::{experiment::TopologyInfo, time::SimTime};
 
 #[derive(Debug)]
 pub struct ExperimentOptions {
     pub topology: TopologyInfo,
     pub arrival_time: Option<String>,
-    pub deadline: Option<f64>,
-    pub extra_args: Option<Value>,
+    pub deadline: Option<SimTime>,
 }
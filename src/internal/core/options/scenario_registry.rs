@@ -1,14 +1,26 @@
 use indexmap::IndexMap;
+use serde_yaml::Value;
 
 use super::super::{
-    super::Simulator, Context, log, options::ExperimentOptions, options::traits::Scenario,
+    super::Simulator, Context, faults::FaultTimer, log, options::ExperimentOptions,
+    options::traits::Scenario, snapshot::SnapshotRegistry,
 };
 
 // Type alias for scenario functions
-type ScenarioFn = fn(&mut Context, &Simulator, ExperimentOptions);
+type ScenarioFn =
+    fn(&mut Context, &Simulator, ExperimentOptions, Option<Value>) -> Result<(), String>;
+// Type alias for functions that list a scenario's options and their default values
+type ScenarioOptionsFn = fn() -> Vec<(String, String)>;
+
+struct ScenarioEntry {
+    start: ScenarioFn,
+    description: &'static str,
+    options: ScenarioOptionsFn,
+    snapshot_types: fn(&mut SnapshotRegistry),
+}
 
 pub struct ScenarioRegistry {
-    scenarios: IndexMap<String, (ScenarioFn, &'static str)>,
+    scenarios: IndexMap<String, ScenarioEntry>,
 }
 
 impl ScenarioRegistry {
@@ -24,7 +36,15 @@ impl ScenarioRegistry {
         if self.scenarios.contains_key(&name) {
             log::global_warn(format!("A scenario named {name} already exists"));
         } else {
-            self.scenarios.insert(name, (S::start, S::description()));
+            self.scenarios.insert(
+                name,
+                ScenarioEntry {
+                    start: start_scenario::<S>,
+                    description: S::description(),
+                    options: scenario_options::<S>,
+                    snapshot_types: S::register_snapshot_types,
+                },
+            );
         }
         self
     }
@@ -35,6 +55,7 @@ impl ScenarioRegistry {
         ctx: &mut Context,
         simulator: &Simulator,
         opts: ExperimentOptions,
+        extra_args: Option<Value>,
     ) -> Result<(), String> {
         match &opts.topology.name {
             Some(name) => log::global_info(format!("Topology selected from configuration: {name}")),
@@ -48,21 +69,45 @@ impl ScenarioRegistry {
         }
 
         match self.scenarios.get(name) {
-            Some((scenario_fn, _)) => {
-                scenario_fn(ctx, simulator, opts);
-                Ok(())
-            }
+            Some(entry) => (entry.start)(ctx, simulator, opts, extra_args)
+                .map_err(|err| format!("Invalid extra_args for scenario '{name}': {err}")),
             None => Err(format!("Scenario '{name}' not found")),
         }
     }
 
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.scenarios.contains_key(name)
+    }
+
+    /// The peers, messages and timers of a scenario that can be restored from snapshots.
+    #[must_use]
+    pub fn snapshot_registry(&self, name: &str) -> Option<SnapshotRegistry> {
+        self.scenarios.get(name).map(|entry| {
+            let mut registry = SnapshotRegistry::default();
+            registry.register_timer::<FaultTimer>();
+            (entry.snapshot_types)(&mut registry);
+            registry
+        })
+    }
+
     #[must_use]
     pub fn list(&self) -> Vec<(&str, &str)> {
         self.scenarios
             .iter()
-            .map(|(name, (_, desc))| (name.as_str(), *desc))
+            .map(|(name, entry)| (name.as_str(), entry.description))
             .collect()
     }
+
+    /// Returns the options accepted by a scenario in `extra_args`
+    /// paired with their default values.
+    #[must_use]
+    pub fn options(&self, name: &str) -> Vec<(String, String)> {
+        self.scenarios
+            .get(name)
+            .map(|entry| (entry.options)())
+            .unwrap_or_default()
+    }
 }
 
 impl Default for ScenarioRegistry {
@@ -70,3 +115,48 @@ impl Default for ScenarioRegistry {
         Self::new()
     }
 }
+
+/// Deserializes `extra_args` into the scenario's configuration, failing
+/// if any of the provided fields is not known by it, and starts it.
+fn start_scenario<S: Scenario>(
+    ctx: &mut Context,
+    simulator: &Simulator,
+    opts: ExperimentOptions,
+    extra_args: Option<Value>,
+) -> Result<(), String> {
+    let config: S::Config = match extra_args {
+        Some(value) => {
+            let mut unknown_fields: Vec<String> = Vec::new();
+            let config = serde_ignored::deserialize(value, |path| {
+                unknown_fields.push(path.to_string());
+            })
+            .map_err(|err| err.to_string())?;
+
+            if !unknown_fields.is_empty() {
+                return Err(format!(
+                    "unknown fields {unknown_fields:?}, accepted options are {:?}",
+                    scenario_options::<S>()
+                        .into_iter()
+                        .map(|(option, _)| option)
+                        .collect::<Vec<String>>()
+                ));
+            }
+
+            config
+        }
+        None => S::Config::default(),
+    };
+
+    S::start(ctx, simulator, opts, config);
+    Ok(())
+}
+
+fn scenario_options<S: Scenario>() -> Vec<(String, String)> {
+    match serde_json::to_value(S::Config::default()) {
+        Ok(serde_json::Value::Object(fields)) => fields
+            .into_iter()
+            .map(|(option, default)| (option, default.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
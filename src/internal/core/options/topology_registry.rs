@@ -59,6 +59,7 @@ impl TopologyRegistry {
                         topology.n_peers.min(ctx.peers.len()),
                         topology.connections,
                     );
+                    ctx.links.compact();
                 }
                 None => {
                     log::global_warn(format!("Topology '{name}' not found"));
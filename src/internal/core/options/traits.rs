@@ -1,11 +1,18 @@
-use ordered_float::OrderedFloat;
+use serde::{Serialize, de::DeserializeOwned};
 
 use crate::internal::{
     Simulator,
-    core::{Context, experiment::ConnectionInfo, options::ExperimentOptions},
+    core::{
+        Context, experiment::ConnectionInfo, options::ExperimentOptions,
+        snapshot::SnapshotRegistry, time::SimDuration,
+    },
 };
 
 pub trait Scenario {
+    /// Options read from the experiment's `extra_args`, unknown fields are rejected.
+    /// The serialized default value is what `--list-scenarios` shows as accepted options.
+    type Config: DeserializeOwned + Serialize + Default;
+
     fn name() -> &'static str
     where
         Self: Sized;
@@ -14,7 +21,20 @@ pub trait Scenario {
     where
         Self: Sized;
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions);
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: Self::Config,
+    );
+
+    /// Registers the peers, messages and timers of the scenario so that
+    /// its runs can be resumed from snapshots.
+    fn register_snapshot_types(_registry: &mut SnapshotRegistry)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 pub trait Topology {
@@ -30,5 +50,5 @@ pub trait ArrivalTimeCallback {
     where
         Self: Sized;
 
-    fn callback(ctx: &mut Context, from: usize, to: usize) -> Option<OrderedFloat<f64>>;
+    fn callback(ctx: &mut Context, from: usize, to: usize) -> Option<SimDuration>;
 }
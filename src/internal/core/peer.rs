@@ -1,15 +1,19 @@
-use super::{Context, Message};
+use super::{Context, Message, snapshot::SavedObject};
 use downcast_rs::{Downcast, impl_downcast};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
 
 // ctx, sender, receiver, msg
 type OnMessageReceiveCallback = fn(&mut Context, usize, usize, &dyn Message) -> ();
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PeerInfo {
     id: usize,
     instantiated: bool,
     alive: bool,
     pub position: (f64, f64, f64),
+    // restored peers take the callback of the peer created by the scenario
+    #[serde(skip, default = "default_on_message_receive_callback")]
     pub on_message_receive: OnMessageReceiveCallback,
 }
 
@@ -22,6 +26,10 @@ fn default_on_message_receive(
     // Does nothing.
 }
 
+fn default_on_message_receive_callback() -> OnMessageReceiveCallback {
+    default_on_message_receive
+}
+
 impl PeerInfo {
     #[must_use]
     pub fn new(x: f64, y: f64, z: f64) -> Self {
@@ -41,6 +49,16 @@ impl PeerInfo {
     }
 }
 
+impl Debug for PeerInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("PeerInfo")
+            .field("id", &self.id)
+            .field("alive", &self.alive)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Default for PeerInfo {
     fn default() -> Self {
         Self {
@@ -53,7 +71,7 @@ impl Default for PeerInfo {
     }
 }
 
-pub trait CustomPeer: Downcast {
+pub trait CustomPeer: Downcast + Send {
     fn get_peer(&self) -> &PeerInfo;
     fn get_peer_mut(&mut self) -> &mut PeerInfo;
     fn instantiate(&mut self, id: usize) -> usize {
@@ -78,5 +96,15 @@ pub trait CustomPeer: Downcast {
     fn is_alive(&self) -> bool {
         self.get_peer().alive
     }
+    /// Describes the peer's state, shown by the debugger. Only the
+    /// `PeerInfo` is shown unless the peer overrides it.
+    fn inspect(&self) -> String {
+        format!("{:#?}", self.get_peer())
+    }
+    /// Saves the peer in snapshots, `None` if it cannot be saved.
+    /// Use the `snapshot` option of `define_custom_peer!`.
+    fn snapshot(&self) -> Option<SavedObject> {
+        None
+    }
 }
 impl_downcast!(CustomPeer);
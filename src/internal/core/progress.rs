@@ -0,0 +1,178 @@
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use super::time::SimTime;
+
+// the wall clock is only read every this many events
+const CHECK_EVERY: u64 = 1024;
+// how often the progress is shown, on a line that is rewritten in a terminal
+const TERMINAL_REFRESH: Duration = Duration::from_millis(250);
+const LINE_REFRESH: Duration = Duration::from_secs(5);
+
+/// How much work a run took, reported when its simulation loop finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RunStats {
+    pub events: u64,
+    /// Wall clock seconds the simulation loop took
+    pub wall_time: f64,
+    pub events_per_second: f64,
+    /// Most events that were pending at once
+    pub peak_queue: usize,
+}
+
+impl RunStats {
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl Display for RunStats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} events in {:.3}s ({:.0} events/s), peak queue of {} events",
+            self.events, self.wall_time, self.events_per_second, self.peak_queue
+        )
+    }
+}
+
+/// Shows how far the simulation loop of a run is on the console while it runs,
+/// and counts its events to report them when it finishes.
+#[derive(Debug)]
+pub struct Progress {
+    /// Names the run, e.g. its experiment and repetition
+    label: String,
+    /// The progress is not shown while the run goes, only its statistics when it finishes
+    summary_only: bool,
+    in_terminal: bool,
+    deadline: Option<SimTime>,
+    start_clock: SimTime,
+    started: Instant,
+    last_shown: Instant,
+    events: u64,
+    peak_queue: usize,
+}
+
+impl Progress {
+    #[must_use]
+    pub fn new(label: impl Into<String>, summary_only: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            label: label.into(),
+            summary_only,
+            in_terminal: io::stderr().is_terminal(),
+            deadline: None,
+            start_clock: SimTime::ZERO,
+            started: now,
+            last_shown: now,
+            events: 0,
+            peak_queue: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Called when the simulation loop starts, runs without a deadline have no ETA.
+    pub fn start(&mut self, clock: SimTime, deadline: Option<SimTime>, queue_len: usize) {
+        let now = Instant::now();
+        self.deadline = deadline.filter(|deadline| *deadline > clock);
+        self.start_clock = clock;
+        self.started = now;
+        self.last_shown = now;
+        self.events = 0;
+        self.peak_queue = queue_len;
+    }
+
+    /// Counts a processed event, `queue_len` being the events still pending.
+    pub fn record(&mut self, clock: SimTime, queue_len: usize) {
+        self.events += 1;
+        self.peak_queue = self.peak_queue.max(queue_len);
+        if self.summary_only || !self.events.is_multiple_of(CHECK_EVERY) {
+            return;
+        }
+
+        let refresh = if self.in_terminal {
+            TERMINAL_REFRESH
+        } else {
+            LINE_REFRESH
+        };
+        if self.last_shown.elapsed() >= refresh {
+            self.last_shown = Instant::now();
+            self.show(clock, queue_len);
+        }
+    }
+
+    fn show(&self, clock: SimTime, queue_len: usize) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let mut line = format!("{}: {:.3}s", self.label, clock.as_secs());
+        if let Some(deadline) = self.deadline {
+            let done = (clock - self.start_clock).as_secs();
+            let total = (deadline - self.start_clock).as_secs();
+            line.push_str(&format!(" of {deadline}s ({:.1}%)", 100.0 * done / total));
+            if done > 0.0 {
+                let eta = elapsed * (total - done) / done;
+                line.push_str(&format!(", ETA {eta:.0}s"));
+            }
+        }
+        line.push_str(&format!(
+            ", {:.0} events/s, {queue_len} queued",
+            self.events as f64 / elapsed
+        ));
+
+        let mut stderr = io::stderr().lock();
+        if self.in_terminal {
+            // clears what is left of the previous line
+            write!(stderr, "\r{line}\x1b[K").ok();
+        } else {
+            writeln!(stderr, "{line}").ok();
+        }
+        stderr.flush().ok();
+    }
+
+    /// The statistics of the run, its progress line is left for the logs that follow.
+    pub fn finish(&mut self) -> RunStats {
+        if !self.summary_only && self.in_terminal && self.last_shown != self.started {
+            eprintln!();
+        }
+        let wall_time = self.started.elapsed().as_secs_f64();
+        RunStats {
+            events: self.events,
+            wall_time,
+            events_per_second: if wall_time > 0.0 {
+                self.events as f64 / wall_time
+            } else {
+                0.0
+            },
+            peak_queue: self.peak_queue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_count_events_and_the_longest_queue() {
+        let mut progress = Progress::new("run", true);
+        progress.start(SimTime::ZERO, Some(SimTime::from_secs(1.0)), 3);
+        for (i, queue_len) in [2, 7, 4, 0].into_iter().enumerate() {
+            progress.record(SimTime::from_secs(0.1 * i as f64), queue_len);
+        }
+
+        let stats = progress.finish();
+        assert_eq!(stats.events, 4);
+        assert_eq!(stats.peak_queue, 7);
+        assert!(stats.wall_time >= 0.0);
+        assert_eq!(stats.to_json()["events"], 4);
+    }
+}
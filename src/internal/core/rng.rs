@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// Derives the seed of a repetition from the experiment's seed.
+/// The first repetition uses the experiment's seed so that single
+/// repetition experiments keep reproducing the same runs.
+#[must_use]
+pub fn derive_seed(master_seed: u64, index: u64) -> u64 {
+    if index == 0 {
+        return master_seed;
+    }
+
+    // SplitMix64 finalizer
+    let mut z = master_seed.wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Independent random number streams derived from the context's seed,
+/// drawing from one of them does not change the values drawn from the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RngStream {
+    /// Decides which messages are lost due to the drop rate
+    NetworkLoss,
+    /// Samples the jitter added to message delays
+    Jitter,
+    /// Decides which messages are duplicated
+    Duplication,
+    /// Used when generating topologies and placing peers
+    Topology,
+    /// Used by the application logic of a peer
+    Peer(usize),
+}
+
+impl RngStream {
+    /// The ChaCha stream id, 0 is left for `Context::rng`.
+    #[must_use]
+    pub fn stream_id(self) -> u64 {
+        match self {
+            RngStream::NetworkLoss => 1,
+            RngStream::Jitter => 2,
+            RngStream::Duplication => 3,
+            RngStream::Topology => 4,
+            RngStream::Peer(id) => 5 + id as u64,
+        }
+    }
+}
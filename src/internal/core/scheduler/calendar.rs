@@ -0,0 +1,347 @@
+use super::Scheduler;
+use crate::internal::core::{
+    events::{Event, EventType},
+    time::SimTime,
+};
+
+const MIN_BUCKETS: usize = 2;
+// how many of the earliest events are used to estimate the width of the buckets
+const WIDTH_SAMPLE: usize = 25;
+
+/// Where an event is kept in the pool, with the keys it is ordered by.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    timestamp: SimTime,
+    id: u64,
+    slot: usize,
+}
+
+impl Entry {
+    fn key(&self) -> (SimTime, u64) {
+        (self.timestamp, self.id)
+    }
+}
+
+/// A calendar queue (R. Brown, 1988). Events are hashed by timestamp into buckets
+/// that are `width` long, a lap through every bucket being a year. The next event
+/// is found by looking at the buckets of the current year in turn, which takes
+/// O(1) on average when each bucket holds a few events. The buckets double or
+/// halve with the number of events and their width is estimated again.
+///
+/// The events are kept in a pool whose slots are reused, the buckets only hold
+/// their keys, so a warmed up queue does not allocate to schedule events.
+#[derive(Debug)]
+pub struct CalendarScheduler {
+    pool: Vec<Option<EventType>>,
+    free: Vec<usize>,
+    // each bucket is sorted from the latest event to the earliest
+    buckets: Vec<Vec<Entry>>,
+    // the entries being moved between buckets on a resize
+    moved: Vec<Entry>,
+    width: f64,
+    // bucket of the earliest event counted from time 0, across years, no event is before it
+    current: i64,
+    len: usize,
+}
+
+impl Default for CalendarScheduler {
+    fn default() -> Self {
+        Self {
+            pool: Vec::new(),
+            free: Vec::new(),
+            buckets: vec![Vec::new(); MIN_BUCKETS],
+            moved: Vec::new(),
+            width: 1.0,
+            current: 0,
+            len: 0,
+        }
+    }
+}
+
+impl CalendarScheduler {
+    /// The bucket of `time` counted from time 0, NaN and infinite times are at the ends.
+    fn time_bucket(&self, time: SimTime) -> i64 {
+        let secs = time.as_secs();
+        if secs.is_nan() {
+            return if secs.is_sign_negative() {
+                i64::MIN
+            } else {
+                i64::MAX
+            };
+        }
+        // saturates for times too far from 0
+        (secs / self.width).floor() as i64
+    }
+
+    fn bucket_index(&self, time_bucket: i64) -> usize {
+        time_bucket.rem_euclid(self.buckets.len() as i64) as usize
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        let index = self.bucket_index(self.time_bucket(entry.timestamp));
+        let bucket = &mut self.buckets[index];
+        let position = bucket.partition_point(|other| other.key() > entry.key());
+        bucket.insert(position, entry);
+    }
+
+    /// The bucket holding the earliest event.
+    fn locate(&self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut time_bucket = self.current;
+        for _ in 0..self.buckets.len() {
+            let index = self.bucket_index(time_bucket);
+            if let Some(earliest) = self.buckets[index].last()
+                && self.time_bucket(earliest.timestamp) <= time_bucket
+            {
+                return Some(index);
+            }
+            let Some(next) = time_bucket.checked_add(1) else {
+                break;
+            };
+            time_bucket = next;
+        }
+
+        // no event this year, the earliest one is searched in every bucket
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, bucket)| bucket.last().map(|earliest| (index, earliest.key())))
+            .min_by_key(|&(_, key)| key)
+            .map(|(index, _)| index)
+    }
+
+    fn take(&mut self, entry: Entry) -> Option<EventType> {
+        self.len -= 1;
+        self.free.push(entry.slot);
+        self.pool[entry.slot].take()
+    }
+
+    fn resize(&mut self, n_buckets: usize) {
+        let mut moved = std::mem::take(&mut self.moved);
+        for bucket in &mut self.buckets {
+            moved.append(bucket);
+        }
+        if let Some(width) = estimate_width(&mut moved) {
+            self.width = width;
+        }
+
+        self.buckets.resize_with(n_buckets, Vec::new);
+        if let Some(earliest) = moved.iter().min_by_key(|entry| entry.key()) {
+            self.current = self.time_bucket(earliest.timestamp);
+        }
+        for entry in moved.drain(..) {
+            self.insert(entry);
+        }
+        self.moved = moved;
+    }
+}
+
+/// Three times the average separation of the earliest events, leaving out the
+/// separations more than twice the average. If they are simultaneous, the
+/// separation is averaged over every event instead.
+fn estimate_width(entries: &mut [Entry]) -> Option<f64> {
+    let sample = entries.len().min(WIDTH_SAMPLE);
+    if sample < 2 {
+        return None;
+    }
+    entries.select_nth_unstable_by_key(sample - 1, Entry::key);
+    let earliest = &mut entries[..sample];
+    earliest.sort_unstable_by_key(Entry::key);
+
+    let separations: Vec<f64> = earliest
+        .windows(2)
+        .map(|pair| pair[1].timestamp.as_secs() - pair[0].timestamp.as_secs())
+        .collect();
+    let average = separations.iter().sum::<f64>() / separations.len() as f64;
+    let close: Vec<f64> = separations
+        .into_iter()
+        .filter(|&separation| separation <= 2.0 * average)
+        .collect();
+    let mut width = 3.0 * close.iter().sum::<f64>() / close.len() as f64;
+
+    if !(width.is_finite() && width > 0.0) {
+        let first = entries.iter().map(|entry| entry.timestamp).min()?;
+        let last = entries.iter().map(|entry| entry.timestamp).max()?;
+        width = 3.0 * (last - first).as_secs() / entries.len() as f64;
+    }
+    (width.is_finite() && width > 0.0).then_some(width)
+}
+
+impl Scheduler for CalendarScheduler {
+    fn push(&mut self, event: EventType) {
+        let timestamp = event.timestamp();
+        let id = event.id();
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.pool[slot] = Some(event);
+                slot
+            }
+            None => {
+                self.pool.push(Some(event));
+                self.pool.len() - 1
+            }
+        };
+
+        let time_bucket = self.time_bucket(timestamp);
+        if self.len == 0 || time_bucket < self.current {
+            self.current = time_bucket;
+        }
+        self.insert(Entry {
+            timestamp,
+            id,
+            slot,
+        });
+        self.len += 1;
+
+        if self.len > 2 * self.buckets.len() {
+            self.resize(2 * self.buckets.len());
+        }
+    }
+
+    fn pop(&mut self) -> Option<EventType> {
+        let index = self.locate()?;
+        let entry = self.buckets[index].pop()?;
+        self.current = self.time_bucket(entry.timestamp);
+        let event = self.take(entry);
+
+        if self.buckets.len() > MIN_BUCKETS && self.len < self.buckets.len() / 2 {
+            self.resize(self.buckets.len() / 2);
+        }
+        event
+    }
+
+    fn peek(&self) -> Option<&EventType> {
+        let entry = self.buckets[self.locate()?].last()?;
+        self.pool[entry.slot].as_ref()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &EventType> + '_> {
+        Box::new(self.pool.iter().flatten())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn remove(&mut self, id: u64) -> Option<EventType> {
+        let (index, position) = self
+            .buckets
+            .iter()
+            .enumerate()
+            .find_map(|(index, bucket)| {
+                bucket
+                    .iter()
+                    .position(|entry| entry.id == id)
+                    .map(|position| (index, position))
+            })?;
+        let entry = self.buckets[index].remove(position);
+        self.take(entry)
+    }
+
+    fn drain(&mut self) -> Vec<EventType> {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.free.clear();
+        self.len = 0;
+        self.pool.drain(..).flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::internal::core::{
+        Context,
+        events::{Timer, TimerEvent},
+        scheduler::HeapScheduler,
+    };
+
+    #[derive(Debug)]
+    struct Noop;
+
+    impl Timer for Noop {
+        fn fire(&self, _ctx: &mut Context) {}
+    }
+
+    fn event(secs: f64, id: u64) -> EventType {
+        let mut event = TimerEvent::create(SimTime::from_secs(secs), Box::new(Noop));
+        event.set_id(id);
+        event
+    }
+
+    fn id_of(event: Option<&EventType>) -> Option<u64> {
+        event.map(Event::id)
+    }
+
+    #[test]
+    fn events_come_out_in_the_order_of_the_heap() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut calendar = CalendarScheduler::default();
+        let mut heap = HeapScheduler::default();
+        let mut clock = 0.0;
+
+        for id in 0..20_000 {
+            let secs = match rng.random_range(0..6) {
+                // ticks and simultaneous events
+                0 => clock + 0.001,
+                1 => clock,
+                2 => clock + rng.random_range(0.0..0.05),
+                // far in the future or before the last event
+                3 => clock + rng.random_range(10.0..1000.0),
+                4 => clock - rng.random_range(0.0..1.0),
+                _ => (clock * 1000.0_f64).round() / 1000.0 + 0.002,
+            };
+            calendar.push(event(secs, id));
+            heap.push(event(secs, id));
+
+            for _ in 0..rng.random_range(0..3) {
+                assert_eq!(id_of(calendar.peek()), id_of(heap.peek()));
+                let next = calendar.pop();
+                assert_eq!(id_of(next.as_ref()), id_of(heap.pop().as_ref()));
+                if let Some(next) = next {
+                    clock = next.timestamp().as_secs();
+                }
+            }
+            if id % 97 == 0 {
+                let removed = rng.random_range(0..=id);
+                assert_eq!(
+                    id_of(calendar.remove(removed).as_ref()),
+                    id_of(heap.remove(removed).as_ref())
+                );
+            }
+        }
+
+        assert_eq!(calendar.len(), heap.len());
+        while let Some(next) = heap.pop() {
+            assert_eq!(id_of(calendar.pop().as_ref()), Some(next.id()));
+        }
+        assert!(calendar.is_empty());
+    }
+
+    #[test]
+    fn slots_of_taken_events_are_reused() {
+        let mut calendar = CalendarScheduler::default();
+        for round in 0..100_u32 {
+            for i in 0..10 {
+                calendar.push(event(f64::from(round), u64::from(round * 10 + i)));
+            }
+            for _ in 0..10 {
+                calendar.pop();
+            }
+        }
+        assert!(calendar.pool.len() <= 10);
+
+        calendar.push(event(-5.0, 1));
+        calendar.push(event(f64::INFINITY, 2));
+        calendar.push(event(3.0, 0));
+        assert_eq!(calendar.drain().len(), 3);
+        assert!(calendar.pop().is_none());
+    }
+}
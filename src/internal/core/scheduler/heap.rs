@@ -0,0 +1,35 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::Scheduler;
+use crate::internal::core::events::EventType;
+
+#[derive(Debug, Default)]
+pub struct HeapScheduler {
+    events: BinaryHeap<Reverse<EventType>>,
+}
+
+impl Scheduler for HeapScheduler {
+    fn push(&mut self, event: EventType) {
+        self.events.push(Reverse(event));
+    }
+
+    fn pop(&mut self) -> Option<EventType> {
+        self.events.pop().map(|Reverse(event)| event)
+    }
+
+    fn peek(&self) -> Option<&EventType> {
+        self.events.peek().map(|Reverse(event)| event)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &EventType> + '_> {
+        Box::new(self.events.iter().map(|Reverse(event)| event))
+    }
+
+    fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    fn drain(&mut self) -> Vec<EventType> {
+        self.events.drain().map(|Reverse(event)| event).collect()
+    }
+}
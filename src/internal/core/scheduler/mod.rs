@@ -0,0 +1,83 @@
+mod calendar;
+mod heap;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use super::events::{Event, EventType};
+
+pub use calendar::CalendarScheduler;
+pub use heap::HeapScheduler;
+
+/// The queue of pending events. Events are taken by timestamp and then by id,
+/// whatever the implementation, so runs do not depend on the scheduler.
+pub trait Scheduler: Send {
+    fn push(&mut self, event: EventType);
+
+    /// Takes the earliest event out of the queue.
+    fn pop(&mut self) -> Option<EventType>;
+
+    /// The event that `pop` returns next.
+    fn peek(&self) -> Option<&EventType>;
+
+    /// The events in the queue, in no particular order.
+    fn iter(&self) -> Box<dyn Iterator<Item = &EventType> + '_>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes the event with `id` out of the queue, whatever its position.
+    fn remove(&mut self, id: u64) -> Option<EventType> {
+        let mut skipped = Vec::new();
+        let mut removed = None;
+        while let Some(event) = self.pop() {
+            if event.id() == id {
+                removed = Some(event);
+                break;
+            }
+            skipped.push(event);
+        }
+        for event in skipped {
+            self.push(event);
+        }
+        removed
+    }
+
+    /// Takes every event out of the queue, in no particular order.
+    fn drain(&mut self) -> Vec<EventType>;
+}
+
+/// Which scheduler keeps the pending events of a run.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+    /// A binary heap, O(log n) for every operation
+    #[default]
+    Heap,
+    /// A calendar queue, O(1) on average when events are spread evenly in time
+    Calendar,
+}
+
+impl SchedulerKind {
+    #[must_use]
+    pub fn create(self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::Heap => Box::new(HeapScheduler::default()),
+            SchedulerKind::Calendar => Box::new(CalendarScheduler::default()),
+        }
+    }
+}
+
+impl Display for SchedulerKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Heap => "heap",
+            Self::Calendar => "calendar",
+        };
+        write!(f, "{name}")
+    }
+}
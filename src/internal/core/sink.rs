@@ -0,0 +1,351 @@
+use arrow_array::{
+    Array, ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array,
+    cast::AsArray,
+    types::{Float64Type, UInt64Type},
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use clap::ValueEnum;
+use parquet::arrow::{ArrowWriter, arrow_reader::ParquetRecordBatchReaderBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    mem,
+    path::Path,
+    sync::Arc,
+};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsFormat {
+    #[default]
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl MetricsFormat {
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MetricsFormat::Jsonl => "jsonl",
+            MetricsFormat::Csv => "csv",
+            MetricsFormat::Parquet => "parquet",
+        }
+    }
+
+    fn from_path<P: AsRef<Path>>(file_path: P) -> Self {
+        match file_path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => MetricsFormat::Csv,
+            Some("parquet") => MetricsFormat::Parquet,
+            _ => MetricsFormat::Jsonl,
+        }
+    }
+}
+
+/// A single value of a metric, the columns written by the CSV and Parquet sinks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRecord<'a> {
+    pub timestamp: f64,
+    pub title: Cow<'a, str>,
+    pub peer: Option<usize>,
+    pub key: Cow<'a, str>,
+    pub value: f64,
+}
+
+/// Turns a metrics object into (peer, key, value) rows. Keys are the dotted path
+/// to each number, the first object key that is a number is taken as the peer and
+/// removed from the path, so `{"3": {"sent": 1}}` becomes `(Some(3), "sent", 1.0)`.
+#[must_use]
+pub fn flatten_metrics(metrics: &Value) -> Vec<(Option<usize>, String, f64)> {
+    let mut rows = Vec::new();
+    flatten_into(metrics, &mut Vec::new(), None, &mut rows);
+    rows
+}
+
+fn flatten_into(
+    value: &Value,
+    path: &mut Vec<String>,
+    peer: Option<usize>,
+    rows: &mut Vec<(Option<usize>, String, f64)>,
+) {
+    match value {
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                let key = if path.is_empty() {
+                    "value".to_string()
+                } else {
+                    path.join(".")
+                };
+                rows.push((peer, key, number));
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                match key.parse::<usize>() {
+                    Ok(peer_id) if peer.is_none() => {
+                        flatten_into(field, path, Some(peer_id), rows);
+                    }
+                    _ => {
+                        path.push(key.clone());
+                        flatten_into(field, path, peer, rows);
+                        path.pop();
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                path.push(i.to_string());
+                flatten_into(item, path, peer, rows);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+// Rows buffered before a Parquet row group is written
+const PARQUET_BATCH_ROWS: usize = 8192;
+
+pub struct ParquetSink {
+    writer: ArrowWriter<File>,
+    schema: SchemaRef,
+    timestamps: Vec<f64>,
+    titles: Vec<String>,
+    peers: Vec<Option<u64>>,
+    keys: Vec<String>,
+    values: Vec<f64>,
+}
+
+impl ParquetSink {
+    fn new(file: File) -> Result<Self, String> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("timestamp", DataType::Float64, false),
+            Field::new("title", DataType::Utf8, false),
+            Field::new("peer", DataType::UInt64, true),
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        Ok(Self {
+            writer: ArrowWriter::try_new(file, schema.clone(), None).map_err(|e| e.to_string())?,
+            schema,
+            timestamps: Vec::new(),
+            titles: Vec::new(),
+            peers: Vec::new(),
+            keys: Vec::new(),
+            values: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, record: &MetricRecord) -> Result<(), String> {
+        self.timestamps.push(record.timestamp);
+        self.titles.push(record.title.to_string());
+        self.peers.push(record.peer.map(|peer| peer as u64));
+        self.keys.push(record.key.to_string());
+        self.values.push(record.value);
+
+        if self.timestamps.len() >= PARQUET_BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<(), String> {
+        if self.timestamps.is_empty() {
+            return Ok(());
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Float64Array::from(mem::take(&mut self.timestamps))),
+            Arc::new(StringArray::from(mem::take(&mut self.titles))),
+            Arc::new(UInt64Array::from(mem::take(&mut self.peers))),
+            Arc::new(StringArray::from(mem::take(&mut self.keys))),
+            Arc::new(Float64Array::from(mem::take(&mut self.values))),
+        ];
+        let batch =
+            RecordBatch::try_new(self.schema.clone(), columns).map_err(|e| e.to_string())?;
+
+        self.writer.write(&batch).map_err(|e| e.to_string())
+    }
+
+    fn close(mut self) -> Result<(), String> {
+        self.write_batch()?;
+        self.writer.close().map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+/// Where metrics are written. Records are buffered and only
+/// written to disk when enough accumulate or the sink is closed.
+pub enum MetricsSink {
+    Jsonl(BufWriter<File>),
+    Csv(Box<csv::Writer<File>>),
+    Parquet(Box<ParquetSink>),
+}
+
+impl MetricsSink {
+    pub fn create<P: AsRef<Path>>(file_path: P, format: MetricsFormat) -> io::Result<Self> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = file_path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = File::create(&file_path)?;
+
+        Ok(match format {
+            MetricsFormat::Jsonl => MetricsSink::Jsonl(BufWriter::new(file)),
+            MetricsFormat::Csv => {
+                // the header is written here so that a resumed file does not repeat it
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(file);
+                writer.write_record(["timestamp", "title", "peer", "key", "value"])?;
+                MetricsSink::Csv(Box::new(writer))
+            }
+            MetricsFormat::Parquet => {
+                MetricsSink::Parquet(Box::new(ParquetSink::new(file).map_err(io::Error::other)?))
+            }
+        })
+    }
+
+    pub fn write_record(&mut self, record: &MetricRecord) -> Result<(), String> {
+        match self {
+            MetricsSink::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, record).map_err(|e| e.to_string())?;
+                writeln!(writer).map_err(|e| e.to_string())
+            }
+            MetricsSink::Csv(writer) => writer.serialize(record).map_err(|e| e.to_string()),
+            MetricsSink::Parquet(sink) => sink.push(record),
+        }
+    }
+
+    /// Writes a metrics object, JSON lines keep it nested under
+    /// `metrics` while the other formats write a record per value.
+    pub fn write_metrics(
+        &mut self,
+        timestamp: f64,
+        title: &str,
+        metrics: &Value,
+    ) -> Result<(), String> {
+        if let MetricsSink::Jsonl(writer) = self {
+            let line = json!({
+                "title": title,
+                "timestamp": timestamp,
+                "metrics": metrics
+            });
+            return writeln!(writer, "{line}").map_err(|e| e.to_string());
+        }
+
+        for (peer, key, value) in flatten_metrics(metrics) {
+            self.write_record(&MetricRecord {
+                timestamp,
+                title: Cow::Borrowed(title),
+                peer,
+                key: Cow::Owned(key),
+                value,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the sink and returns its file, Parquet files are only
+    /// valid once closed so `None` is returned for them.
+    pub fn file(&mut self) -> io::Result<Option<&File>> {
+        match self {
+            MetricsSink::Jsonl(writer) => {
+                writer.flush()?;
+                Ok(Some(writer.get_ref()))
+            }
+            MetricsSink::Csv(writer) => {
+                writer.flush()?;
+                Ok(Some(writer.get_ref()))
+            }
+            MetricsSink::Parquet(_) => Ok(None),
+        }
+    }
+
+    pub fn close(self) -> Result<(), String> {
+        match self {
+            MetricsSink::Jsonl(mut writer) => writer.flush().map_err(|e| e.to_string()),
+            MetricsSink::Csv(mut writer) => writer.flush().map_err(|e| e.to_string()),
+            MetricsSink::Parquet(sink) => sink.close(),
+        }
+    }
+}
+
+/// Reads the records of a metrics file written in any format, chosen by its
+/// extension. Nested JSON lines are flattened like the other formats.
+pub fn read_records<P: AsRef<Path>>(file_path: P) -> Result<Vec<MetricRecord<'static>>, String> {
+    let file = File::open(&file_path).map_err(|e| e.to_string())?;
+
+    match MetricsFormat::from_path(&file_path) {
+        MetricsFormat::Csv => csv::Reader::from_reader(file)
+            .deserialize()
+            .map(|record| record.map_err(|e| e.to_string()))
+            .collect(),
+        MetricsFormat::Parquet => read_parquet(file),
+        MetricsFormat::Jsonl => {
+            let mut records = Vec::new();
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| e.to_string())?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let value: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+                match value.get("metrics") {
+                    Some(metrics) => {
+                        let timestamp = value["timestamp"].as_f64().unwrap_or_default();
+                        let title = value["title"].as_str().unwrap_or_default().to_string();
+                        for (peer, key, value) in flatten_metrics(metrics) {
+                            records.push(MetricRecord {
+                                timestamp,
+                                title: Cow::Owned(title.clone()),
+                                peer,
+                                key: Cow::Owned(key),
+                                value,
+                            });
+                        }
+                    }
+                    None => records.push(serde_json::from_value(value).map_err(|e| e.to_string())?),
+                }
+            }
+            Ok(records)
+        }
+    }
+}
+
+fn read_parquet(file: File) -> Result<Vec<MetricRecord<'static>>, String> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(ParquetRecordBatchReaderBuilder::build)
+        .map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| e.to_string())?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .ok_or_else(|| format!("missing column '{name}'"))
+        };
+
+        let timestamps = column("timestamp")?.as_primitive::<Float64Type>();
+        let titles = column("title")?.as_string::<i32>();
+        let peers = column("peer")?.as_primitive::<UInt64Type>();
+        let keys = column("key")?.as_string::<i32>();
+        let values = column("value")?.as_primitive::<Float64Type>();
+
+        for i in 0..batch.num_rows() {
+            records.push(MetricRecord {
+                timestamp: timestamps.value(i),
+                title: Cow::Owned(titles.value(i).to_string()),
+                peer: (!peers.is_null(i)).then(|| peers.value(i) as usize),
+                key: Cow::Owned(keys.value(i).to_string()),
+                value: values.value(i),
+            });
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,360 @@
+use indexmap::IndexMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::{
+    any,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use super::{
+    Context, Message,
+    events::{Event, EventType, MessageDeliveryEvent, Timer, TimerEvent},
+    experiment::Jitter,
+    faults::FaultPlan,
+    graph::Links,
+    log,
+    metrics::SavedMetrics,
+    peer::CustomPeer,
+    rng::RngStream,
+    stats::SavedNetworkStats,
+    time::SimTime,
+    utils,
+};
+
+/// A peer, message or timer saved in a snapshot, `kind` is the path of its type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedObject {
+    pub kind: String,
+    pub state: Value,
+}
+
+impl SavedObject {
+    /// Saves an object with the path of its type, used by `impl_snapshot!`.
+    #[must_use]
+    pub fn of<T: Serialize>(object: &T) -> Option<Self> {
+        let kind = any::type_name::<T>();
+        match serde_json::to_value(object) {
+            Ok(state) => Some(Self {
+                kind: kind.to_string(),
+                state,
+            }),
+            Err(e) => {
+                log::global_error(format!("Failed to save {kind}: {e}"));
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SavedEvent {
+    Timer {
+        id: u64,
+        timestamp: f64,
+        timer: SavedObject,
+    },
+    MessageDelivery {
+        id: u64,
+        timestamp: f64,
+        sent_at: f64,
+        sender: usize,
+        receiver: usize,
+        message: SavedObject,
+    },
+}
+
+impl SavedEvent {
+    fn restore(self, registry: &SnapshotRegistry) -> Result<EventType, String> {
+        let (id, mut event) = match self {
+            SavedEvent::Timer {
+                id,
+                timestamp,
+                timer,
+            } => (
+                id,
+                TimerEvent::create(SimTime::from_secs(timestamp), registry.timer(timer)?),
+            ),
+            SavedEvent::MessageDelivery {
+                id,
+                timestamp,
+                sent_at,
+                sender,
+                receiver,
+                message,
+            } => (
+                id,
+                MessageDeliveryEvent::create_boxed(
+                    SimTime::from_secs(timestamp),
+                    SimTime::from_secs(sent_at),
+                    sender,
+                    receiver,
+                    registry.message(message)?,
+                ),
+            ),
+        };
+        event.set_id(id);
+        Ok(event)
+    }
+}
+
+/// Position of a random number generator derived from the context's seed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RngState {
+    pub stream: u64,
+    pub word_pos: u128,
+}
+
+impl RngState {
+    #[must_use]
+    pub fn of(rng: &ChaCha8Rng) -> Self {
+        Self {
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+
+    #[must_use]
+    pub fn restore(&self, seed: u64) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+/// How much of an output file had been written when a snapshot was taken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedFile {
+    pub path: String,
+    pub length: u64,
+}
+
+impl SavedFile {
+    /// `file` must have been flushed.
+    pub fn of(path: &str, mut file: &File) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_string(),
+            length: file.stream_position()?,
+        })
+    }
+
+    /// Replaces the contents of `file` with what had been written to the saved file.
+    pub fn copy_into(&self, mut file: &File) -> io::Result<()> {
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        let copied = io::copy(&mut File::open(&self.path)?.take(self.length), &mut file)?;
+        if copied < self.length {
+            return Err(io::Error::other(format!(
+                "{} has {copied} bytes but {} had been written when the snapshot was taken",
+                self.path, self.length
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The output files of a run, resumed runs start theirs with the saved contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedFiles {
+    pub log: Option<SavedFile>,
+    pub metrics: Option<SavedFile>,
+    pub registry: Option<SavedFile>,
+    pub trace: Option<SavedFile>,
+}
+
+/// Everything needed to continue a run from the moment it was saved. Callbacks and
+/// hooks are not saved, they are set again by the scenario when the run is resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextSnapshot {
+    pub experiment: String,
+    pub repetition: u64,
+    pub seed: u64,
+    pub clock: f64,
+    pub next_event_id: u64,
+    pub current_event_id: u64,
+    pub events: Vec<SavedEvent>,
+    pub rng: RngState,
+    pub rng_streams: Vec<(RngStream, RngState)>,
+    pub links: Links,
+    pub peers: Vec<SavedObject>,
+    pub drop_rate: f64,
+    pub duplicate_rate: f64,
+    pub jitter: Jitter,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faults: Option<FaultPlan>,
+    pub network_stats: SavedNetworkStats,
+    pub metrics: SavedMetrics,
+    pub files: SavedFiles,
+}
+
+impl ContextSnapshot {
+    pub fn read<P: AsRef<Path>>(file_path: P) -> Result<Self, String> {
+        let file = File::open(file_path).map_err(|e| e.to_string())?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, file_path: P) -> Result<(), String> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = file_path.as_ref().parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut writer = BufWriter::new(File::create(file_path).map_err(|e| e.to_string())?);
+
+        serde_json::to_writer(&mut writer, self).map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+type RestoreFn<T> = fn(Value) -> Result<Box<T>, serde_json::Error>;
+
+/// The peers, messages and timers that can be restored from a snapshot, by the path of their type.
+#[derive(Default)]
+pub struct SnapshotRegistry {
+    peers: IndexMap<&'static str, RestoreFn<dyn CustomPeer>>,
+    messages: IndexMap<&'static str, RestoreFn<dyn Message>>,
+    timers: IndexMap<&'static str, RestoreFn<dyn Timer>>,
+}
+
+impl SnapshotRegistry {
+    pub fn register_peer<P: CustomPeer + DeserializeOwned>(&mut self) -> &mut Self {
+        self.peers.insert(any::type_name::<P>(), |state| {
+            Ok(Box::new(serde_json::from_value::<P>(state)?))
+        });
+        self
+    }
+
+    pub fn register_message<M: Message + DeserializeOwned>(&mut self) -> &mut Self {
+        self.messages.insert(any::type_name::<M>(), |state| {
+            Ok(Box::new(serde_json::from_value::<M>(state)?))
+        });
+        self
+    }
+
+    pub fn register_timer<T: Timer + DeserializeOwned>(&mut self) -> &mut Self {
+        self.timers.insert(any::type_name::<T>(), |state| {
+            Ok(Box::new(serde_json::from_value::<T>(state)?))
+        });
+        self
+    }
+
+    pub fn peer(&self, saved: SavedObject) -> Result<Box<dyn CustomPeer>, String> {
+        Self::restore(&self.peers, "peer", saved)
+    }
+
+    pub fn message(&self, saved: SavedObject) -> Result<Box<dyn Message>, String> {
+        Self::restore(&self.messages, "message", saved)
+    }
+
+    pub fn timer(&self, saved: SavedObject) -> Result<Box<dyn Timer>, String> {
+        Self::restore(&self.timers, "timer", saved)
+    }
+
+    /// The name a registered message type is counted under in the network statistics,
+    /// the last segment of its path as given by `Message::type_name`.
+    #[must_use]
+    pub fn message_type(&self, name: &str) -> Option<&'static str> {
+        self.messages
+            .keys()
+            .map(|&path| utils::short_type_name(path))
+            .find(|registered| *registered == name)
+    }
+
+    fn restore<T: ?Sized>(
+        restorers: &IndexMap<&'static str, RestoreFn<T>>,
+        what: &str,
+        saved: SavedObject,
+    ) -> Result<Box<T>, String> {
+        let restore = restorers.get(saved.kind.as_str()).ok_or_else(|| {
+            format!(
+                "The {what} {} is not registered in the scenario's snapshot registry",
+                saved.kind
+            )
+        })?;
+        restore(saved.state)
+            .map_err(|e| format!("Failed to restore the {what} {}: {e}", saved.kind))
+    }
+
+    /// Restores the events of a snapshot.
+    pub fn events(&self, saved: Vec<SavedEvent>) -> Result<Vec<EventType>, String> {
+        saved.into_iter().map(|event| event.restore(self)).collect()
+    }
+}
+
+/// A snapshot waiting to replace the state of a context when its simulation loop starts.
+pub struct Resume {
+    pub snapshot: ContextSnapshot,
+    pub registry: SnapshotRegistry,
+}
+
+/// Identifies a run in its snapshots and saves one each time the clock passes a checkpoint.
+#[derive(Debug, Clone)]
+pub struct CheckpointPlan {
+    pub experiment: String,
+    pub repetition: u64,
+    /// Where checkpoints are written, they are not saved without one
+    pub dir: Option<String>,
+    // sorted from the latest to the earliest
+    times: Vec<SimTime>,
+}
+
+impl CheckpointPlan {
+    #[must_use]
+    pub fn new(
+        experiment: &str,
+        repetition: u64,
+        dir: Option<String>,
+        mut times: Vec<SimTime>,
+    ) -> Self {
+        times.sort_by(|a, b| b.cmp(a));
+        Self {
+            experiment: experiment.to_string(),
+            repetition,
+            dir,
+            times,
+        }
+    }
+}
+
+/// Saves the checkpoints that are due before `next`, the event about to be
+/// processed, so that every event up to the checkpoint's time was processed.
+pub fn save_due_checkpoints(ctx: &mut Context, next: &EventType) {
+    loop {
+        let Some(plan) = ctx.checkpoints.as_mut() else {
+            return;
+        };
+        let Some(&time) = plan.times.last() else {
+            return;
+        };
+        if next.timestamp() <= time {
+            return;
+        }
+        plan.times.pop();
+
+        let Some(dir) = &plan.dir else {
+            continue;
+        };
+        let path = format!(
+            "{dir}/{}_{}_checkpoint_{time}.json",
+            plan.experiment, plan.repetition
+        );
+        match save_snapshot(ctx, &path, Some(next)) {
+            Ok(()) => log::global_internal(format!("Checkpoint at {time} saved to {path}")),
+            Err(e) => log::global_error(format!("Failed to save the checkpoint at {time}: {e}")),
+        }
+    }
+}
+
+/// Saves the state of the context, `next` being an event taken from the queue but not processed.
+pub fn save_snapshot(
+    ctx: &mut Context,
+    path: &str,
+    next: Option<&EventType>,
+) -> Result<(), String> {
+    ctx.snapshot(next)?.write(path)
+}
@@ -0,0 +1,420 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// What happened to a message, used to update the network statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageOutcome {
+    /// A message was sent between connected peers, even if it is later lost
+    Sent,
+    Delivered,
+    /// The message was lost due to the drop rate
+    DroppedLoss,
+    /// The receiver was dead when the message arrived
+    DroppedDead,
+    /// The latency of the message could not be calculated, so it was never scheduled
+    DroppedNoLatency,
+    Duplicated,
+    /// The sender is not connected to the receiver
+    NotConnected,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficCounter {
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+impl TrafficCounter {
+    fn add(&mut self, other: TrafficCounter) {
+        self.messages += other.messages;
+        self.bytes += other.bytes;
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageCounters {
+    pub sent: TrafficCounter,
+    pub delivered: TrafficCounter,
+    pub dropped_loss: TrafficCounter,
+    pub dropped_dead: TrafficCounter,
+    #[serde(default)]
+    pub dropped_no_latency: TrafficCounter,
+    pub duplicated: TrafficCounter,
+    pub not_connected: TrafficCounter,
+}
+
+impl MessageCounters {
+    pub fn record(&mut self, outcome: MessageOutcome, bytes: u64) {
+        let counter = match outcome {
+            MessageOutcome::Sent => &mut self.sent,
+            MessageOutcome::Delivered => &mut self.delivered,
+            MessageOutcome::DroppedLoss => &mut self.dropped_loss,
+            MessageOutcome::DroppedDead => &mut self.dropped_dead,
+            MessageOutcome::DroppedNoLatency => &mut self.dropped_no_latency,
+            MessageOutcome::Duplicated => &mut self.duplicated,
+            MessageOutcome::NotConnected => &mut self.not_connected,
+        };
+        counter.add(TrafficCounter { messages: 1, bytes });
+    }
+
+    fn add(&mut self, other: &MessageCounters) {
+        self.sent.add(other.sent);
+        self.delivered.add(other.delivered);
+        self.dropped_loss.add(other.dropped_loss);
+        self.dropped_dead.add(other.dropped_dead);
+        self.dropped_no_latency.add(other.dropped_no_latency);
+        self.duplicated.add(other.duplicated);
+        self.not_connected.add(other.not_connected);
+    }
+}
+
+/// Counts values into buckets given by their upper bounds,
+/// values larger than every bound go into an overflow bucket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "SavedHistogram", from = "SavedHistogram")]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Histogram {
+    #[must_use]
+    pub fn new(mut bounds: Vec<f64>) -> Self {
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Creates `n` buckets with upper bounds `start`, `start * factor`, `start * factor^2`...
+    #[must_use]
+    pub fn exponential(start: f64, factor: f64, n: usize) -> Self {
+        Self::new(
+            (0..n)
+                .scan(start, |bound, _| {
+                    let current = *bound;
+                    *bound *= factor;
+                    Some(current)
+                })
+                .collect(),
+        )
+    }
+
+    /// Buckets from 10 microseconds up to roughly 84 seconds.
+    #[must_use]
+    pub fn latency() -> Self {
+        Self::exponential(1.0e-5, 2.0, 24)
+    }
+
+    pub fn record(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Adds the values of another histogram with the same buckets.
+    pub fn merge(&mut self, other: &Histogram) {
+        assert_eq!(
+            self.bounds, other.bounds,
+            "Only histograms with the same buckets can be merged"
+        );
+
+        for (count, other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    #[must_use]
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    #[must_use]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    #[must_use]
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    #[must_use]
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Estimates the q-quantile (q in [0.0, 1.0]) as the upper
+    /// bound of the bucket it falls in, capped by the maximum.
+    #[must_use]
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let target = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(self.bounds.get(i).map_or(self.max, |b| b.min(self.max)));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let buckets: Vec<Value> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(i, count)| {
+                json!({
+                    "le": self.bounds.get(i).copied().unwrap_or(f64::INFINITY).to_string(),
+                    "count": count,
+                })
+            })
+            .collect();
+
+        json!({
+            "count": self.count,
+            "sum": self.sum,
+            "min": self.min(),
+            "max": self.max(),
+            "mean": self.mean(),
+            "p50": self.quantile(0.5),
+            "p99": self.quantile(0.99),
+            "buckets": buckets,
+        })
+    }
+}
+
+/// Message statistics collected by the engine. Sent, lost, duplicated and
+/// not connected messages are counted for the sender while delivered and
+/// dropped because the receiver is dead are counted for the receiver.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(into = "SavedNetworkStats")]
+pub struct NetworkStats {
+    counters: IndexMap<(usize, &'static str), MessageCounters>,
+    latency: IndexMap<&'static str, Histogram>,
+}
+
+impl NetworkStats {
+    pub fn record(
+        &mut self,
+        peer_id: usize,
+        message_type: &'static str,
+        outcome: MessageOutcome,
+        bytes: u64,
+    ) {
+        self.counters
+            .entry((peer_id, message_type))
+            .or_default()
+            .record(outcome, bytes);
+    }
+
+    /// Records the delay, in seconds, of a message that was scheduled for delivery.
+    pub fn record_latency(&mut self, message_type: &'static str, latency: f64) {
+        self.latency
+            .entry(message_type)
+            .or_insert_with(Histogram::latency)
+            .record(latency);
+    }
+
+    #[must_use]
+    pub fn total(&self) -> MessageCounters {
+        self.sum(|_, _| true)
+    }
+
+    #[must_use]
+    pub fn peer(&self, peer_id: usize) -> MessageCounters {
+        self.sum(|id, _| id == peer_id)
+    }
+
+    #[must_use]
+    pub fn message_type(&self, message_type: &str) -> MessageCounters {
+        self.sum(|_, name| name == message_type)
+    }
+
+    #[must_use]
+    pub fn peer_message_type(&self, peer_id: usize, message_type: &str) -> MessageCounters {
+        self.sum(|id, name| id == peer_id && name == message_type)
+    }
+
+    /// Latency of every message type together.
+    #[must_use]
+    pub fn latency(&self) -> Histogram {
+        let mut histogram = Histogram::latency();
+        for type_histogram in self.latency.values() {
+            histogram.merge(type_histogram);
+        }
+        histogram
+    }
+
+    #[must_use]
+    pub fn message_type_latency(&self, message_type: &str) -> Option<&Histogram> {
+        self.latency.get(message_type)
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let mut per_peer: IndexMap<String, MessageCounters> = IndexMap::new();
+        let mut per_type: IndexMap<&str, MessageCounters> = IndexMap::new();
+
+        let mut keys: Vec<&(usize, &'static str)> = self.counters.keys().collect();
+        keys.sort_unstable();
+        for key @ (peer_id, message_type) in keys {
+            let counters = &self.counters[key];
+            per_peer
+                .entry(peer_id.to_string())
+                .or_default()
+                .add(counters);
+            per_type.entry(message_type).or_default().add(counters);
+        }
+
+        let latency_per_type: IndexMap<&str, Value> = self
+            .latency
+            .iter()
+            .map(|(name, histogram)| (*name, histogram.to_json()))
+            .collect();
+
+        json!({
+            "total": self.total(),
+            "per_peer": per_peer,
+            "per_type": per_type,
+            "latency": self.latency().to_json(),
+            "latency_per_type": latency_per_type,
+        })
+    }
+
+    /// Restores saved statistics, `message_type` gives the name of each saved message type.
+    pub fn restore(
+        saved: SavedNetworkStats,
+        message_type: impl Fn(&str) -> Option<&'static str>,
+    ) -> Result<Self, String> {
+        let name = |saved_name: &str| {
+            message_type(saved_name).ok_or_else(|| {
+                format!(
+                    "The message type {saved_name} of the network statistics is not registered \
+                     in the scenario's snapshot registry"
+                )
+            })
+        };
+
+        Ok(Self {
+            counters: saved
+                .counters
+                .into_iter()
+                .map(|(peer_id, message_type, counters)| {
+                    Ok(((peer_id, name(&message_type)?), counters))
+                })
+                .collect::<Result<_, String>>()?,
+            latency: saved
+                .latency
+                .into_iter()
+                .map(|(message_type, histogram)| Ok((name(&message_type)?, histogram)))
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    fn sum(&self, filter: impl Fn(usize, &str) -> bool) -> MessageCounters {
+        let mut total = MessageCounters::default();
+        for ((peer_id, message_type), counters) in &self.counters {
+            if filter(*peer_id, message_type) {
+                total.add(counters);
+            }
+        }
+        total
+    }
+}
+
+// JSON has no infinities, the bounds of an empty histogram are saved as null
+#[derive(Serialize, Deserialize)]
+struct SavedHistogram {
+    bounds: Vec<f64>,
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl From<Histogram> for SavedHistogram {
+    fn from(histogram: Histogram) -> Self {
+        Self {
+            min: histogram.min(),
+            max: histogram.max(),
+            bounds: histogram.bounds,
+            counts: histogram.counts,
+            count: histogram.count,
+            sum: histogram.sum,
+        }
+    }
+}
+
+impl From<SavedHistogram> for Histogram {
+    fn from(saved: SavedHistogram) -> Self {
+        Self {
+            bounds: saved.bounds,
+            counts: saved.counts,
+            count: saved.count,
+            sum: saved.sum,
+            min: saved.min.unwrap_or(f64::INFINITY),
+            max: saved.max.unwrap_or(f64::NEG_INFINITY),
+        }
+    }
+}
+
+/// The network statistics of a snapshot, with the names of the message types
+/// as strings until they are matched with the types the scenario registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedNetworkStats {
+    counters: Vec<(usize, String, MessageCounters)>,
+    latency: Vec<(String, Histogram)>,
+}
+
+impl From<NetworkStats> for SavedNetworkStats {
+    fn from(stats: NetworkStats) -> Self {
+        Self {
+            counters: stats
+                .counters
+                .into_iter()
+                .map(|((peer_id, message_type), counters)| {
+                    (peer_id, message_type.to_string(), counters)
+                })
+                .collect(),
+            latency: stats
+                .latency
+                .into_iter()
+                .map(|(message_type, histogram)| (message_type.to_string(), histogram))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Sub},
+    str::FromStr,
+};
+
+/// The unit of a duration, used to read durations and to print times.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Ns,
+    Us,
+    Ms,
+    #[default]
+    S,
+}
+
+impl TimeUnit {
+    /// How many of this unit are in a second.
+    #[must_use]
+    pub fn per_second(self) -> f64 {
+        match self {
+            TimeUnit::Ns => 1e9,
+            TimeUnit::Us => 1e6,
+            TimeUnit::Ms => 1e3,
+            TimeUnit::S => 1.0,
+        }
+    }
+
+    #[must_use]
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeUnit::Ns => "ns",
+            TimeUnit::Us => "us",
+            TimeUnit::Ms => "ms",
+            TimeUnit::S => "s",
+        }
+    }
+}
+
+// the longer suffixes first, `ms` also ends with `s`
+const TIME_SUFFIXES: [(&str, TimeUnit); 5] = [
+    ("ns", TimeUnit::Ns),
+    ("us", TimeUnit::Us),
+    ("µs", TimeUnit::Us),
+    ("ms", TimeUnit::Ms),
+    ("s", TimeUnit::S),
+];
+
+const BANDWIDTH_SUFFIXES: [(&str, f64); 4] =
+    [("kbps", 1e3), ("mbps", 1e6), ("gbps", 1e9), ("bps", 1.0)];
+
+/// Reads a number followed by one of the suffixes, a number alone is in `default` units.
+fn parse_quantity<U: Copy>(
+    text: &str,
+    suffixes: &[(&str, U)],
+    default: U,
+) -> Result<(f64, U), String> {
+    let text = text.trim();
+    let lowercase = text.to_lowercase();
+    let (number, unit) = suffixes
+        .iter()
+        .find_map(|&(suffix, unit)| Some((lowercase.strip_suffix(suffix)?, unit)))
+        .unwrap_or((&lowercase, default));
+
+    number
+        .trim()
+        .parse::<f64>()
+        .map(|value| (value, unit))
+        .map_err(|_| format!("'{text}' is not a number followed by a unit"))
+}
+
+/// A span of simulation time, kept in seconds. Configurations give it as a
+/// number of seconds or with its unit, e.g. `500ms` or `20us`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct SimDuration(f64);
+
+impl SimDuration {
+    pub const ZERO: SimDuration = SimDuration(0.0);
+
+    #[must_use]
+    pub const fn from_secs(secs: f64) -> Self {
+        Self(secs)
+    }
+
+    #[must_use]
+    pub fn from_millis(millis: f64) -> Self {
+        Self::from_unit(millis, TimeUnit::Ms)
+    }
+
+    #[must_use]
+    pub fn from_unit(value: f64, unit: TimeUnit) -> Self {
+        // dividing by an exact power of ten gives the same seconds as writing them
+        Self(value / unit.per_second())
+    }
+
+    #[must_use]
+    pub const fn as_secs(self) -> f64 {
+        self.0
+    }
+
+    #[must_use]
+    pub fn as_unit(self, unit: TimeUnit) -> f64 {
+        self.0 * unit.per_second()
+    }
+
+    #[must_use]
+    pub fn is_negative(self) -> bool {
+        self.0 < 0.0
+    }
+}
+
+/// A moment of the simulation, the time since it started. With a tick time base
+/// it is a whole number of ticks, compared and added as integers.
+#[derive(Clone, Copy)]
+pub struct SimTime(Moment);
+
+// seconds, or a count of ticks of which `per_second` make a second
+#[derive(Clone, Copy)]
+enum Moment {
+    Secs(f64),
+    Ticks { ticks: u64, per_second: u64 },
+}
+
+impl SimTime {
+    pub const ZERO: SimTime = SimTime(Moment::Secs(0.0));
+
+    #[must_use]
+    pub const fn from_secs(secs: f64) -> Self {
+        Self(Moment::Secs(secs))
+    }
+
+    /// The time in seconds, ticks past 2^53 are no longer exact.
+    #[must_use]
+    pub const fn as_secs(self) -> f64 {
+        match self.0 {
+            Moment::Secs(secs) => secs,
+            // dividing keeps times like 20ms the same as when written in seconds
+            Moment::Ticks { ticks, per_second } => ticks as f64 / per_second as f64,
+        }
+    }
+
+    /// The time since the start of the simulation.
+    #[must_use]
+    pub fn elapsed(self) -> SimDuration {
+        SimDuration(self.as_secs())
+    }
+
+    /// Formats the time in `unit`, rounded to the nanosecond, or in seconds without a unit.
+    #[must_use]
+    pub fn display_in(self, unit: Option<TimeUnit>) -> String {
+        match unit {
+            Some(unit) => {
+                let nanos = (self.as_secs() * 1e9).round();
+                format!("{}{}", nanos / (1e9 / unit.per_second()), unit.suffix())
+            }
+            None => self.to_string(),
+        }
+    }
+}
+
+impl Default for SimTime {
+    fn default() -> Self {
+        SimTime::ZERO
+    }
+}
+
+impl fmt::Debug for SimTime {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("SimTime").field(&self.as_secs()).finish()
+    }
+}
+
+// times are ordered like the events of the queue, which must be total
+impl PartialEq for SimTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SimTime {}
+
+impl PartialOrd for SimTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SimTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0, other.0) {
+            (
+                Moment::Ticks { ticks, per_second },
+                Moment::Ticks {
+                    ticks: other_ticks,
+                    per_second: other_per_second,
+                },
+            ) => {
+                // exact even when the resolutions differ
+                let this = u128::from(ticks) * u128::from(other_per_second);
+                this.cmp(&(u128::from(other_ticks) * u128::from(per_second)))
+            }
+            _ => self.as_secs().total_cmp(&other.as_secs()),
+        }
+    }
+}
+
+impl Add<SimDuration> for SimTime {
+    type Output = SimTime;
+
+    fn add(self, duration: SimDuration) -> SimTime {
+        match self.0 {
+            Moment::Secs(secs) => SimTime(Moment::Secs(secs + duration.0)),
+            Moment::Ticks { ticks, per_second } => {
+                // the duration is rounded to whole ticks, which then add exactly
+                let step = (duration.0 * per_second as f64).round() as i64;
+                SimTime(Moment::Ticks {
+                    ticks: ticks.saturating_add_signed(step),
+                    per_second,
+                })
+            }
+        }
+    }
+}
+
+impl Sub for SimTime {
+    type Output = SimDuration;
+
+    fn sub(self, earlier: SimTime) -> SimDuration {
+        match (self.0, earlier.0) {
+            (
+                Moment::Ticks { ticks, per_second },
+                Moment::Ticks {
+                    ticks: earlier_ticks,
+                    per_second: earlier_per_second,
+                },
+            ) if per_second == earlier_per_second => {
+                let diff = i128::from(ticks) - i128::from(earlier_ticks);
+                SimDuration(diff as f64 / per_second as f64)
+            }
+            _ => SimDuration(self.as_secs() - earlier.as_secs()),
+        }
+    }
+}
+
+impl Add for SimDuration {
+    type Output = SimDuration;
+
+    fn add(self, other: SimDuration) -> SimDuration {
+        SimDuration(self.0 + other.0)
+    }
+}
+
+impl AddAssign for SimDuration {
+    fn add_assign(&mut self, other: SimDuration) {
+        self.0 += other.0;
+    }
+}
+
+impl Mul<f64> for SimDuration {
+    type Output = SimDuration;
+
+    fn mul(self, factor: f64) -> SimDuration {
+        SimDuration(self.0 * factor)
+    }
+}
+
+impl Sum for SimDuration {
+    fn sum<I: Iterator<Item = SimDuration>>(iter: I) -> Self {
+        iter.fold(SimDuration::ZERO, Add::add)
+    }
+}
+
+impl Display for SimDuration {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for SimTime {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_secs())
+    }
+}
+
+impl FromStr for SimDuration {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let (value, unit) = parse_quantity(text, &TIME_SUFFIXES, TimeUnit::S)?;
+        Ok(Self::from_unit(value, unit))
+    }
+}
+
+impl FromStr for SimTime {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        text.parse::<SimDuration>()
+            .map(|duration| SimTime::from_secs(duration.0))
+    }
+}
+
+/// The bandwidth of a link, kept in bits per second. Configurations give it as
+/// a number of bits per second or with its unit, e.g. `80kbps` or `1Gbps`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Bandwidth(f64);
+
+impl Bandwidth {
+    #[must_use]
+    pub const fn from_bps(bps: f64) -> Self {
+        Self(bps)
+    }
+
+    #[must_use]
+    pub const fn as_bps(self) -> f64 {
+        self.0
+    }
+
+    /// How long it takes to transmit `bits`.
+    #[must_use]
+    pub fn transmission_time(self, bits: u64) -> SimDuration {
+        SimDuration(bits as f64 / self.0)
+    }
+}
+
+impl Display for Bandwidth {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}bps", self.0)
+    }
+}
+
+impl FromStr for Bandwidth {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let (value, bps) = parse_quantity(text, &BANDWIDTH_SUFFIXES, 1.0)?;
+        Ok(Self(value * bps))
+    }
+}
+
+// the quantities are written as plain numbers in their base unit
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Quantity {
+    Number(f64),
+    Text(String),
+}
+
+macro_rules! impl_quantity_serde {
+    ($name:ident, $as_base:ident, $from_base:ident) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_f64(self.$as_base())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                match Quantity::deserialize(deserializer)? {
+                    Quantity::Number(value) => Ok(Self::$from_base(value)),
+                    Quantity::Text(text) => text.parse().map_err(serde::de::Error::custom),
+                }
+            }
+        }
+    };
+}
+
+/// How the times of a run are kept. With ticks every time is a whole number of
+/// `resolution`, so adding intervals does not accumulate rounding errors and
+/// events due at the same tick are exactly simultaneous.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeBase {
+    #[default]
+    Continuous,
+    Ticks {
+        per_second: u64,
+    },
+}
+
+impl TimeBase {
+    /// Ticks of `resolution`, or continuous times if there is none. A second
+    /// must be a whole number of ticks, like with `1ns` or `10us`.
+    pub fn from_resolution(resolution: Option<SimDuration>) -> Result<Self, String> {
+        let Some(resolution) = resolution else {
+            return Ok(TimeBase::Continuous);
+        };
+
+        // 1 / 1e-9 is not exactly 1e9, the count of ticks is rounded
+        let per_second = (1.0 / resolution.0).round();
+        if !per_second.is_finite()
+            || per_second < 1.0
+            || (per_second * resolution.0 - 1.0).abs() > 1e-9
+        {
+            return Err(format!(
+                "The time resolution should divide a second in whole ticks, not {resolution}."
+            ));
+        }
+        Ok(TimeBase::Ticks {
+            per_second: per_second as u64,
+        })
+    }
+
+    /// The tick of `time`, rounded to the nearest one. Always 0 for continuous times.
+    #[must_use]
+    pub fn ticks(self, time: SimTime) -> u64 {
+        match self {
+            TimeBase::Continuous => 0,
+            TimeBase::Ticks { per_second } => match time.0 {
+                Moment::Ticks {
+                    ticks,
+                    per_second: time_per_second,
+                } if time_per_second == per_second => ticks,
+                _ => (time.as_secs() * per_second as f64).round() as u64,
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn from_ticks(self, ticks: u64) -> SimTime {
+        match self {
+            TimeBase::Continuous => SimTime::ZERO,
+            TimeBase::Ticks { per_second } => SimTime(Moment::Ticks { ticks, per_second }),
+        }
+    }
+
+    /// Moves `time` to its nearest tick.
+    #[must_use]
+    pub fn snap(self, time: SimTime) -> SimTime {
+        match self {
+            TimeBase::Continuous => time,
+            TimeBase::Ticks { .. } => self.from_ticks(self.ticks(time)),
+        }
+    }
+}
+
+impl_quantity_serde!(SimDuration, as_secs, from_secs);
+impl_quantity_serde!(SimTime, as_secs, from_secs);
+impl_quantity_serde!(Bandwidth, as_bps, from_bps);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_read_their_unit() {
+        let parse = |text: &str| text.parse::<SimDuration>().map(SimDuration::as_secs);
+
+        assert_eq!(parse("500ms"), Ok(0.5));
+        assert_eq!(parse("20ms"), Ok(0.02));
+        assert_eq!(parse("1.5 s"), Ok(1.5));
+        assert_eq!(parse("250us"), Ok(0.000_25));
+        assert_eq!(parse("10ns"), Ok(1e-8));
+        assert_eq!(parse("2"), Ok(2.0));
+        assert!(parse("fast").is_err());
+    }
+
+    #[test]
+    fn bandwidths_read_their_unit() {
+        let parse = |text: &str| text.parse::<Bandwidth>().map(Bandwidth::as_bps);
+
+        assert_eq!(parse("80kbps"), Ok(80_000.0));
+        assert_eq!(parse("1Gbps"), Ok(1e9));
+        assert_eq!(parse("100"), Ok(100.0));
+    }
+
+    #[test]
+    fn numbers_and_units_deserialize() {
+        let durations: Vec<SimDuration> = serde_yaml::from_str("[0.02, 20ms, 1]").unwrap();
+        let secs: Vec<f64> = durations.iter().map(|d| d.as_secs()).collect();
+
+        assert_eq!(secs, vec![0.02, 0.02, 1.0]);
+        assert_eq!(serde_yaml::to_string(&durations[1]).unwrap().trim(), "0.02");
+    }
+
+    #[test]
+    fn ticks_do_not_accumulate_rounding_errors() {
+        let interval = SimDuration::from_millis(1.0);
+        let ticks =
+            TimeBase::from_resolution(Some(SimDuration::from_unit(1.0, TimeUnit::Ns))).unwrap();
+
+        let (mut continuous, mut exact) = (SimTime::ZERO, ticks.snap(SimTime::ZERO));
+        for _ in 0..5_000_000 {
+            continuous = continuous + interval;
+            exact = exact + interval;
+        }
+
+        assert_ne!(continuous, SimTime::from_secs(5_000.0));
+        assert_eq!(exact, SimTime::from_secs(5_000.0));
+        assert_eq!(ticks.ticks(exact), 5_000_000_000_000);
+        assert!(TimeBase::from_resolution(Some(SimDuration::ZERO)).is_err());
+        assert!(TimeBase::from_resolution(Some(SimDuration::from_millis(0.3))).is_err());
+    }
+
+    #[test]
+    fn ticks_compare_exactly_past_the_precision_of_seconds() {
+        let ticks =
+            TimeBase::from_resolution(Some(SimDuration::from_unit(1.0, TimeUnit::Ns))).unwrap();
+        let (early, late) = (ticks.from_ticks(1 << 53), ticks.from_ticks((1 << 53) + 1));
+
+        assert_eq!(early.as_secs(), late.as_secs());
+        assert!(early < late);
+        assert_eq!(ticks.ticks(early + (late - early)), (1 << 53) + 1);
+    }
+
+    #[test]
+    fn times_are_shown_in_the_chosen_unit() {
+        let time = SimTime::from_secs(0.0217);
+
+        assert_eq!(time.display_in(None), "0.0217");
+        assert_eq!(time.display_in(Some(TimeUnit::Ms)), "21.7ms");
+        assert_eq!(time.display_in(Some(TimeUnit::Us)), "21700us");
+    }
+}
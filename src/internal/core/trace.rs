@@ -0,0 +1,537 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::log;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    #[default]
+    Jsonl,
+    Binary,
+}
+
+impl TraceFormat {
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TraceFormat::Jsonl => "trace.jsonl",
+            TraceFormat::Binary => "trace.bin",
+        }
+    }
+
+    fn from_path<P: AsRef<Path>>(file_path: P) -> Self {
+        match file_path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("bin") => TraceFormat::Binary,
+            _ => TraceFormat::Jsonl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    Timer,
+    MessageDelivery,
+    /// A message that was sent but never delivered, recorded when it is dropped.
+    MessageDrop,
+}
+
+/// A processed event, fields that do not apply to its kind are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub id: u64,
+    pub timestamp: f64,
+    pub kind: TraceKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<usize>,
+    /// The peer a message is sent to, or the peer a timer acts for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer_type: Option<Cow<'static, str>>,
+    /// When the message was sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<f64>,
+    /// Why the message was dropped, `loss`, `dead`, `not_connected` or `no_latency`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<Cow<'static, str>>,
+}
+
+impl Display for TraceRecord {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "#{} at {} ", self.id, self.timestamp)?;
+        match self.kind {
+            TraceKind::Timer => {
+                write!(
+                    f,
+                    "timer {}",
+                    self.timer_type.as_deref().unwrap_or_default()
+                )?;
+                match self.receiver {
+                    Some(peer) => write!(f, " of peer {peer}"),
+                    None => Ok(()),
+                }
+            }
+            TraceKind::MessageDelivery => write!(
+                f,
+                "delivery of {} ({} bytes) from {} to {}",
+                self.message_type.as_deref().unwrap_or_default(),
+                self.message_size.unwrap_or_default(),
+                self.sender.map_or("?".to_string(), |s| s.to_string()),
+                self.receiver.map_or("?".to_string(), |r| r.to_string()),
+            ),
+            TraceKind::MessageDrop => write!(
+                f,
+                "drop of {} ({} bytes) from {} to {}, {}",
+                self.message_type.as_deref().unwrap_or_default(),
+                self.message_size.unwrap_or_default(),
+                self.sender.map_or("?".to_string(), |s| s.to_string()),
+                self.receiver.map_or("?".to_string(), |r| r.to_string()),
+                self.reason.as_deref().unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+// Identifies binary trace files, followed by the records
+const BINARY_MAGIC: &[u8; 8] = b"TDESTRC3";
+const NO_PEER: u64 = u64::MAX;
+
+pub struct TraceWriter {
+    format: TraceFormat,
+    writer: BufWriter<File>,
+    file_location: String,
+}
+
+impl TraceWriter {
+    pub fn create<P: AsRef<Path>>(file_path: P, format: TraceFormat) -> io::Result<Self> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = file_path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(&file_path)?);
+        let file_location = match file_path.as_ref().canonicalize() {
+            Ok(path) => path.to_string_lossy().to_string(),
+            // if cannot get canonicalize path, use relative
+            Err(_) => file_path.as_ref().to_string_lossy().to_string(),
+        };
+
+        if format == TraceFormat::Binary {
+            writer.write_all(BINARY_MAGIC)?;
+        }
+        Ok(Self {
+            format,
+            writer,
+            file_location,
+        })
+    }
+
+    pub fn write(&mut self, record: &TraceRecord) -> io::Result<()> {
+        match self.format {
+            TraceFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)
+            }
+            TraceFormat::Binary => {
+                let w = &mut self.writer;
+                w.write_all(&record.id.to_le_bytes())?;
+                w.write_all(&record.timestamp.to_le_bytes())?;
+                match record.kind {
+                    TraceKind::Timer => {
+                        w.write_all(&[0])?;
+                        w.write_all(&record.receiver.map_or(NO_PEER, |p| p as u64).to_le_bytes())?;
+                        write_str(w, record.timer_type.as_deref().unwrap_or_default())
+                    }
+                    TraceKind::MessageDelivery | TraceKind::MessageDrop => {
+                        let is_drop = record.kind == TraceKind::MessageDrop;
+                        w.write_all(&[if is_drop { 2 } else { 1 }])?;
+                        for peer in [record.sender, record.receiver] {
+                            w.write_all(&peer.map_or(NO_PEER, |p| p as u64).to_le_bytes())?;
+                        }
+                        write_str(w, record.message_type.as_deref().unwrap_or_default())?;
+                        w.write_all(&record.message_size.unwrap_or_default().to_le_bytes())?;
+                        w.write_all(&record.sent_at.unwrap_or(f64::NAN).to_le_bytes())?;
+                        if is_drop {
+                            write_str(w, record.reason.as_deref().unwrap_or_default())?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes the trace and returns its file with its location.
+    pub fn file(&mut self) -> io::Result<(&File, &str)> {
+        self.writer.flush()?;
+        Ok((self.writer.get_ref(), self.file_location.as_str()))
+    }
+}
+
+fn write_str(writer: &mut impl Write, text: &str) -> io::Result<()> {
+    writer.write_all(&(text.len() as u32).to_le_bytes())?;
+    writer.write_all(text.as_bytes())
+}
+
+/// Reads the records of a trace one at a time, the format is given by the file's extension.
+pub struct TraceReader {
+    format: TraceFormat,
+    reader: BufReader<File>,
+}
+
+impl TraceReader {
+    pub fn open<P: AsRef<Path>>(file_path: P) -> io::Result<Self> {
+        let format = TraceFormat::from_path(&file_path);
+        let mut reader = BufReader::new(File::open(file_path)?);
+
+        if format == TraceFormat::Binary {
+            let mut magic = [0; 8];
+            reader.read_exact(&mut magic)?;
+            if &magic != BINARY_MAGIC {
+                return Err(io::Error::other("not a binary trace file"));
+            }
+        }
+        Ok(Self { format, reader })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<TraceRecord>> {
+        match self.format {
+            TraceFormat::Jsonl => {
+                let mut line = String::new();
+                loop {
+                    line.clear();
+                    if self.reader.read_line(&mut line)? == 0 {
+                        return Ok(None);
+                    }
+                    if !line.trim().is_empty() {
+                        return serde_json::from_str(&line)
+                            .map(Some)
+                            .map_err(io::Error::other);
+                    }
+                }
+            }
+            TraceFormat::Binary => {
+                if self.reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+
+                let r = &mut self.reader;
+                let id = u64::from_le_bytes(read_array(r)?);
+                let timestamp = f64::from_le_bytes(read_array(r)?);
+                let record = match read_array::<1>(r)? {
+                    [0] => TraceRecord {
+                        id,
+                        timestamp,
+                        kind: TraceKind::Timer,
+                        sender: None,
+                        receiver: read_peer(r)?,
+                        message_type: None,
+                        message_size: None,
+                        timer_type: Some(Cow::Owned(read_str(r)?)),
+                        sent_at: None,
+                        reason: None,
+                    },
+                    [kind @ (1 | 2)] => {
+                        let is_drop = kind == 2;
+                        TraceRecord {
+                            id,
+                            timestamp,
+                            kind: if is_drop {
+                                TraceKind::MessageDrop
+                            } else {
+                                TraceKind::MessageDelivery
+                            },
+                            sender: read_peer(r)?,
+                            receiver: read_peer(r)?,
+                            message_type: Some(Cow::Owned(read_str(r)?)),
+                            message_size: Some(u64::from_le_bytes(read_array(r)?)),
+                            timer_type: None,
+                            sent_at: Some(f64::from_le_bytes(read_array(r)?))
+                                .filter(|sent_at| !sent_at.is_nan()),
+                            reason: if is_drop {
+                                Some(Cow::Owned(read_str(r)?))
+                            } else {
+                                None
+                            },
+                        }
+                    }
+                    [kind] => {
+                        return Err(io::Error::other(format!("unknown event kind {kind}")));
+                    }
+                };
+                Ok(Some(record))
+            }
+        }
+    }
+}
+
+impl Iterator for TraceReader {
+    type Item = io::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_peer(reader: &mut impl Read) -> io::Result<Option<usize>> {
+    Ok(match u64::from_le_bytes(read_array(reader)?) {
+        NO_PEER => None,
+        peer => Some(peer as usize),
+    })
+}
+
+fn read_str(reader: &mut impl Read) -> io::Result<String> {
+    let len = u32::from_le_bytes(read_array(reader)?) as usize;
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(io::Error::other)
+}
+
+/// The first event where a run differs from its recorded trace,
+/// `None` meaning that one of them ended earlier.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub index: u64,
+    pub expected: Option<TraceRecord>,
+    pub actual: Option<TraceRecord>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let describe = |record: &Option<TraceRecord>| {
+            record
+                .as_ref()
+                .map_or("the end of the trace".to_string(), ToString::to_string)
+        };
+        write!(
+            f,
+            "event {} diverged, expected {} but got {}",
+            self.index,
+            describe(&self.expected),
+            describe(&self.actual)
+        )
+    }
+}
+
+/// Compares the events of a run against a recorded trace.
+pub struct TraceVerifier {
+    reader: TraceReader,
+    index: u64,
+    divergence: Option<Divergence>,
+}
+
+impl TraceVerifier {
+    pub fn open<P: AsRef<Path>>(file_path: P) -> io::Result<Self> {
+        Ok(Self {
+            reader: TraceReader::open(file_path)?,
+            index: 0,
+            divergence: None,
+        })
+    }
+
+    fn check(&mut self, actual: Option<&TraceRecord>) {
+        if self.divergence.is_some() {
+            return;
+        }
+
+        let expected = match self.reader.next() {
+            Some(Ok(record)) => Some(record),
+            Some(Err(e)) => {
+                self.divergence = Some(Divergence {
+                    index: self.index,
+                    expected: None,
+                    actual: actual.cloned(),
+                });
+                log::global_error(format!("Failed to read the recorded trace: {e}"));
+                return;
+            }
+            None => None,
+        };
+
+        if expected.as_ref() != actual {
+            self.divergence = Some(Divergence {
+                index: self.index,
+                expected,
+                actual: actual.cloned(),
+            });
+        }
+        if actual.is_some() {
+            self.index += 1;
+        }
+    }
+
+    #[must_use]
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// How many events were compared.
+    #[must_use]
+    pub fn events(&self) -> u64 {
+        self.index
+    }
+}
+
+/// Records the events processed by the engine or verifies them against a previous run.
+pub enum Tracer {
+    Writer(TraceWriter),
+    Verifier(Box<TraceVerifier>),
+    /// Keeps the records in memory, used by tests
+    Memory(Vec<TraceRecord>),
+}
+
+impl Tracer {
+    pub fn record(&mut self, record: &TraceRecord) {
+        match self {
+            Tracer::Writer(writer) => {
+                if let Err(e) = writer.write(record) {
+                    log::global_error(format!("Failed to write trace record: {e}"));
+                }
+            }
+            Tracer::Verifier(verifier) => verifier.check(Some(record)),
+            Tracer::Memory(records) => records.push(record.clone()),
+        }
+    }
+
+    /// Flushes the trace or, when verifying, checks that no recorded events are left.
+    pub fn finish(&mut self) {
+        match self {
+            Tracer::Writer(writer) => {
+                if let Err(e) = writer.flush() {
+                    log::global_error(format!("Failed to write trace file: {e}"));
+                }
+            }
+            Tracer::Verifier(verifier) => verifier.check(None),
+            Tracer::Memory(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<TraceRecord> {
+        let timer = |id, timestamp, peer| TraceRecord {
+            id,
+            timestamp,
+            kind: TraceKind::Timer,
+            sender: None,
+            receiver: peer,
+            message_type: None,
+            message_size: None,
+            timer_type: Some(Cow::Borrowed("StartTimer")),
+            sent_at: None,
+            reason: None,
+        };
+        let message = |id, timestamp, reason: Option<&'static str>| TraceRecord {
+            id,
+            timestamp,
+            kind: if reason.is_some() {
+                TraceKind::MessageDrop
+            } else {
+                TraceKind::MessageDelivery
+            },
+            sender: Some(0),
+            receiver: Some(1),
+            message_type: Some(Cow::Borrowed("Ping")),
+            message_size: Some(64),
+            timer_type: None,
+            sent_at: Some(0.1),
+            reason: reason.map(Cow::Borrowed),
+        };
+
+        vec![
+            timer(0, 0.0, None),
+            timer(1, 0.1, Some(0)),
+            message(2, 0.2, None),
+            message(3, 0.3, Some("loss")),
+        ]
+    }
+
+    fn write(path: &Path, format: TraceFormat, records: &[TraceRecord]) {
+        let mut writer = TraceWriter::create(path, format).unwrap();
+        for record in records {
+            writer.write(record).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    fn temp_path(name: &str, format: TraceFormat) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "tdes_{name}_{}.{}",
+            std::process::id(),
+            format.extension()
+        ))
+    }
+
+    #[test]
+    fn traces_are_read_back_as_written() {
+        for format in [TraceFormat::Jsonl, TraceFormat::Binary] {
+            let path = temp_path("round_trip", format);
+            write(&path, format, &records());
+
+            let read: Vec<TraceRecord> = TraceReader::open(&path)
+                .unwrap()
+                .collect::<io::Result<_>>()
+                .unwrap();
+            assert_eq!(read, records(), "{format:?} trace");
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn verifiers_report_the_first_divergent_event() {
+        let path = temp_path("verified", TraceFormat::Binary);
+        write(&path, TraceFormat::Binary, &records());
+
+        let mut rerun = records();
+        rerun[2].receiver = Some(2);
+        rerun[3].reason = Some(Cow::Borrowed("dead"));
+        let mut tracer = Tracer::Verifier(Box::new(TraceVerifier::open(&path).unwrap()));
+        for record in &rerun {
+            tracer.record(record);
+        }
+        tracer.finish();
+
+        let Tracer::Verifier(verifier) = tracer else {
+            unreachable!()
+        };
+        let divergence = verifier.divergence().expect("the runs should diverge");
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.expected.as_ref(), Some(&records()[2]));
+        assert_eq!(divergence.actual.as_ref(), Some(&rerun[2]));
+
+        // a run that stops early diverges where it ended
+        let mut verifier = TraceVerifier::open(&path).unwrap();
+        verifier.check(Some(&records()[0]));
+        verifier.check(None);
+        let divergence = verifier.divergence().expect("the run should end early");
+        assert_eq!(divergence.index, 1);
+        assert_eq!(divergence.expected.as_ref(), Some(&records()[1]));
+        assert_eq!(divergence.actual, None);
+        fs::remove_file(&path).unwrap();
+    }
+}
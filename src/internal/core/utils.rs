@@ -0,0 +1,28 @@
+/// The name of a type without its path or generic arguments, which have paths of
+/// their own, e.g. `Ping` for `scenarios::ping::Ping` or `Wrapper<scenarios::ping::Ping>`.
+#[must_use]
+pub fn short_type_name(full_name: &str) -> &str {
+    let path = full_name
+        .split_once('<')
+        .map_or(full_name, |(path, _)| path);
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_names_drop_the_path_and_the_generics() {
+        assert_eq!(short_type_name("tdes::scenarios::Ping"), "Ping");
+        assert_eq!(short_type_name("Ping"), "Ping");
+        assert_eq!(
+            short_type_name("tdes::Wrapper<tdes::scenarios::Ping>"),
+            "Wrapper"
+        );
+        assert_eq!(
+            short_type_name("alloc::vec::Vec<core::option::Option<u8>>"),
+            "Vec"
+        );
+    }
+}
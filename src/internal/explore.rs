@@ -0,0 +1,321 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    fs,
+};
+
+use super::cli::utils::write_file_with_dirs;
+use super::core::{
+    interleaving::{Deviation, Interleaving, Schedule},
+    invariants::InvariantViolation,
+    rng,
+    time::SimDuration,
+    trace::TraceRecord,
+};
+
+/// How the orders of concurrent deliveries are explored.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExploreStrategy {
+    /// Every order of the deliveries at the first choice points, depth first
+    #[default]
+    Dfs,
+    /// Schedules that deviate less from the default order first, up to the bound
+    Delay,
+    /// Random priorities for the senders, lowered at `bound - 1` random choice points
+    Pct,
+}
+
+impl Display for ExploreStrategy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Dfs => "dfs",
+            Self::Delay => "delay",
+            Self::Pct => "pct",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExploreOptions {
+    pub strategy: ExploreStrategy,
+    /// Alternatives are only explored at the first choice points of a run, unused by pct
+    pub depth: usize,
+    /// Most deviations of a delay bounded schedule, or the depth of pct
+    pub bound: usize,
+    /// Most simulations run
+    pub runs: usize,
+    /// Deliveries due at most this long after the next event are concurrent with it
+    pub window: SimDuration,
+    /// Seed of the priorities of pct
+    pub seed: u64,
+}
+
+impl Default for ExploreOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ExploreStrategy::Dfs,
+            depth: 20,
+            bound: 2,
+            runs: 1000,
+            window: SimDuration::ZERO,
+            seed: 0,
+        }
+    }
+}
+
+/// A simulation run with an interleaving.
+pub struct ExploredRun {
+    pub interleaving: Interleaving,
+    pub violation: Option<InvariantViolation>,
+}
+
+/// A reordered delivery of a failing schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reordering {
+    pub choice_point: usize,
+    pub clock: f64,
+    /// The event that is processed by default
+    pub instead_of: TraceRecord,
+    pub processed: TraceRecord,
+}
+
+/// The smallest schedule found that violates an invariant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Counterexample {
+    pub window: SimDuration,
+    pub schedule: Schedule,
+    pub reorderings: Vec<Reordering>,
+    pub violation: InvariantViolation,
+}
+
+impl Counterexample {
+    fn new(window: SimDuration, run: ExploredRun) -> Option<Self> {
+        let violation = run.violation?;
+        let schedule = run.interleaving.schedule();
+        let reorderings = schedule
+            .deviations
+            .iter()
+            .map(|deviation| {
+                let point = &run.interleaving.choice_points[deviation.choice_point];
+                Reordering {
+                    choice_point: deviation.choice_point,
+                    clock: point.clock,
+                    instead_of: point.alternatives[0].clone(),
+                    processed: point.alternatives[deviation.alternative].clone(),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            window,
+            schedule,
+            reorderings,
+            violation,
+        })
+    }
+}
+
+impl Display for Counterexample {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invariant '{}' is violated after {} reordered deliveries:",
+            self.violation.invariant,
+            self.reorderings.len()
+        )?;
+        for reordering in &self.reorderings {
+            write!(
+                f,
+                "\n  at choice point {}: {} instead of {}",
+                reordering.choice_point, reordering.processed, reordering.instead_of
+            )?;
+        }
+        write!(f, "\n{}", self.violation)
+    }
+}
+
+/// A failing schedule of a repetition, it can be replayed with its outputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailingSchedule {
+    pub experiment: String,
+    pub repetition: u64,
+    pub seed: u64,
+    pub strategy: ExploreStrategy,
+    pub counterexample: Counterexample,
+}
+
+impl FailingSchedule {
+    pub fn read(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_file_with_dirs(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+/// The outcome of an exploration.
+#[derive(Debug, Clone)]
+pub struct Exploration {
+    pub runs: usize,
+    pub counterexample: Option<Counterexample>,
+}
+
+/// Runs the simulation with different orders of its concurrent deliveries until
+/// an invariant is violated, the failing schedule is then minimized.
+pub fn explore(
+    options: &ExploreOptions,
+    mut run: impl FnMut(Interleaving) -> ExploredRun,
+) -> Exploration {
+    let mut runs = 0;
+    let mut counted = |interleaving: Interleaving| {
+        runs += 1;
+        run(interleaving)
+    };
+
+    let failing = match options.strategy {
+        ExploreStrategy::Dfs => depth_first(options, &mut counted),
+        ExploreStrategy::Delay => delay_bounded(options, &mut counted),
+        ExploreStrategy::Pct => pct(options, &mut counted),
+    };
+    let counterexample = failing
+        .map(|failing| minimize(options.window, failing, &mut counted))
+        .and_then(|failing| Counterexample::new(options.window, failing));
+
+    Exploration {
+        runs,
+        counterexample,
+    }
+}
+
+fn depth_first(
+    options: &ExploreOptions,
+    run: &mut impl FnMut(Interleaving) -> ExploredRun,
+) -> Option<ExploredRun> {
+    let mut schedule = Schedule::default();
+
+    for _ in 0..options.runs {
+        let result = run(Interleaving::new(options.window, schedule));
+        if result.violation.is_some() {
+            return Some(result);
+        }
+
+        // the last explored choice point that has alternatives left
+        let taken = &result.interleaving.taken;
+        let points = &result.interleaving.choice_points;
+        let backtrack = (0..taken.len().min(options.depth))
+            .rev()
+            .find(|&i| taken[i] + 1 < points[i].alternatives.len())?;
+
+        let mut prefix = taken[..backtrack].to_vec();
+        prefix.push(taken[backtrack] + 1);
+        schedule = Schedule::from_taken(&prefix);
+    }
+    None
+}
+
+fn delay_bounded(
+    options: &ExploreOptions,
+    run: &mut impl FnMut(Interleaving) -> ExploredRun,
+) -> Option<ExploredRun> {
+    let mut schedules = VecDeque::from([Schedule::default()]);
+
+    for _ in 0..options.runs {
+        let schedule = schedules.pop_front()?;
+        let result = run(Interleaving::new(options.window, schedule.clone()));
+        if result.violation.is_some() {
+            return Some(result);
+        }
+        if schedule.deviations.len() >= options.bound {
+            continue;
+        }
+
+        // deviations are added after the last one, the run follows the default order there
+        let first = schedule
+            .deviations
+            .last()
+            .map_or(0, |deviation| deviation.choice_point + 1);
+        let points = &result.interleaving.choice_points;
+        for (choice_point, point) in points.iter().enumerate().take(options.depth).skip(first) {
+            for alternative in 1..point.alternatives.len() {
+                let mut deviated = schedule.clone();
+                deviated.deviations.push(Deviation {
+                    choice_point,
+                    alternative,
+                });
+                schedules.push_back(deviated);
+            }
+        }
+    }
+    None
+}
+
+fn pct(
+    options: &ExploreOptions,
+    run: &mut impl FnMut(Interleaving) -> ExploredRun,
+) -> Option<ExploredRun> {
+    // the default order tells how many choice points the change points are drawn from
+    let first = run(Interleaving::new(options.window, Schedule::default()));
+    if first.violation.is_some() {
+        return Some(first);
+    }
+    let length = first.interleaving.choice_points.len();
+
+    (1..options.runs as u64)
+        .map(|i| {
+            let seed = rng::derive_seed(options.seed, i);
+            run(Interleaving::pct(
+                options.window,
+                seed,
+                options.bound.max(1) as u64,
+                length,
+            ))
+        })
+        .find(|result| result.violation.is_some())
+}
+
+/// Removes the deviations of a failing run while it still violates the same invariant.
+pub fn minimize(
+    window: SimDuration,
+    failing: ExploredRun,
+    run: &mut impl FnMut(Interleaving) -> ExploredRun,
+) -> ExploredRun {
+    let Some(invariant) = failing
+        .violation
+        .as_ref()
+        .map(|violation| violation.invariant.clone())
+    else {
+        return failing;
+    };
+
+    let mut smallest = failing;
+    'shrink: loop {
+        let deviations = smallest.interleaving.schedule().deviations;
+        for removed in 0..deviations.len() {
+            let mut candidate = deviations.clone();
+            candidate.remove(removed);
+
+            let result = run(Interleaving::new(
+                window,
+                Schedule {
+                    deviations: candidate,
+                },
+            ));
+            let fails = result
+                .violation
+                .as_ref()
+                .is_some_and(|violation| violation.invariant == invariant);
+            if fails && result.interleaving.schedule().deviations.len() < deviations.len() {
+                smallest = result;
+                continue 'shrink;
+            }
+        }
+        return smallest;
+    }
+}
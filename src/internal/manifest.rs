@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::cli::utils::write_file_with_dirs;
+
+/// A repetition that was run and where its outputs were written,
+/// file names are relative to the experiment's results directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub repetition: u64,
+    pub seed: u64,
+    pub log_file: Option<String>,
+    pub metrics_file: Option<String>,
+    #[serde(default)]
+    pub registry_file: Option<String>,
+    #[serde(default)]
+    pub trace_file: Option<String>,
+    /// Directory with the log file of each peer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_log_dir: Option<String>,
+    /// Report of the invariant that stopped the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violation_file: Option<String>,
+}
+
+/// Describes the results of an experiment, it is written
+/// to `manifest.json` next to the files of every repetition.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExperimentManifest {
+    pub experiment: String,
+    pub scenario: String,
+    pub timestamp: i64,
+    pub seed: u64,
+    pub runs: Vec<RunRecord>,
+}
+
+impl ExperimentManifest {
+    pub const FILE_NAME: &'static str = "manifest.json";
+
+    pub fn read(results_dir: &str) -> Result<Self, String> {
+        let path = format!("{results_dir}/{}", Self::FILE_NAME);
+        let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+
+    pub fn write(&self, results_dir: &str) -> Result<String, String> {
+        let path = format!("{results_dir}/{}", Self::FILE_NAME);
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        write_file_with_dirs(&path, contents).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+#[must_use]
+pub fn results_dir(dir: &str, experiment_name: &str, timestamp: i64) -> String {
+    format!("{dir}/results/{experiment_name}/{timestamp}")
+}
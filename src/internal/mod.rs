@@ -1,5 +1,13 @@
 pub mod cli;
 pub mod core;
+pub mod explore;
+mod manifest;
+mod run;
+pub mod shrink;
 mod simulator;
+mod summary;
 
+pub use manifest::RunRecord;
+pub use run::{RepetitionResult, RunOptions, RunResult};
 pub use simulator::Simulator;
+pub use summary::{ExperimentSummary, MetricSummary};
@@ -0,0 +1,144 @@
+use super::{
+    cli::Args,
+    core::{
+        invariants::{EventHistory, InvariantViolation},
+        log::LoggerLevel,
+        log_filter::LogFilter,
+        metrics::SavedMetrics,
+        scheduler::SchedulerKind,
+        stats::NetworkStats,
+        time::{SimTime, TimeUnit},
+    },
+    manifest::{ExperimentManifest, RunRecord},
+    summary::ExperimentSummary,
+};
+
+/// How the simulations are run, settings that are not part of their
+/// configuration. The command line sets them from its arguments.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub logger_level: Option<LoggerLevel>,
+    /// The least level of the logs written to the log files, every log if not set
+    pub file_level: Option<LoggerLevel>,
+    /// Selects the logs written by their peer, and the level of those written to the console by their module
+    pub log_filter: Option<LogFilter>,
+    /// Nothing but errors is written to the console, neither the logs of the
+    /// simulations nor the progress of the runs
+    pub quiet: bool,
+    /// The amount of logs needed to flush to file
+    pub flush_threshold: usize,
+    /// Pauses before the first event of each run to read debugger commands from the terminal
+    pub debug: bool,
+    /// Simulation times at which a snapshot of each run is saved
+    pub checkpoint_at: Vec<SimTime>,
+    /// How many runs happen at the same time, 0 uses every available core
+    pub jobs: usize,
+    /// Only runs this repetition of each experiment
+    pub rep: Option<u64>,
+    /// How many of the last processed events are reported when an invariant is violated
+    pub invariant_history: usize,
+    /// Unit of the times in the logs, seconds without a unit if not set
+    pub time_unit: Option<TimeUnit>,
+    /// The queue of the pending events, it does not change the order they are processed in
+    pub scheduler: SchedulerKind,
+    /// Shows the progress of each run on the console while it runs, the statistics
+    /// of the runs are reported either way
+    pub progress: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            logger_level: None,
+            file_level: None,
+            log_filter: None,
+            quiet: false,
+            flush_threshold: 200,
+            debug: false,
+            checkpoint_at: Vec::new(),
+            jobs: 1,
+            rep: None,
+            invariant_history: EventHistory::DEFAULT_CAPACITY,
+            time_unit: None,
+            scheduler: SchedulerKind::Heap,
+            progress: false,
+        }
+    }
+}
+
+impl From<&Args> for RunOptions {
+    fn from(args: &Args) -> Self {
+        Self {
+            logger_level: args.logger_level,
+            file_level: args.file_level,
+            log_filter: args.log_filter.clone(),
+            quiet: args.quiet,
+            flush_threshold: args.flush_threshold,
+            debug: args.debug,
+            checkpoint_at: args.checkpoint_at.clone().unwrap_or_default(),
+            jobs: args.jobs,
+            rep: args.rep,
+            invariant_history: args.invariant_history,
+            time_unit: args.time_unit,
+            scheduler: args.scheduler,
+            progress: args.progress,
+        }
+    }
+}
+
+/// The outcome of a repetition of an experiment.
+#[derive(Debug, Clone)]
+pub struct RepetitionResult {
+    /// The repetition, its seed and the names of its output files
+    pub record: RunRecord,
+    /// Simulation time when the run finished
+    pub clock: f64,
+    pub network_stats: NetworkStats,
+    /// Last values of the metrics registry
+    pub metrics: SavedMetrics,
+    /// The invariant that stopped the run
+    pub violation: Option<InvariantViolation>,
+}
+
+/// The outcome of the repetitions of an experiment that were run.
+#[derive(Debug)]
+pub struct RunResult {
+    pub experiment: String,
+    pub scenario: String,
+    /// Seed the seeds of the repetitions are derived from
+    pub seed: u64,
+    /// Wall clock time when the simulations started, names the results directory
+    pub timestamp: i64,
+    pub repetitions: Vec<RepetitionResult>,
+    /// Where the outputs were written, unset if the configuration has no directory
+    pub results_dir: Option<String>,
+    /// Summary of the metrics written by the repetitions, read from `results_dir`
+    pub summary: Option<ExperimentSummary>,
+}
+
+impl RunResult {
+    /// The repetitions stopped by a violated invariant.
+    pub fn violations(&self) -> impl Iterator<Item = (u64, &InvariantViolation)> {
+        self.repetitions.iter().filter_map(|repetition| {
+            repetition
+                .violation
+                .as_ref()
+                .map(|violation| (repetition.record.repetition, violation))
+        })
+    }
+
+    #[must_use]
+    pub fn manifest(&self) -> ExperimentManifest {
+        ExperimentManifest {
+            experiment: self.experiment.clone(),
+            scenario: self.scenario.clone(),
+            timestamp: self.timestamp,
+            seed: self.seed,
+            runs: self
+                .repetitions
+                .iter()
+                .map(|repetition| repetition.record.clone())
+                .collect(),
+        }
+    }
+}
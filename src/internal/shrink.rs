@@ -0,0 +1,75 @@
+use super::core::{faults::Fault, invariants::InvariantViolation};
+
+/// The smallest set of faults found that still violates the invariant of a failing run.
+#[derive(Debug, Clone)]
+pub struct ShrunkFaults {
+    pub faults: Vec<Fault>,
+    pub violation: InvariantViolation,
+    /// How many faults the failing run injected
+    pub injected: usize,
+    pub runs: usize,
+}
+
+/// Removes faults of a failing run while the same invariant is violated, by delta
+/// debugging. `run` replays the run with only the given faults, the rates must not
+/// inject others. Returns `None` if the run does not fail again with every fault.
+pub fn shrink(
+    injected: &[Fault],
+    invariant: &str,
+    max_runs: usize,
+    mut run: impl FnMut(&[Fault]) -> Option<InvariantViolation>,
+) -> Option<ShrunkFaults> {
+    let mut runs = 0;
+    let mut fails = |faults: &[Fault], runs: &mut usize| {
+        *runs += 1;
+        run(faults).filter(|violation| violation.invariant == invariant)
+    };
+
+    let mut violation = fails(injected, &mut runs)?;
+    let mut faults = injected.to_vec();
+    if let Some(without_faults) = fails(&[], &mut runs) {
+        violation = without_faults;
+        faults.clear();
+    }
+
+    // tries removing each of `granularity` chunks, which get smaller when none can be removed
+    let mut granularity = 2;
+    while faults.len() >= 2 && runs < max_runs {
+        let chunk = faults.len().div_ceil(granularity);
+        let mut removed = false;
+
+        for start in (0..faults.len()).step_by(chunk) {
+            if runs >= max_runs {
+                break;
+            }
+
+            let end = (start + chunk).min(faults.len());
+            let rest: Vec<Fault> = faults[..start]
+                .iter()
+                .chain(&faults[end..])
+                .copied()
+                .collect();
+            if let Some(still_fails) = fails(&rest, &mut runs) {
+                faults = rest;
+                violation = still_fails;
+                granularity = (granularity - 1).max(2);
+                removed = true;
+                break;
+            }
+        }
+
+        if !removed {
+            if granularity >= faults.len() {
+                break;
+            }
+            granularity = (granularity * 2).min(faults.len());
+        }
+    }
+
+    Some(ShrunkFaults {
+        faults,
+        violation,
+        injected: injected.len(),
+        runs,
+    })
+}
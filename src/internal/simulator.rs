@@ -1,22 +1,52 @@
 use super::{
-    cli::{Args, SimulationConfig, get_config_from_args, utils::write_file_with_dirs},
+    cli::{
+        Args, OutputConfig, SimulationConfig, get_config_from_args, utils::write_file_with_dirs,
+    },
     core::{
-        Context, log,
+        Context,
+        debugger::Debugger,
+        experiment::Experiment,
+        faults::{Fault, FaultPlan},
+        interleaving::Interleaving,
+        invariants::{EventHistory, InvariantViolation},
+        log,
         options::{
             ArrivalTimeCallback, ArrivalTimeRegistry, ExperimentOptions, Scenario,
             ScenarioRegistry, Topology, TopologyRegistry,
         },
+        progress::Progress,
+        rng,
+        snapshot::{CheckpointPlan, ContextSnapshot, Resume},
+        time::SimDuration,
+        trace::{TraceVerifier, TraceWriter, Tracer},
     },
+    explore::{self, ExploreOptions, ExploredRun, FailingSchedule},
+    manifest::{self, ExperimentManifest, RunRecord},
+    run::{RepetitionResult, RunOptions, RunResult},
+    shrink::{self, ShrunkFaults},
+    summary::{self, ExperimentSummary},
 };
 
 use chrono::Local;
 use clap::Parser;
+use std::{
+    mem,
+    num::NonZeroUsize,
+    path::Path,
+    process,
+    sync::{
+        Mutex,
+        atomic::{self, AtomicUsize},
+    },
+    thread,
+};
 
 #[derive(Default)]
 pub struct Simulator {
     pub scenario_registry: ScenarioRegistry,
     pub topology_registry: TopologyRegistry,
     pub arrival_time_registry: ArrivalTimeRegistry,
+    pub options: RunOptions,
 }
 
 impl Simulator {
@@ -26,6 +56,7 @@ impl Simulator {
             scenario_registry: ScenarioRegistry::new(),
             topology_registry: TopologyRegistry::new(),
             arrival_time_registry: ArrivalTimeRegistry::new(),
+            options: RunOptions::default(),
         }
     }
 
@@ -44,86 +75,1055 @@ impl Simulator {
         self
     }
 
-    pub fn start(&mut self) {
-        let args = Args::parse();
+    pub fn set_options(&mut self, options: RunOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
 
-        let mut config: SimulationConfig = match get_config_from_args(args.clone(), self) {
-            Ok(c_option) => match c_option {
-                Some(c) => c,
-                None => return,
-            },
-            Err(e) => {
-                log::global_error(format!("Failed to load configuration file: {e}"));
-                return;
+    /// Creates the context of a run of an experiment with the network settings it configures.
+    fn create_context(
+        experiment: &Experiment,
+        seed: u64,
+        options: &RunOptions,
+        quiet: bool,
+    ) -> Context {
+        let mut exp_ctx = Context::new(Some(seed), options.logger_level, quiet);
+        if let Some(rate) = experiment.drop_rate {
+            exp_ctx.set_drop_rate(rate);
+        }
+        if let Some(rate) = experiment.duplicate_rate {
+            exp_ctx.set_duplicate_rate(rate);
+        }
+        if let Some(jitter) = experiment.jitter {
+            exp_ctx.set_jitter(jitter);
+        }
+        // the resolutions were validated with the configuration
+        if let Some(resolution) = experiment.time_resolution
+            && let Err(e) = exp_ctx.set_time_resolution(resolution)
+        {
+            log::global_error(e);
+        }
+        if let Some(faults) = &experiment.faults {
+            FaultPlan::install(&mut exp_ctx, faults);
+        }
+        exp_ctx.logger.set_flush_threshold(options.flush_threshold);
+        exp_ctx.logger.set_time_unit(options.time_unit);
+        if let Some(level) = options.file_level {
+            exp_ctx.logger.set_file_level(level);
+        }
+        if let Some(filter) = &options.log_filter {
+            exp_ctx.logger.set_filter(filter.clone());
+        }
+        exp_ctx.set_scheduler(options.scheduler);
+        exp_ctx.event_history = EventHistory::new(options.invariant_history);
+        if options.debug {
+            exp_ctx.debugger = Some(Debugger::new());
+        }
+
+        exp_ctx
+    }
+
+    fn run_scenario(&self, ctx: &mut Context, experiment: &Experiment) {
+        let opts = ExperimentOptions {
+            topology: experiment.topology.clone(),
+            arrival_time: experiment.arrival_time.clone(),
+            deadline: experiment.deadline,
+        };
+
+        if let Err(err) = self.scenario_registry.run_scenario(
+            &experiment.scenario,
+            ctx,
+            self,
+            opts,
+            experiment.extra_args.clone(),
+        ) {
+            log::global_error(format!("Scenario not run: {err:?}"));
+        }
+    }
+
+    /// Runs a single repetition of an experiment and returns its results,
+    /// `concurrent` runs do not write to the console while they run.
+    fn run_repetition(
+        &self,
+        config: &SimulationConfig,
+        experiment_idx: usize,
+        rep: u64,
+        timestamp: i64,
+        concurrent: bool,
+    ) -> RepetitionResult {
+        let (mut exp_ctx, mut record) =
+            self.prepare_repetition(config, experiment_idx, rep, timestamp, concurrent);
+        let experiment = &config.experiments[experiment_idx];
+        self.run_scenario(&mut exp_ctx, experiment);
+
+        let violation = exp_ctx.violation.take();
+        if let Some(violation) = &violation {
+            Self::report_violation(config, experiment, timestamp, &mut record, violation);
+        }
+
+        RepetitionResult {
+            record,
+            clock: exp_ctx.clock.as_secs(),
+            network_stats: mem::take(&mut exp_ctx.network_stats),
+            metrics: exp_ctx.metrics.save(),
+            violation,
+        }
+    }
+
+    /// Reports the invariant that stopped a run, its report is written with the other outputs.
+    fn report_violation(
+        config: &SimulationConfig,
+        experiment: &Experiment,
+        timestamp: i64,
+        record: &mut RunRecord,
+        violation: &InvariantViolation,
+    ) {
+        let (name, rep) = (&experiment.name, record.repetition);
+        log::global_error(format!(
+            "EXPERIMENT '{name}' (repetition {rep}) was stopped\n{violation}\nRerun it with --rep {rep}"
+        ));
+
+        let Some(dir) = &config.dir else {
+            return;
+        };
+        let violation_file = format!("{name}_{rep}_violation.json");
+        let violation_file_path = format!(
+            "{}/{violation_file}",
+            manifest::results_dir(dir, name, timestamp)
+        );
+        let written = serde_json::to_string_pretty(violation)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                write_file_with_dirs(&violation_file_path, contents).map_err(|e| e.to_string())
+            });
+        match written {
+            Ok(()) => {
+                log::global_internal(format!(
+                    "Wrote invariant violation to: {violation_file_path}"
+                ));
+                record.violation_file = Some(violation_file);
             }
+            Err(e) => log::global_error(format!(
+                "Failed to write invariant violation to {violation_file_path}: {e}"
+            )),
+        }
+    }
+
+    /// Creates the context of a repetition with its output files.
+    fn prepare_repetition(
+        &self,
+        config: &SimulationConfig,
+        experiment_idx: usize,
+        rep: u64,
+        timestamp: i64,
+        concurrent: bool,
+    ) -> (Context, RunRecord) {
+        let experiment = &config.experiments[experiment_idx];
+        let seed = experiment.seeds[&rep];
+
+        if !self.options.quiet {
+            // Print new line before each experiment
+            println!();
+            log::global_internal(format!(
+                "EXPERIMENT '{}' (repetition {rep})",
+                experiment.name
+            ));
+        }
+
+        let quiet = concurrent || self.options.quiet;
+        let mut exp_ctx = Self::create_context(experiment, seed, &self.options, quiet);
+        // the statistics of every run are reported, the progress is only shown when asked
+        let label = format!(
+            "EXPERIMENT '{}' ({}/{}) repetition {rep} ({}/{})",
+            experiment.name,
+            experiment_idx + 1,
+            config.experiments.len(),
+            rep + 1,
+            experiment.repetitions.unwrap_or(1)
+        );
+        let summary_only = concurrent || !self.options.progress;
+        exp_ctx.progress = Some(Progress::new(label, summary_only));
+
+        let mut record = RunRecord {
+            repetition: rep,
+            seed,
+            log_file: None,
+            metrics_file: None,
+            registry_file: None,
+            trace_file: None,
+            peer_log_dir: None,
+            violation_file: None,
         };
+        let mut checkpoint_dir = None;
 
-        let timestamp = Local::now().timestamp();
+        if let Some(directory) = &config.dir {
+            let name = experiment.name.clone();
+            let results_dir = manifest::results_dir(directory, &name, timestamp);
+            checkpoint_dir = Some(results_dir.clone());
 
-        for experiment in &mut config.experiments {
-            for rep in 0..experiment.repetitions.unwrap_or(1) {
-                // Print new line before each experiment
-                println!();
-                log::global_internal(format!("EXPERIMENT '{}'", experiment.name));
+            let extension = config.output.metrics_format.extension();
+            let log_file = format!("{name}_{rep}.{}", config.output.log_format.extension());
+            let metrics_file = format!("{name}_{rep}.{extension}");
+            let registry_file = format!("{name}_{rep}_registry.{extension}");
+            let log_file_path = format!("{results_dir}/{log_file}");
+            let metrics_file_path = format!("{results_dir}/{metrics_file}");
+            let registry_file_path = format!("{results_dir}/{registry_file}");
 
-                let mut exp_ctx = Context::new(experiment.seed, args.logger_level, args.quiet);
-                if let Some(rate) = experiment.drop_rate {
-                    exp_ctx.set_drop_rate(rate);
+            exp_ctx.logger.set_log_format(config.output.log_format);
+            match exp_ctx.logger.set_log_file(&log_file_path) {
+                Ok(()) => record.log_file = Some(log_file),
+                Err(e) => {
+                    log::global_error(format!("Failed to set log file to {log_file_path}: {e}"));
                 }
-                if let Some(rate) = experiment.duplicate_rate {
-                    exp_ctx.set_duplicate_rate(rate);
+            }
+            if config.output.per_peer_logs {
+                let peer_log_dir = format!("{name}_{rep}_peers");
+                let peer_log_dir_path = format!("{results_dir}/{peer_log_dir}");
+                match exp_ctx.logger.set_peer_log_dir(&peer_log_dir_path) {
+                    Ok(()) => record.peer_log_dir = Some(peer_log_dir),
+                    Err(e) => log::global_error(format!(
+                        "Failed to set the directory of the peer logs to {peer_log_dir_path}: {e}"
+                    )),
                 }
-                if let Some(jitter) = experiment.jitter {
-                    exp_ctx.set_jitter(jitter);
+            }
+            match exp_ctx
+                .logger
+                .set_metrics_file(&metrics_file_path, config.output.metrics_format)
+            {
+                Ok(()) => record.metrics_file = Some(metrics_file),
+                Err(e) => log::global_error(format!(
+                    "Failed to set metrics file to {metrics_file_path}: {e}"
+                )),
+            }
+            match exp_ctx
+                .metrics
+                .set_output(&registry_file_path, config.output.metrics_format)
+            {
+                Ok(()) => record.registry_file = Some(registry_file),
+                Err(e) => log::global_error(format!(
+                    "Failed to set metrics registry file to {registry_file_path}: {e}"
+                )),
+            }
+
+            if let Some(format) = config.output.trace {
+                let trace_file = format!("{name}_{rep}.{}", format.extension());
+                let trace_file_path = format!("{results_dir}/{trace_file}");
+
+                match TraceWriter::create(&trace_file_path, format) {
+                    Ok(writer) => {
+                        exp_ctx.tracer = Some(Tracer::Writer(writer));
+                        record.trace_file = Some(trace_file);
+                    }
+                    Err(e) => log::global_error(format!(
+                        "Failed to set trace file to {trace_file_path}: {e}"
+                    )),
                 }
+            }
+        } else {
+            if config.output.trace.is_some() {
+                log::global_warn(
+                    "Traces are only recorded when the results are written to a directory",
+                );
+            }
+            if !self.options.checkpoint_at.is_empty() {
+                log::global_warn(
+                    "Checkpoints are only saved when the results are written to a directory",
+                );
+            }
+        }
 
-                if let Some(directory) = &config.dir {
-                    let name = experiment.name.clone();
+        exp_ctx
+            .metrics
+            .set_interval(config.output.metrics_interval.map(SimDuration::as_secs));
+        exp_ctx.checkpoints = Some(CheckpointPlan::new(
+            &experiment.name,
+            rep,
+            checkpoint_dir,
+            self.options.checkpoint_at.clone(),
+        ));
 
-                    let log_file_path =
-                        format!("{directory}/results/{name}/{timestamp}/{name}_{rep}.log",);
-                    let metrics_file_path =
-                        format!("{directory}/results/{name}/{timestamp}/{name}_{rep}.jsonl",);
+        (exp_ctx, record)
+    }
 
-                    if let Err(e) = exp_ctx.logger.set_log_file(&log_file_path) {
+    /// Continues a run from a snapshot, its outputs are written to a new results
+    /// directory and start with what the run had written when it was saved.
+    fn resume(&self, config: &mut SimulationConfig, snapshot_file: &str) -> bool {
+        let snapshot = match ContextSnapshot::read(snapshot_file) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                log::global_error(format!("Failed to read the snapshot {snapshot_file}: {e}"));
+                return false;
+            }
+        };
+
+        let Some(idx) = config
+            .experiments
+            .iter()
+            .position(|experiment| experiment.name == snapshot.experiment)
+        else {
+            log::global_error(format!(
+                "The configuration has no experiment named '{}'",
+                snapshot.experiment
+            ));
+            return false;
+        };
+        let experiment = &mut config.experiments[idx];
+        let Some(registry) = self
+            .scenario_registry
+            .snapshot_registry(&experiment.scenario)
+        else {
+            log::global_error(format!("Scenario '{}' not found", experiment.scenario));
+            return false;
+        };
+
+        let rep = snapshot.repetition;
+        experiment.seeds.insert(rep, snapshot.seed);
+        experiment.seed.get_or_insert(snapshot.seed);
+
+        log::global_internal(format!(
+            "RESUMING '{}' (repetition {rep}) from {snapshot_file} at {}",
+            snapshot.experiment, snapshot.clock
+        ));
+        self.run_alone(config, idx, rep, |ctx| {
+            ctx.resume = Some(Box::new(Resume { snapshot, registry }));
+        })
+    }
+
+    /// Runs a repetition of an experiment by itself, with outputs in a new results
+    /// directory, returns false if an invariant was violated.
+    fn run_alone(
+        &self,
+        config: &SimulationConfig,
+        idx: usize,
+        rep: u64,
+        setup: impl FnOnce(&mut Context),
+    ) -> bool {
+        let experiment = &config.experiments[idx];
+        let mut timestamp = Local::now().timestamp();
+        // outputs may be copied from another results directory, which must not be reused
+        if let Some(dir) = &config.dir {
+            while Path::new(&manifest::results_dir(dir, &experiment.name, timestamp)).exists() {
+                timestamp += 1;
+            }
+        }
+
+        let (mut exp_ctx, mut record) = self.prepare_repetition(config, idx, rep, timestamp, false);
+        setup(&mut exp_ctx);
+        self.run_scenario(&mut exp_ctx, experiment);
+
+        let violation = exp_ctx.violation.take();
+        if let Some(violation) = &violation {
+            Self::report_violation(config, experiment, timestamp, &mut record, violation);
+        }
+
+        if let Some(dir) = &config.dir {
+            let manifest = ExperimentManifest {
+                experiment: experiment.name.clone(),
+                scenario: experiment.scenario.clone(),
+                timestamp,
+                seed: experiment.seed.unwrap_or_default(),
+                runs: vec![record],
+            };
+            let results_dir = manifest::results_dir(dir, &experiment.name, timestamp);
+            match manifest.write(&results_dir) {
+                Ok(path) => log::global_internal(format!("Wrote results manifest to: {path}")),
+                Err(e) => log::global_warn(format!(
+                    "Failed to write the results manifest of '{}': {e}",
+                    experiment.name
+                )),
+            }
+        }
+        violation.is_none()
+    }
+
+    /// Explores the delivery orders of a repetition of each experiment, the smallest
+    /// failing schedules are saved. Returns false if any invariant was violated.
+    fn explore_interleavings(
+        &self,
+        config: &mut SimulationConfig,
+        options: &ExploreOptions,
+    ) -> bool {
+        if let Err(e) = config.expand_sweeps() {
+            log::global_error(format!("Failed to expand the sweeps: {e}"));
+            return false;
+        }
+
+        let rep = self.options.rep.unwrap_or(0);
+        let timestamp = Local::now().timestamp();
+        let mut all_hold = true;
+
+        for idx in 0..config.experiments.len() {
+            let experiment = &mut config.experiments[idx];
+            let master_seed = *experiment.seed.get_or_insert_with(rand::random);
+            let seed = rng::derive_seed(master_seed, rep);
+            experiment.seeds.insert(rep, seed);
+
+            let experiment = &config.experiments[idx];
+            if !self.scenario_registry.contains(&experiment.scenario) {
+                log::global_error(format!(
+                    "Scenario '{}' of experiment '{}' not found",
+                    experiment.scenario, experiment.name
+                ));
+                all_hold = false;
+                continue;
+            }
+
+            println!();
+            log::global_internal(format!(
+                "EXPLORING '{}' (repetition {rep}) with {}, at most {} runs",
+                experiment.name, options.strategy, options.runs
+            ));
+
+            // the runs only differ by the order of deliveries, their logs would repeat
+            log::mute_global(true);
+            let options = ExploreOptions {
+                seed,
+                ..options.clone()
+            };
+            let exploration = explore::explore(&options, |interleaving| {
+                let mut exp_ctx = Self::create_context(experiment, seed, &self.options, true);
+                exp_ctx.interleaving = Some(Box::new(interleaving));
+                self.run_scenario(&mut exp_ctx, experiment);
+
+                ExploredRun {
+                    interleaving: *exp_ctx
+                        .interleaving
+                        .take()
+                        .expect("the run should keep its interleaving"),
+                    violation: exp_ctx.violation.take(),
+                }
+            });
+            log::mute_global(false);
+
+            let Some(counterexample) = exploration.counterexample else {
+                log::global_internal(format!(
+                    "No invariant of '{}' was violated in {} runs",
+                    experiment.name, exploration.runs
+                ));
+                continue;
+            };
+            all_hold = false;
+            log::global_error(format!(
+                "EXPERIMENT '{}' (repetition {rep}) failed after {} runs\n{counterexample}",
+                experiment.name, exploration.runs
+            ));
+
+            let Some(dir) = &config.dir else {
+                continue;
+            };
+            let schedule_file_path = format!(
+                "{}/{}_{rep}_schedule.json",
+                manifest::results_dir(dir, &experiment.name, timestamp),
+                experiment.name
+            );
+            let schedule = FailingSchedule {
+                experiment: experiment.name.clone(),
+                repetition: rep,
+                seed,
+                strategy: options.strategy,
+                counterexample,
+            };
+            match schedule.write(&schedule_file_path) {
+                Ok(()) => log::global_internal(format!(
+                    "Wrote the failing schedule to: {schedule_file_path}, replay it with --replay-schedule"
+                )),
+                Err(e) => log::global_error(format!(
+                    "Failed to write the failing schedule to {schedule_file_path}: {e}"
+                )),
+            }
+        }
+
+        all_hold
+    }
+
+    /// Runs a repetition with the delivery order of a failing schedule.
+    fn replay_schedule(&self, config: &mut SimulationConfig, schedule_file: &str) -> bool {
+        let schedule = match FailingSchedule::read(schedule_file) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                log::global_error(format!("Failed to read the schedule {schedule_file}: {e}"));
+                return false;
+            }
+        };
+        if let Err(e) = config.expand_sweeps() {
+            log::global_error(format!("Failed to expand the sweeps: {e}"));
+            return false;
+        }
+
+        let Some(idx) = config
+            .experiments
+            .iter()
+            .position(|experiment| experiment.name == schedule.experiment)
+        else {
+            log::global_error(format!(
+                "The configuration has no experiment named '{}'",
+                schedule.experiment
+            ));
+            return false;
+        };
+        let experiment = &mut config.experiments[idx];
+        let rep = schedule.repetition;
+        experiment.seeds.insert(rep, schedule.seed);
+        experiment.seed.get_or_insert(schedule.seed);
+
+        log::global_internal(format!(
+            "REPLAYING '{}' (repetition {rep}) with the {} reordered deliveries of {schedule_file}",
+            schedule.experiment,
+            schedule.counterexample.reorderings.len()
+        ));
+        let counterexample = schedule.counterexample;
+        self.run_alone(config, idx, rep, |ctx| {
+            ctx.interleaving = Some(Box::new(Interleaving::new(
+                counterexample.window,
+                counterexample.schedule,
+            )));
+        })
+    }
+
+    /// Runs the repetitions of each experiment and shrinks the faults of those that violate
+    /// an invariant, the smallest sets are saved as configurations that fail the same way.
+    /// Returns false if any invariant was violated.
+    fn shrink_faults(&self, config: &mut SimulationConfig, max_runs: usize) -> bool {
+        if let Err(e) = config.expand_sweeps() {
+            log::global_error(format!("Failed to expand the sweeps: {e}"));
+            return false;
+        }
+
+        let timestamp = Local::now().timestamp();
+        let mut all_hold = true;
+
+        for experiment in &mut config.experiments {
+            if !self.scenario_registry.contains(&experiment.scenario) {
+                log::global_error(format!(
+                    "Scenario '{}' of experiment '{}' not found",
+                    experiment.scenario, experiment.name
+                ));
+                all_hold = false;
+                continue;
+            }
+
+            let master_seed = *experiment.seed.get_or_insert_with(rand::random);
+            let reps: Vec<u64> = match self.options.rep {
+                Some(rep) => vec![rep],
+                None => (0..experiment.repetitions.unwrap_or(1)).collect(),
+            };
+
+            for rep in reps {
+                let seed = rng::derive_seed(master_seed, rep);
+                experiment.seeds.insert(rep, seed);
+
+                println!();
+                log::global_internal(format!(
+                    "SHRINKING '{}' (repetition {rep}), at most {max_runs} runs",
+                    experiment.name
+                ));
+                log::mute_global(true);
+                let shrunk = self.shrink_repetition(experiment, seed, max_runs);
+                log::mute_global(false);
+
+                let shrunk = match shrunk {
+                    Ok(Some(shrunk)) => shrunk,
+                    Ok(None) => {
+                        log::global_internal(format!(
+                            "No invariant of '{}' was violated",
+                            experiment.name
+                        ));
+                        continue;
+                    }
+                    Err(e) => {
                         log::global_error(format!(
-                            "Failed to set log file to {log_file_path}: {e}"
+                            "EXPERIMENT '{}' (repetition {rep}) failed but {e}",
+                            experiment.name
                         ));
+                        all_hold = false;
+                        continue;
                     }
-                    if let Err(e) = exp_ctx.logger.set_metrics_file(&metrics_file_path) {
+                };
+                all_hold = false;
+
+                let faults: Vec<String> = shrunk
+                    .faults
+                    .iter()
+                    .map(|fault| format!("\n  {fault}"))
+                    .collect();
+                log::global_error(format!(
+                    "EXPERIMENT '{}' (repetition {rep}) fails with {} of its {} faults, found in {} runs:{}\n{}",
+                    experiment.name,
+                    shrunk.faults.len(),
+                    shrunk.injected,
+                    shrunk.runs,
+                    faults.concat(),
+                    shrunk.violation
+                ));
+                Self::write_faults(
+                    config.dir.as_deref(),
+                    &config.output,
+                    experiment,
+                    rep,
+                    seed,
+                    timestamp,
+                    shrunk.faults,
+                );
+            }
+        }
+
+        all_hold
+    }
+
+    /// Runs a repetition with its rates, then shrinks the faults it injected if it failed.
+    fn shrink_repetition(
+        &self,
+        experiment: &Experiment,
+        seed: u64,
+        max_runs: usize,
+    ) -> Result<Option<ShrunkFaults>, String> {
+        let mut exp_ctx = Self::create_context(experiment, seed, &self.options, true);
+        exp_ctx.faults.get_or_insert_default();
+        self.run_scenario(&mut exp_ctx, experiment);
+
+        let Some(violation) = exp_ctx.violation.take() else {
+            return Ok(None);
+        };
+        let injected = exp_ctx
+            .faults
+            .take()
+            .map(|plan| plan.injected().to_vec())
+            .unwrap_or_default();
+
+        // the replays only inject the faults they are given
+        let mut replayed = experiment.clone();
+        replayed.drop_rate = None;
+        replayed.duplicate_rate = None;
+        shrink::shrink(&injected, &violation.invariant, max_runs, |faults| {
+            replayed.faults = Some(faults.to_vec());
+            let mut exp_ctx = Self::create_context(&replayed, seed, &self.options, true);
+            self.run_scenario(&mut exp_ctx, &replayed);
+            exp_ctx.violation.take()
+        })
+        .map(Some)
+        .ok_or_else(|| {
+            format!(
+                "it did not fail again with the {} faults it injected, its other randomness depends on them",
+                injected.len()
+            )
+        })
+    }
+
+    /// Writes a configuration with a single repetition that injects `faults` and no others.
+    fn write_faults(
+        dir: Option<&str>,
+        output: &OutputConfig,
+        experiment: &Experiment,
+        rep: u64,
+        seed: u64,
+        timestamp: i64,
+        faults: Vec<Fault>,
+    ) {
+        let mut failing = experiment.clone();
+        failing.seed = Some(seed);
+        failing.repetitions = Some(1);
+        failing.drop_rate = None;
+        failing.duplicate_rate = None;
+        failing.faults = Some(faults);
+        failing.seeds.clear();
+        let mut config = SimulationConfig::from_experiments(vec![failing]);
+        config.output = output.clone();
+
+        let yaml_str = match serde_yaml::to_string(&config) {
+            Ok(yaml_str) => yaml_str,
+            Err(e) => {
+                log::global_error(format!("Could not serialize the faults configuration: {e}"));
+                return;
+            }
+        };
+        let Some(dir) = dir else {
+            println!("\nFaults configuration:\n{yaml_str}");
+            return;
+        };
+
+        let faults_file_path = format!(
+            "{}/{}_{rep}_faults.yaml",
+            manifest::results_dir(dir, &experiment.name, timestamp),
+            experiment.name
+        );
+        match write_file_with_dirs(&faults_file_path, &yaml_str) {
+            Ok(()) => log::global_internal(format!(
+                "Wrote the faults configuration to: {faults_file_path}, run it with -c"
+            )),
+            Err(e) => log::global_error(format!(
+                "Failed to write the faults configuration to {faults_file_path}: {e}"
+            )),
+        }
+    }
+
+    /// Reruns every repetition recorded in the manifest of a results directory and
+    /// compares its events with the recorded trace, returns false if any diverged.
+    fn verify_traces(&self, config: &SimulationConfig, results_dir: &str) -> bool {
+        let manifest = match ExperimentManifest::read(results_dir) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                log::global_error(format!("Failed to read the manifest in {results_dir}: {e}"));
+                return false;
+            }
+        };
+
+        let Some(experiment) = config
+            .experiments
+            .iter()
+            .find(|experiment| experiment.name == manifest.experiment)
+        else {
+            log::global_error(format!(
+                "Experiment '{}' is not in the configuration",
+                manifest.experiment
+            ));
+            return false;
+        };
+
+        let mut all_match = true;
+        for run in &manifest.runs {
+            let Some(trace_file) = &run.trace_file else {
+                log::global_warn(format!(
+                    "Repetition {} of '{}' has no recorded trace",
+                    run.repetition, experiment.name
+                ));
+                continue;
+            };
+
+            let trace_file_path = format!("{results_dir}/{trace_file}");
+            let verifier = match TraceVerifier::open(&trace_file_path) {
+                Ok(verifier) => verifier,
+                Err(e) => {
+                    log::global_error(format!("Failed to open trace {trace_file_path}: {e}"));
+                    all_match = false;
+                    continue;
+                }
+            };
+
+            println!();
+            log::global_internal(format!(
+                "VERIFYING EXPERIMENT '{}' (repetition {}) against {trace_file_path}",
+                experiment.name, run.repetition
+            ));
+
+            let mut exp_ctx = Self::create_context(experiment, run.seed, &self.options, true);
+            exp_ctx.tracer = Some(Tracer::Verifier(Box::new(verifier)));
+            self.run_scenario(&mut exp_ctx, experiment);
+
+            if let Some(Tracer::Verifier(verifier)) = &exp_ctx.tracer {
+                match verifier.divergence() {
+                    Some(divergence) => {
+                        all_match = false;
                         log::global_error(format!(
-                            "Failed to set metrics file to {metrics_file_path}: {e}"
+                            "Repetition {} of '{}' is not deterministic: {divergence}",
+                            run.repetition, experiment.name
                         ));
                     }
+                    None => log::global_internal(format!(
+                        "Repetition {} of '{}' matches its trace, {} events compared",
+                        run.repetition,
+                        experiment.name,
+                        verifier.events()
+                    )),
                 }
+            }
+        }
 
-                exp_ctx.logger.set_flush_threshold(args.flush_threshold);
+        all_match
+    }
 
-                // add generated seed to config
-                if experiment.repetitions.unwrap_or(1) == 1 {
-                    experiment.seed = Some(exp_ctx.seed);
+    /// Runs the repetitions of the experiments in a configuration and returns a result
+    /// per experiment, writing their outputs when the configuration has a directory.
+    /// The seeds that were generated or derived are recorded in the configuration.
+    /// Quiet options keep everything but errors off the console.
+    pub fn run_config(&self, config: &mut SimulationConfig) -> Result<Vec<RunResult>, String> {
+        config.expand_sweeps()?;
+        config.validate()?;
+        if let Some(experiment) = config
+            .experiments
+            .iter()
+            .find(|experiment| !self.scenario_registry.contains(&experiment.scenario))
+        {
+            return Err(format!(
+                "Scenario '{}' of experiment '{}' not found",
+                experiment.scenario, experiment.name
+            ));
+        }
+
+        let timestamp = Local::now().timestamp();
+        if self.options.quiet {
+            log::mute_global(true);
+        }
+
+        // every repetition of every experiment, in the order of the configuration
+        let mut runs: Vec<(usize, u64)> = Vec::new();
+        for (idx, experiment) in config.experiments.iter_mut().enumerate() {
+            let repetitions = experiment.repetitions.unwrap_or(1);
+
+            // repetitions derive their seeds from the experiment's seed, which is
+            // generated if not provided so that it is recorded in the configuration
+            let master_seed = *experiment.seed.get_or_insert_with(rand::random);
+
+            let reps: Vec<u64> = match self.options.rep {
+                Some(rep) if rep < repetitions => vec![rep],
+                Some(rep) => {
+                    log::global_warn(format!(
+                        "Experiment '{}' skipped because it only has {repetitions} repetitions, repetition {rep} does not exist",
+                        experiment.name
+                    ));
+                    Vec::new()
                 }
+                None => (0..repetitions).collect(),
+            };
 
-                let opts = ExperimentOptions {
-                    topology: experiment.topology.clone(),
-                    arrival_time: experiment.arrival_time.clone(),
-                    deadline: experiment.deadline,
-                    extra_args: experiment.extra_args.clone(),
-                };
+            for rep in reps {
+                experiment
+                    .seeds
+                    .insert(rep, rng::derive_seed(master_seed, rep));
+                runs.push((idx, rep));
+            }
+        }
+
+        let jobs = match self.options.jobs {
+            // the debugger reads from the terminal, runs cannot share it
+            _ if self.options.debug => 1,
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            jobs => jobs,
+        }
+        .min(runs.len())
+        .max(1);
 
-                if let Err(err) = self.scenario_registry.run_scenario(
-                    &experiment.scenario,
-                    &mut exp_ctx,
-                    self,
-                    opts,
-                ) {
-                    log::global_error(format!("Scenario not run: {err:?}"));
+        let config = &*config;
+        let repetitions: Vec<RepetitionResult> = if jobs == 1 {
+            runs.iter()
+                .map(|&(idx, rep)| self.run_repetition(config, idx, rep, timestamp, false))
+                .collect()
+        } else {
+            log::global_internal(format!(
+                "Running {} simulations on {jobs} threads",
+                runs.len()
+            ));
+
+            let next_run = AtomicUsize::new(0);
+            let results: Mutex<Vec<Option<RepetitionResult>>> = Mutex::new(vec![None; runs.len()]);
+
+            thread::scope(|scope| {
+                for _ in 0..jobs {
+                    scope.spawn(|| {
+                        loop {
+                            let i = next_run.fetch_add(1, atomic::Ordering::Relaxed);
+                            let Some(&(idx, rep)) = runs.get(i) else {
+                                break;
+                            };
+
+                            // console logs of concurrent runs would be interleaved
+                            let result = self.run_repetition(config, idx, rep, timestamp, true);
+                            results
+                                .lock()
+                                .expect("no run should panic while holding the lock")[i] =
+                                Some(result);
+                        }
+                    });
+                }
+            });
+
+            let repetitions: Vec<RepetitionResult> = results
+                .into_inner()
+                .expect("no run should panic while holding the lock")
+                .into_iter()
+                .map(|result| result.expect("every run should have finished"))
+                .collect();
+
+            if !self.options.quiet {
+                println!();
+                for (&(idx, rep), result) in runs.iter().zip(&repetitions) {
+                    log::global_internal(format!(
+                        "EXPERIMENT '{}' repetition {rep} finished, seed is \"{}\"",
+                        config.experiments[idx].name, result.record.seed
+                    ));
                 }
             }
+
+            repetitions
+        };
+
+        let mut results: Vec<RunResult> = config
+            .experiments
+            .iter()
+            .map(|experiment| RunResult {
+                experiment: experiment.name.clone(),
+                scenario: experiment.scenario.clone(),
+                seed: experiment.seed.unwrap_or_default(),
+                timestamp,
+                repetitions: Vec::new(),
+                results_dir: None,
+                summary: None,
+            })
+            .collect();
+        for (&(idx, _), repetition) in runs.iter().zip(repetitions) {
+            results[idx].repetitions.push(repetition);
         }
 
-        println!("\nFinished all the simulations, wall clock timestamp is: {timestamp}");
+        if let Some(dir) = &config.dir {
+            Self::write_results(dir, timestamp, &mut results);
+        }
+        if self.options.quiet {
+            log::mute_global(false);
+        }
+
+        Ok(results)
+    }
+
+    /// Runs the repetitions of an experiment without writing their results. As
+    /// with `run_config`, only errors are written to the console when the options are quiet.
+    pub fn run_experiment(&self, experiment: &Experiment) -> Result<RunResult, String> {
+        if experiment.sweep.is_some() {
+            return Err(format!(
+                "Experiment '{}' has a sweep, its experiments are run with run_config",
+                experiment.name
+            ));
+        }
+
+        let mut config = SimulationConfig::from_experiments(vec![experiment.clone()]);
+        let mut results = self.run_config(&mut config)?;
+        Ok(results.remove(0))
+    }
+
+    /// Writes the manifest and summary of every experiment that ran, then compares them.
+    fn write_results(dir: &str, timestamp: i64, results: &mut [RunResult]) {
+        let mut summaries: Vec<ExperimentSummary> = Vec::new();
+
+        for result in results.iter_mut() {
+            if result.repetitions.is_empty() {
+                continue;
+            }
+
+            let manifest = result.manifest();
+            let results_dir = manifest::results_dir(dir, &result.experiment, timestamp);
+            match manifest.write(&results_dir) {
+                Ok(path) => log::global_internal(format!("Wrote results manifest to: {path}")),
+                Err(e) => log::global_warn(format!(
+                    "Failed to write the results manifest of '{}': {e}",
+                    result.experiment
+                )),
+            }
+
+            let summary = ExperimentSummary::from_manifest(&results_dir, &manifest);
+            match summary.write(&results_dir) {
+                Ok(path) => log::global_internal(format!("Wrote results summary to: {path}")),
+                Err(e) => log::global_warn(format!(
+                    "Failed to write the results summary of '{}': {e}",
+                    result.experiment
+                )),
+            }
+
+            result.results_dir = Some(results_dir);
+            result.summary = Some(summary.clone());
+            summaries.push(summary);
+        }
+
+        if !summaries.is_empty() {
+            match summary::write_comparison(dir, timestamp, &summaries) {
+                Ok(paths) => log::global_internal(format!(
+                    "Wrote experiments comparison to: {}",
+                    paths.join(", ")
+                )),
+                Err(e) => {
+                    log::global_warn(format!("Failed to write experiments comparison: {e}"));
+                }
+            }
+        }
+    }
+
+    /// Runs the simulator as a command line program, from the arguments of the process.
+    pub fn start(&mut self) {
+        let args = Args::parse();
+
+        let mut config: SimulationConfig = match get_config_from_args(args.clone(), self) {
+            Ok(c_option) => match c_option {
+                Some(c) => c,
+                None => return,
+            },
+            Err(e) => {
+                log::global_error(format!("Failed to load configuration file: {e}"));
+                return;
+            }
+        };
+        self.options = RunOptions::from(&args);
+
+        if let Some(results_dir) = &args.verify_trace {
+            if !self.verify_traces(&config, results_dir) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        if let Some(strategy) = args.explore {
+            let options = ExploreOptions {
+                strategy,
+                depth: args.explore_depth,
+                bound: args.explore_bound,
+                runs: args.explore_runs,
+                window: args.explore_window,
+                seed: 0,
+            };
+            if !self.explore_interleavings(&mut config, &options) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        if args.shrink {
+            if !self.shrink_faults(&mut config, args.shrink_runs) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        if let Some(schedule_file) = &args.replay_schedule {
+            if !self.replay_schedule(&mut config, schedule_file) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        if let Some(snapshot_file) = &args.resume {
+            if !self.resume(&mut config, snapshot_file) {
+                process::exit(1);
+            }
+            return;
+        }
+
+        let results = match self.run_config(&mut config) {
+            Ok(results) => results,
+            Err(e) => {
+                log::global_error(format!("Failed to run the simulations: {e}"));
+                process::exit(1);
+            }
+        };
+
+        if let Some(result) = results.first() {
+            println!(
+                "\nFinished all the simulations, wall clock timestamp is: {}",
+                result.timestamp
+            );
+        }
+
+        Self::write_config(config);
+
+        let violations = results
+            .iter()
+            .map(|result| result.violations().count())
+            .sum::<usize>();
+        if violations > 0 {
+            log::global_error(format!(
+                "{violations} runs were stopped by a violated invariant"
+            ));
+            process::exit(1);
+        }
+    }
 
+    /// Writes the configuration with the seeds that were used, or prints it without a directory.
+    fn write_config(config: SimulationConfig) {
         // TODO: Deal with this as it may panic
         let yaml_str = match serde_yaml::to_string(&config) {
             Ok(yaml_str) => yaml_str,
@@ -0,0 +1,263 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use super::{
+    cli::utils::write_file_with_dirs,
+    core::{log, sink},
+    manifest::ExperimentManifest,
+};
+
+// Two-sided 95% critical values of the Student's t-distribution for 1 to 30 degrees of freedom
+const T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+// Critical value of the normal distribution, used past 30 degrees of freedom
+const Z_CRITICAL_95: f64 = 1.96;
+
+/// Statistics of a metric over the repetitions of an experiment.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricSummary {
+    pub n: usize,
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    /// 95% confidence interval of the mean, needs at least two repetitions
+    pub ci95: Option<(f64, f64)>,
+}
+
+impl MetricSummary {
+    #[must_use]
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let std = if n > 1 {
+            (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let ci95 = (n > 1).then(|| {
+            let critical = T_CRITICAL_95.get(n - 2).copied().unwrap_or(Z_CRITICAL_95);
+            let half_width = critical * std / (n as f64).sqrt();
+            (mean - half_width, mean + half_width)
+        });
+
+        Some(Self {
+            n,
+            mean,
+            std,
+            min: sorted[0],
+            max: sorted[n - 1],
+            p50: percentile(&sorted, 0.5),
+            p90: percentile(&sorted, 0.9),
+            p99: percentile(&sorted, 0.99),
+            ci95,
+        })
+    }
+}
+
+/// Summary of every metric of an experiment, written to `summary.json`
+/// next to its manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentSummary {
+    pub experiment: String,
+    pub scenario: String,
+    pub repetitions: usize,
+    pub metrics: IndexMap<String, MetricSummary>,
+}
+
+impl ExperimentSummary {
+    pub const FILE_NAME: &'static str = "summary.json";
+
+    /// Reads the metrics of every repetition in the manifest and summarizes them.
+    /// A metric is the last value of each record logged with `log::metrics`, named
+    /// `title.key`, and of the last sample of the metrics registry, named
+    /// `registry.name.field`, with `@peer` appended for the values of a peer.
+    #[must_use]
+    pub fn from_manifest(results_dir: &str, manifest: &ExperimentManifest) -> Self {
+        let mut values: IndexMap<String, Vec<f64>> = IndexMap::new();
+
+        for run in &manifest.runs {
+            let mut run_values: IndexMap<String, f64> = IndexMap::new();
+
+            if let Some(file) = &run.metrics_file {
+                let path = format!("{results_dir}/{file}");
+                if let Err(e) = read_last_values(&path, None, &mut run_values) {
+                    log::global_warn(format!("Failed to read metrics file {path}: {e}"));
+                }
+            }
+            if let Some(file) = &run.registry_file {
+                let path = format!("{results_dir}/{file}");
+                if let Err(e) = read_last_values(&path, Some("registry"), &mut run_values) {
+                    log::global_warn(format!("Failed to read metrics registry file {path}: {e}"));
+                }
+            }
+
+            for (key, value) in run_values {
+                values.entry(key).or_default().push(value);
+            }
+        }
+
+        Self {
+            experiment: manifest.experiment.clone(),
+            scenario: manifest.scenario.clone(),
+            repetitions: manifest.runs.len(),
+            metrics: values
+                .into_iter()
+                .filter_map(|(key, values)| {
+                    MetricSummary::from_values(&values).map(|summary| (key, summary))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, results_dir: &str) -> Result<String, String> {
+        let path = format!("{results_dir}/{}", Self::FILE_NAME);
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        write_file_with_dirs(&path, contents).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Writes a Markdown and a CSV table comparing the metrics of the experiments
+/// of a configuration, returning where they were written.
+pub fn write_comparison(
+    dir: &str,
+    timestamp: i64,
+    summaries: &[ExperimentSummary],
+) -> Result<Vec<String>, String> {
+    let mut keys: Vec<&str> = Vec::new();
+    for summary in summaries {
+        for key in summary.metrics.keys() {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let mut markdown = format!(
+        "| metric | {} |\n|---|{}\n",
+        summaries
+            .iter()
+            .map(|s| s.experiment.as_str())
+            .collect::<Vec<&str>>()
+            .join(" | "),
+        "---|".repeat(summaries.len())
+    );
+    for key in &keys {
+        let cells: Vec<String> = summaries
+            .iter()
+            .map(|s| match s.metrics.get(*key) {
+                Some(metric) => match metric.ci95 {
+                    Some((low, _)) => format!("{:.6} ± {:.6}", metric.mean, metric.mean - low),
+                    None => format!("{:.6}", metric.mean),
+                },
+                None => "-".to_string(),
+            })
+            .collect();
+        markdown.push_str(&format!("| {key} | {} |\n", cells.join(" | ")));
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "experiment",
+            "metric",
+            "n",
+            "mean",
+            "std",
+            "min",
+            "max",
+            "p50",
+            "p90",
+            "p99",
+            "ci95_low",
+            "ci95_high",
+        ])
+        .map_err(|e| e.to_string())?;
+    for summary in summaries {
+        for (key, metric) in &summary.metrics {
+            let (ci_low, ci_high) = metric
+                .ci95
+                .map_or((String::new(), String::new()), |(low, high)| {
+                    (low.to_string(), high.to_string())
+                });
+            writer
+                .write_record([
+                    summary.experiment.clone(),
+                    key.clone(),
+                    metric.n.to_string(),
+                    metric.mean.to_string(),
+                    metric.std.to_string(),
+                    metric.min.to_string(),
+                    metric.max.to_string(),
+                    metric.p50.to_string(),
+                    metric.p90.to_string(),
+                    metric.p99.to_string(),
+                    ci_low,
+                    ci_high,
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let csv = String::from_utf8(writer.into_inner().map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    let markdown_path = format!("{dir}/results/summary_{timestamp}.md");
+    let csv_path = format!("{dir}/results/summary_{timestamp}.csv");
+    write_file_with_dirs(&markdown_path, markdown).map_err(|e| e.to_string())?;
+    write_file_with_dirs(&csv_path, csv).map_err(|e| e.to_string())?;
+
+    Ok(vec![markdown_path, csv_path])
+}
+
+/// Linear interpolation between the closest ranks of sorted values.
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+/// Keeps the values of the last record of each title, named `{prefix}.{title}.{key}`
+/// with `@{peer}` appended when the value belongs to a peer.
+fn read_last_values(
+    path: &str,
+    prefix: Option<&str>,
+    values: &mut IndexMap<String, f64>,
+) -> Result<(), String> {
+    let records = sink::read_records(path)?;
+
+    let mut last_timestamps: IndexMap<&str, f64> = IndexMap::new();
+    for record in &records {
+        last_timestamps.insert(&record.title, record.timestamp);
+    }
+
+    for record in &records {
+        if last_timestamps[record.title.as_ref()] != record.timestamp {
+            continue;
+        }
+
+        let mut key = match prefix {
+            Some(prefix) => format!("{prefix}.{}.{}", record.title, record.key),
+            None => format!("{}.{}", record.title, record.key),
+        };
+        if let Some(peer) = record.peer {
+            key.push_str(&format!("@{peer}"));
+        }
+        values.insert(key, record.value);
+    }
+    Ok(())
+}
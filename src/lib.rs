@@ -0,0 +1,10 @@
+//! A discrete event simulator of distributed systems. Scenarios define the peers
+//! and messages of a system, the [`Simulator`] runs the experiments that configure
+//! them and returns their results. Most programs only need the [`prelude`].
+
+pub mod internal;
+pub mod prelude;
+pub mod scenarios;
+pub mod testing;
+
+pub use internal::Simulator;
@@ -1,13 +1,11 @@
-pub mod internal;
-mod scenarios;
-
-use internal::Simulator;
-use scenarios::{
-    DistributedGeneralizedLinearModel, Example, FlowUpdatingPairwise, SimpleMessageScenario,
+use tdes::{
+    Simulator,
+    scenarios::{
+        DistributedGeneralizedLinearModel, Example, FlowUpdatingPairwise, ProposedDglm,
+        SimpleMessageScenario,
+    },
 };
 
-use crate::scenarios::ProposedDglm;
-
 fn main() {
     Simulator::default()
         .add_scenario::<Example>()
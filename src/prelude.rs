@@ -0,0 +1,25 @@
+//! The types needed to write scenarios and run them from another crate.
+
+pub use crate::internal::{
+    ExperimentSummary, MetricSummary, RepetitionResult, RunOptions, RunRecord, RunResult,
+    Simulator,
+    cli::SimulationConfig,
+    core::{
+        Context, Message, engine,
+        events::Timer,
+        experiment::{Experiment, Jitter, LinkInfo, TopologyInfo},
+        faults::Fault,
+        hooks::SimulationHooks,
+        invariants::InvariantViolation,
+        log,
+        metrics::SavedMetrics,
+        options::{ArrivalTimeCallback, ExperimentOptions, Scenario, Topology},
+        peer::{CustomPeer, PeerInfo},
+        rng::RngStream,
+        scheduler::SchedulerKind,
+        snapshot::SnapshotRegistry,
+        stats::NetworkStats,
+        time::{Bandwidth, SimDuration, SimTime, TimeUnit},
+    },
+};
+pub use crate::{define_custom_peer, get_peer_of_type, impl_snapshot};
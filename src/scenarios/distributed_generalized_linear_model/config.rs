@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DglmConfig {
+    /// Kills peer 0 at 0.1 seconds
+    pub kill_peer: bool,
+}
@@ -1,9 +1,10 @@
 use csv::ReaderBuilder;
 use faer::Mat;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::path::Path;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ModelData {
     pub x: Mat<f64>,
     pub y: Mat<f64>,
@@ -1,8 +1,10 @@
 pub mod binomial;
 pub mod gaussian;
 
+use serde::{Deserialize, Serialize};
+
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum FamilyEnum {
     Binomial = 0,
     Gaussian = 1,
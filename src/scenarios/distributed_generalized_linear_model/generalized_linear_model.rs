@@ -2,6 +2,7 @@ use faer::{
     Mat,
     linalg::{solvers::DenseSolveCore, triangular_solve::solve_upper_triangular_in_place},
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     family::{FamilyEnum, binomial, gaussian},
@@ -14,6 +15,7 @@ use super::{
 pub const DEFAULT_MAXIT: usize = 25;
 pub const DEFAULT_TOL: f64 = 1.0e-10;
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GeneralizedLinearModel {
     pub r_local: Mat<f64>,
     pub coefficients: Mat<f64>,
@@ -114,3 +116,126 @@ pub fn distributed_single_solve_n(
 
     (r_local, beta, stop)
 }
+
+#[cfg(test)]
+mod tests {
+    use faer::{Mat, mat};
+
+    use super::*;
+    use crate::scenarios::distributed_generalized_linear_model::utils::{
+        mat_allclose, mat_cat_vec,
+    };
+
+    fn design() -> Mat<f64> {
+        mat![
+            [1.0, 0.5],
+            [1.0, 1.5],
+            [1.0, 2.0],
+            [1.0, 3.5],
+            [1.0, 4.0],
+            [1.0, 5.5],
+            [1.0, 6.0],
+            [1.0, 7.5],
+        ]
+    }
+
+    fn solve(
+        r: &Mat<f64>,
+        beta: &Mat<f64>,
+        family: FamilyEnum,
+        nrow: usize,
+        iter: usize,
+    ) -> (Mat<f64>, bool) {
+        let (_, beta, stop) =
+            distributed_single_solve_n(r, beta, family, nrow, DEFAULT_MAXIT, DEFAULT_TOL, iter);
+        (beta, stop)
+    }
+
+    #[test]
+    fn gaussian_matches_least_squares() {
+        let x = design();
+        let y = mat![[1.1], [2.9], [4.2], [6.8], [8.1], [11.2], [11.9], [15.1]];
+        let beta = Mat::zeros(2, 1);
+
+        let r = distributed_single_iter_n(FamilyEnum::Gaussian, &x, &y, beta.clone());
+        let (beta, _) = solve(&r, &beta, FamilyEnum::Gaussian, x.nrows(), 1);
+
+        // (X^T X)^-1 X^T y
+        let expected = (x.transpose() * &x).partial_piv_lu().inverse() * x.transpose() * &y;
+        assert!(mat_allclose(&beta, &expected, 1e-10, 1e-12));
+    }
+
+    #[test]
+    fn stacked_chunks_give_the_same_coefficients() {
+        let x = design();
+        let y = mat![[1.1], [2.9], [4.2], [6.8], [8.1], [11.2], [11.9], [15.1]];
+        let beta = Mat::zeros(2, 1);
+
+        let r_all = distributed_single_iter_n(FamilyEnum::Gaussian, &x, &y, beta.clone());
+        let r_chunks: Vec<Mat<f64>> = [0, 3, 5]
+            .iter()
+            .zip([3, 2, 3])
+            .map(|(&start, nrows)| {
+                distributed_single_iter_n(
+                    FamilyEnum::Gaussian,
+                    &x.submatrix(start, 0, nrows, 2).to_owned(),
+                    &y.submatrix(start, 0, nrows, 1).to_owned(),
+                    beta.clone(),
+                )
+            })
+            .collect();
+        let r_stacked = mat_cat_vec(&r_chunks, CatDim::Vertical);
+
+        let (from_all, _) = solve(&r_all, &beta, FamilyEnum::Gaussian, x.nrows(), 1);
+        let (from_chunks, _) = solve(&r_stacked, &beta, FamilyEnum::Gaussian, x.nrows(), 1);
+        assert!(mat_allclose(&from_chunks, &from_all, 1e-10, 1e-12));
+    }
+
+    #[test]
+    fn binomial_converges_to_the_score_equations() {
+        let x = design();
+        let y = mat![[0.0], [0.0], [1.0], [0.0], [1.0], [0.0], [1.0], [1.0]];
+        let mut beta = Mat::zeros(2, 1);
+
+        let mut iter = 0;
+        loop {
+            iter += 1;
+            let r = distributed_single_iter_n(FamilyEnum::Binomial, &x, &y, beta.clone());
+            let (next, stop) = solve(&r, &beta, FamilyEnum::Binomial, x.nrows(), iter);
+            beta = next;
+            if stop {
+                break;
+            }
+        }
+        assert!(
+            iter < DEFAULT_MAXIT,
+            "did not converge in {iter} iterations"
+        );
+
+        // at the maximum likelihood X^T (y - mu) = 0
+        let mu = binomial::linkinv(&(&x * &beta));
+        let score = x.transpose() * (&y - mu);
+        assert!(mat_allclose(&score, &Mat::zeros(2, 1), 0.0, 1e-8));
+    }
+
+    #[test]
+    fn binomial_link() {
+        let eta = mat![[0.0, 2.0]];
+
+        let mu = binomial::linkinv(&eta);
+        assert!(mat_allclose(
+            &mu,
+            &mat![[0.5, 1.0 / (1.0 + (-2.0f64).exp())]],
+            1e-12,
+            0.0
+        ));
+        // the derivative of the logistic function is mu (1 - mu)
+        assert!(mat_allclose(
+            &binomial::mu_eta(&eta),
+            &binomial::variance(&mu),
+            1e-12,
+            0.0
+        ));
+        assert_eq!(binomial::variance(&mat![[0.5]]), mat![[0.25]]);
+    }
+}
@@ -5,6 +5,25 @@ use crate::internal::core::{Context, hooks::CustomOnFinishHook, log};
 
 use super::{peer::GlmPeer, utils::mat_allclose_default};
 
+/// Peers that finished solved the same model, so they must have the same coefficients.
+pub fn finished_peers_agree(ctx: &Context) -> Result<(), String> {
+    let mut finished = ctx.peers.iter().enumerate().filter_map(|(id, p)| {
+        p.downcast_ref::<GlmPeer>()
+            .filter(|peer| peer.state.finished)
+            .map(|peer| (id, &peer.state.model.coefficients))
+    });
+
+    let Some((first_id, first)) = finished.next() else {
+        return Ok(());
+    };
+    match finished.find(|(_, coefficients)| !mat_allclose_default(coefficients, first)) {
+        Some((id, coefficients)) => Err(format!(
+            "peer {id} finished with {coefficients:?} but peer {first_id} with {first:?}"
+        )),
+        None => Ok(()),
+    }
+}
+
 pub fn on_simulation_finish_hook(central: Mat<f64>) -> CustomOnFinishHook {
     Box::new(move |ctx| {
         let coefficients: Vec<Mat<f64>> = ctx
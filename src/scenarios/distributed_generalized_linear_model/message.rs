@@ -1,25 +1,30 @@
 use faer::Mat;
+use serde::{Deserialize, Serialize};
 
-use crate::internal::core::Message;
+use crate::internal::core::{Message, macros::impl_snapshot};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GlmSumRowsMessage {
     pub origin: usize,
     pub nrows: usize,
 }
 impl Message for GlmSumRowsMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         1 + 1
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlmConcatMessage {
     pub origin: usize,
     pub r_remote: Mat<f64>,
     pub iter: usize,
 }
 impl Message for GlmConcatMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         let (r, c) = self.r_remote.shape();
 
@@ -1,5 +1,6 @@
 mod algorithms;
 mod callbacks;
+mod config;
 mod hooks;
 mod message;
 mod peer;
@@ -10,12 +11,12 @@ mod family;
 mod generalized_linear_model;
 mod utils;
 
+use config::DglmConfig;
 use data::{ModelData, chunk_nx, model_beta, model_data};
 use faer::Mat;
-use ordered_float::OrderedFloat;
+use message::{GlmConcatMessage, GlmSumRowsMessage};
 use peer::GlmPeer;
 use rand::Rng;
-use serde_yaml::Value;
 
 use crate::{
     internal::{
@@ -24,6 +25,9 @@ use crate::{
             Context, engine,
             hooks::SimulationHooks,
             options::{ExperimentOptions, Scenario},
+            rng::RngStream,
+            snapshot::SnapshotRegistry,
+            time::SimTime,
         },
     },
     scenarios::distributed_generalized_linear_model::timers::{KillTimer, StartTimer},
@@ -32,6 +36,8 @@ use crate::{
 pub struct DistributedGeneralizedLinearModel;
 
 impl Scenario for DistributedGeneralizedLinearModel {
+    type Config = DglmConfig;
+
     fn name() -> &'static str {
         "dglm"
     }
@@ -40,7 +46,21 @@ impl Scenario for DistributedGeneralizedLinearModel {
         "A distributed implementation of the generalized linear model."
     }
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions) {
+    fn register_snapshot_types(registry: &mut SnapshotRegistry) {
+        registry
+            .register_peer::<GlmPeer>()
+            .register_message::<GlmSumRowsMessage>()
+            .register_message::<GlmConcatMessage>()
+            .register_timer::<KillTimer>()
+            .register_timer::<StartTimer>();
+    }
+
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: DglmConfig,
+    ) {
         let n_peers: usize = opts.topology.n_peers;
 
         let data: ModelData = match model_data("glm") {
@@ -66,13 +86,16 @@ impl Scenario for DistributedGeneralizedLinearModel {
         let x_chunks = chunk_nx(data.x, n_peers);
         let y_chunks = chunk_nx(data.y, n_peers);
 
-        for (i, (x, y)) in x_chunks.into_iter().zip(y_chunks.into_iter()).enumerate() {
+        for (i, (x, y)) in x_chunks.into_iter().zip(y_chunks).enumerate() {
             let (pos_x, pos_y) = match opts.topology.positions.as_ref().and_then(|v| v.get(i)) {
                 Some(&(px, py, _)) => (px, py),
-                None => (
-                    ctx.rng.random_range(-100.0..=100.0) * 1000.0,
-                    ctx.rng.random_range(-100.0..=100.0) * 1000.0,
-                ),
+                None => {
+                    let rng = ctx.rng_for(RngStream::Topology);
+                    (
+                        rng.random_range(-100.0..=100.0) * 1000.0,
+                        rng.random_range(-100.0..=100.0) * 1000.0,
+                    )
+                }
             };
 
             engine::add_peer(ctx, GlmPeer::new(pos_x, pos_y, x, y));
@@ -87,17 +110,21 @@ impl Scenario for DistributedGeneralizedLinearModel {
 
         // init
         for peer_id in 0..ctx.peers.len() {
-            engine::add_timer(ctx, OrderedFloat(0.0), StartTimer { peer_id });
+            engine::add_timer(ctx, SimTime::ZERO, StartTimer { peer_id });
         }
 
-        if let Some(custom) = opts.extra_args {
-            if let Some(Value::Bool(true)) = custom.get("kill_peer") {
-                engine::add_timer(ctx, OrderedFloat(0.1), KillTimer::new(0));
-            }
+        if config.kill_peer {
+            engine::add_timer(ctx, SimTime::from_secs(0.1), KillTimer::new(0));
         }
 
         let mut hooks = SimulationHooks::default();
-        hooks.set_on_simulation_finish_hook(hooks::on_simulation_finish_hook(beta_mat));
+        hooks
+            .set_on_simulation_finish_hook(hooks::on_simulation_finish_hook(beta_mat))
+            .add_invariant(
+                "finished peers agree",
+                1,
+                Box::new(hooks::finished_peers_agree),
+            );
 
         engine::run(ctx, &hooks, opts.deadline);
     }
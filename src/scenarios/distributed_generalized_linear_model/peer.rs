@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use faer::Mat;
+use serde::{Deserialize, Serialize};
 
 use crate::internal::core::{
     macros::define_custom_peer,
@@ -13,6 +14,7 @@ use super::{
     generalized_linear_model::{self, GeneralizedLinearModel},
 };
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GlmState {
     pub model: GeneralizedLinearModel,
     pub data: ModelData,
@@ -23,12 +25,13 @@ pub struct GlmState {
     pub finished: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GlmPeer {
     pub peer_info: PeerInfo,
     pub state: GlmState,
 }
 
-define_custom_peer!(GlmPeer);
+define_custom_peer!(GlmPeer, debug, snapshot);
 
 impl GlmPeer {
     pub fn new(pos_x: f64, pos_y: f64, x: Mat<f64>, y: Mat<f64>) -> Self {
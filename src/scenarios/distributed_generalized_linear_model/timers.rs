@@ -1,9 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    internal::core::{Context, events::Timer, log},
+    internal::core::{Context, events::Timer, log, macros::impl_snapshot},
     scenarios::distributed_generalized_linear_model::algorithms,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KillTimer {
     pub target: usize,
 }
@@ -15,6 +17,12 @@ impl KillTimer {
 }
 
 impl Timer for KillTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.target)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         if let Some(target) = ctx.peers.get_mut(self.target) {
             target.kill();
@@ -23,12 +31,18 @@ impl Timer for KillTimer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartTimer {
     pub peer_id: usize,
 }
 
 impl Timer for StartTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.peer_id)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         algorithms::broadcast_sum_rows(ctx, self.peer_id);
     }
@@ -48,7 +48,7 @@ pub fn mul_elementwise(a: &Mat<f64>, b: &Mat<f64>) -> Mat<f64> {
         // Scalar broadcast on a
         (1, 1, br, bc) => {
             let val = a.get(0, 0);
-            Mat::from_fn(br, bc, |i, j| a.get(i, j) * val)
+            Mat::from_fn(br, bc, |i, j| b.get(i, j) * val)
         }
         // Incompatible shapes
         _ => panic!("Cannot multiply matrices element-wise due to incompatible shapes"),
@@ -136,3 +136,67 @@ pub fn mat_allclose(a: &Mat<f64>, b: &Mat<f64>, rtol: f64, atol: f64) -> bool {
 pub fn mat_allclose_default(a: &Mat<f64>, b: &Mat<f64>) -> bool {
     mat_allclose(a, b, DEFAULT_RTOL, DEFAULT_ATOL)
 }
+
+#[cfg(test)]
+mod tests {
+    use faer::mat;
+
+    use super::*;
+
+    #[test]
+    fn cat_stacks_matrices() {
+        let a = mat![[1.0, 2.0], [3.0, 4.0]];
+        let b = mat![[5.0, 6.0]];
+
+        assert_eq!(
+            mat_cat(&a, &b, CatDim::Vertical),
+            mat![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]
+        );
+        assert_eq!(
+            mat_cat(&a, &b.transpose().to_owned(), CatDim::Horizontal),
+            mat![[1.0, 2.0, 5.0], [3.0, 4.0, 6.0]]
+        );
+        assert_eq!(
+            mat_cat_vec(&[b.clone(), b.clone(), b], CatDim::Vertical).nrows(),
+            3
+        );
+    }
+
+    #[test]
+    fn mul_elementwise_broadcasts() {
+        let a = mat![[1.0, 2.0], [3.0, 4.0]];
+        let column = mat![[2.0], [3.0]];
+        let row = mat![[2.0, 3.0]];
+        let scalar = mat![[2.0]];
+
+        assert_eq!(mul_elementwise(&a, &a), mat![[1.0, 4.0], [9.0, 16.0]]);
+        assert_eq!(mul_elementwise(&column, &a), mat![[2.0, 4.0], [9.0, 12.0]]);
+        assert_eq!(mul_elementwise(&a, &column), mul_elementwise(&column, &a));
+        assert_eq!(mul_elementwise(&a, &row), mat![[2.0, 6.0], [6.0, 12.0]]);
+        assert_eq!(mul_elementwise(&row, &a), mul_elementwise(&a, &row));
+        assert_eq!(mul_elementwise(&scalar, &a), mat![[2.0, 4.0], [6.0, 8.0]]);
+        assert_eq!(mul_elementwise(&a, &scalar), mul_elementwise(&scalar, &a));
+    }
+
+    #[test]
+    #[should_panic(expected = "incompatible shapes")]
+    fn mul_elementwise_rejects_mismatched_shapes() {
+        mul_elementwise(&mat![[1.0, 2.0, 3.0]], &mat![[1.0, 2.0]]);
+    }
+
+    #[test]
+    fn diag_of_a_rectangular_matrix() {
+        let a = mat![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        assert_eq!(mat_diag(&a), mat![[1.0], [5.0]]);
+    }
+
+    #[test]
+    fn allclose_uses_both_tolerances() {
+        let a = mat![[1.0, 1000.0]];
+
+        assert!(mat_allclose_default(&a, &mat![[1.0 + 1e-9, 1000.0 + 1e-3]]));
+        assert!(!mat_allclose_default(&a, &mat![[1.0 + 1e-4, 1000.0]]));
+        assert!(!mat_allclose_default(&a, &mat![[1.0], [1000.0]]));
+        assert!(mat_allclose(&a, &mat![[1.1, 1000.0]], 0.0, 0.2));
+    }
+}
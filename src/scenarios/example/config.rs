@@ -0,0 +1,5 @@
+use serde::{Deserialize, Serialize};
+
+/// The example scenario has no options, any field given is rejected as unknown.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExampleConfig {}
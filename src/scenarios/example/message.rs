@@ -1,12 +1,19 @@
-use crate::internal::core::{Context, Message, engine, log, macros::get_peer_of_type};
+use serde::{Deserialize, Serialize};
+
+use crate::internal::core::{
+    Context, Message, engine, log,
+    macros::{get_peer_of_type, impl_snapshot},
+};
 
 use super::peer::ExamplePeer;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExampleMessage {
     pub sender: usize,
 }
 impl Message for ExampleMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         1
     }
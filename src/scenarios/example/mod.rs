@@ -1,6 +1,8 @@
+mod config;
 mod message;
 mod peer;
 
+use config::ExampleConfig;
 use message::ExampleMessage;
 use peer::ExamplePeer;
 use rand::Rng;
@@ -11,12 +13,16 @@ use crate::internal::{
         Context, engine,
         hooks::SimulationHooks,
         options::{ExperimentOptions, Scenario},
+        rng::RngStream,
+        snapshot::SnapshotRegistry,
     },
 };
 
 pub struct Example {}
 
 impl Scenario for Example {
+    type Config = ExampleConfig;
+
     fn name() -> &'static str
     where
         Self: Sized,
@@ -31,16 +37,30 @@ impl Scenario for Example {
         "An example simulation."
     }
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions) {
+    fn register_snapshot_types(registry: &mut SnapshotRegistry) {
+        registry
+            .register_peer::<ExamplePeer>()
+            .register_message::<ExampleMessage>();
+    }
+
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        _config: ExampleConfig,
+    ) {
         for i in 0..opts.topology.n_peers {
             let (pos_x, pos_y, pos_z) =
                 match opts.topology.positions.as_ref().and_then(|v| v.get(i)) {
                     Some(&(px, py, pz)) => (px, py, pz.unwrap_or(0.0)),
-                    None => (
-                        ctx.rng.random_range(-10.0..=10.0),
-                        ctx.rng.random_range(-10.0..=10.0),
-                        0.0,
-                    ),
+                    None => {
+                        let rng = ctx.rng_for(RngStream::Topology);
+                        (
+                            rng.random_range(-10.0..=10.0),
+                            rng.random_range(-10.0..=10.0),
+                            0.0,
+                        )
+                    }
                 };
 
             engine::add_peer(ctx, ExamplePeer::new(pos_x, pos_y, pos_z));
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::internal::core::{
     macros::define_custom_peer,
     peer::{CustomPeer, PeerInfo},
@@ -5,7 +7,7 @@ use crate::internal::core::{
 
 use super::message::example_on_message_receive;
 
-#[derive(Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ExamplePeer {
     pub peer_info: PeerInfo,
     pub value: u64,
@@ -20,4 +22,4 @@ impl ExamplePeer {
     }
 }
 
-define_custom_peer!(ExamplePeer);
+define_custom_peer!(ExamplePeer, debug, snapshot);
@@ -8,14 +8,17 @@ pub fn avg_and_send(ctx: &mut Context, peer_id: usize, neigh_id: usize) {
         format!("Peer_{peer_id} avg_and_send to Peer_{neigh_id}"),
     );
 
-    if let Some(neighbors) = engine::get_neighbors_alive(ctx, peer_id) {
+    if peer_id < ctx.peers.len() {
+        let flows = &ctx.peers[peer_id]
+            .downcast_ref::<FlowUpdatingPairwisePeer>()
+            .expect("peer should exist")
+            .flows;
+        let flows_sum: f64 = engine::neighbors_alive(ctx, peer_id)
+            .map(|idx| *flows.get(&idx).unwrap_or(&0.0))
+            .sum();
+
         let peer: &mut FlowUpdatingPairwisePeer =
             get_peer_of_type!(ctx, peer_id, FlowUpdatingPairwisePeer).expect("peer should exist");
-
-        let flows_sum: f64 = neighbors
-            .into_iter()
-            .map(|idx| *peer.flows.get(&idx).unwrap_or(&0.0))
-            .sum();
         let estimate = f64::from(peer.value) - flows_sum;
         let avg = (peer.estimates.get(&neigh_id).copied().unwrap_or(0.0) + estimate) / 2.0;
 
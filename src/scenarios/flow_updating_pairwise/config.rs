@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlowUpdatingPairwiseConfig {
+    /// Initial value of each peer, truncated to an integer, peers without one get a
+    /// random value in [0, 80]
+    pub values: Vec<f64>,
+}
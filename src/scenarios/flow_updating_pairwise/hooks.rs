@@ -3,6 +3,20 @@ use ordered_float::OrderedFloat;
 use super::FlowUpdatingPairwisePeer;
 use crate::internal::core::{Context, log};
 
+/// A NaN or infinite average never converges and spreads to every neighbour.
+pub fn averages_are_finite(ctx: &Context) -> Result<(), String> {
+    let invalid = ctx.peers.iter().enumerate().find_map(|(id, p)| {
+        p.downcast_ref::<FlowUpdatingPairwisePeer>()
+            .filter(|peer| !peer.last_avg.is_finite())
+            .map(|peer| (id, peer.last_avg))
+    });
+
+    match invalid {
+        Some((id, avg)) => Err(format!("peer {id} has the average {avg}")),
+        None => Ok(()),
+    }
+}
+
 pub fn on_simulation_finish_hook(ctx: &mut Context) {
     let avgs: Vec<OrderedFloat<f64>> = ctx
         .peers
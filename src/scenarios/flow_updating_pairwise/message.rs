@@ -1,12 +1,16 @@
-use crate::internal::core::Message;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::internal::core::{Message, macros::impl_snapshot};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowUpdatingPairwiseMessage {
     pub sender: usize,
     pub flow: f64,
     pub estimate: f64,
 }
 impl Message for FlowUpdatingPairwiseMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         1 + 8 + 8
     }
@@ -1,11 +1,13 @@
 mod algorithms;
 mod callbacks;
+mod config;
 mod hooks;
 mod message;
 mod peer;
 mod timer;
 
-use ordered_float::OrderedFloat;
+use config::FlowUpdatingPairwiseConfig;
+use message::FlowUpdatingPairwiseMessage;
 use peer::FlowUpdatingPairwisePeer;
 use rand::Rng;
 use timer::TickTimer;
@@ -17,6 +19,9 @@ use crate::{
             Context, engine,
             hooks::SimulationHooks,
             options::{ExperimentOptions, Scenario},
+            rng::RngStream,
+            snapshot::SnapshotRegistry,
+            time::{SimDuration, SimTime},
         },
     },
     scenarios::flow_updating_pairwise::timer::{MetricsTimer, StartTimer},
@@ -25,6 +30,8 @@ use crate::{
 pub struct FlowUpdatingPairwise {}
 
 impl Scenario for FlowUpdatingPairwise {
+    type Config = FlowUpdatingPairwiseConfig;
+
     fn name() -> &'static str
     where
         Self: Sized,
@@ -39,28 +46,35 @@ impl Scenario for FlowUpdatingPairwise {
         "An implementation of the flow updating pairwise algorithm."
     }
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions) {
-        let n_peers = opts.topology.n_peers;
+    fn register_snapshot_types(registry: &mut SnapshotRegistry) {
+        registry
+            .register_peer::<FlowUpdatingPairwisePeer>()
+            .register_message::<FlowUpdatingPairwiseMessage>()
+            .register_timer::<TickTimer>()
+            .register_timer::<StartTimer>()
+            .register_timer::<MetricsTimer>();
+    }
 
-        let peer_values: Vec<i32> = opts
-            .extra_args
-            .as_ref()
-            .and_then(|custom| custom.get("values"))
-            .and_then(|v| v.as_sequence())
-            .map(|seq| {
-                seq.iter()
-                    .filter_map(|val| val.as_f64().map(|f| f as i32))
-                    .collect()
-            })
-            .unwrap_or_default();
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: FlowUpdatingPairwiseConfig,
+    ) {
+        let n_peers = opts.topology.n_peers;
+        let peer_values = config.values;
 
         for i in 0..n_peers {
+            let rng = ctx.rng_for(RngStream::Topology);
             let (rx, ry) = (
-                ctx.rng.random_range(-100000.0..=100000.0),
-                ctx.rng.random_range(-100000.0..=100000.0),
+                rng.random_range(-100000.0..=100000.0),
+                rng.random_range(-100000.0..=100000.0),
             );
 
-            let val: i32 = *peer_values.get(i).unwrap_or(&ctx.rng.random_range(0..=80));
+            let val: i32 = peer_values
+                .get(i)
+                .map(|&value| value as i32)
+                .unwrap_or_else(|| ctx.rng_for(RngStream::Peer(i)).random_range(0..=80));
 
             engine::add_peer(ctx, FlowUpdatingPairwisePeer::new(rx, ry, val));
         }
@@ -73,19 +87,25 @@ impl Scenario for FlowUpdatingPairwise {
             .get_callback(opts.arrival_time);
 
         let mut hooks = SimulationHooks::default();
-        hooks.set_on_simulation_finish_hook(Box::new(hooks::on_simulation_finish_hook));
+        hooks
+            .set_on_simulation_finish_hook(Box::new(hooks::on_simulation_finish_hook))
+            .add_invariant(
+                "averages are finite",
+                1,
+                Box::new(hooks::averages_are_finite),
+            );
 
-        let tick_interval = 0.001;
-        let metrics_interval = 0.001;
+        let tick_interval = SimDuration::from_millis(1.0);
+        let metrics_interval = SimDuration::from_millis(1.0);
 
         // init
         for peer_id in 0..ctx.peers.len() {
-            engine::add_timer(ctx, OrderedFloat(0.0), StartTimer { peer_id });
+            engine::add_timer(ctx, SimTime::ZERO, StartTimer { peer_id });
         }
 
         engine::add_timer(
             ctx,
-            OrderedFloat(tick_interval),
+            SimTime::ZERO + tick_interval,
             TickTimer {
                 interval: tick_interval,
             },
@@ -93,7 +113,7 @@ impl Scenario for FlowUpdatingPairwise {
 
         engine::add_timer(
             ctx,
-            OrderedFloat(0.0),
+            SimTime::ZERO,
             MetricsTimer {
                 interval: metrics_interval,
             },
@@ -102,3 +122,38 @@ impl Scenario for FlowUpdatingPairwise {
         engine::run(ctx, &hooks, opts.deadline);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{internal::core::builtins::topologies::RingTopology, testing::TestSimulation};
+
+    #[test]
+    fn peers_converge_to_the_average() {
+        let values = [10, 20, 30, 60];
+        let mut sim = TestSimulation::new(42);
+        for value in values {
+            sim.add_peer(FlowUpdatingPairwisePeer::new(0.0, 0.0, value));
+        }
+        sim.connect::<RingTopology>();
+        for peer_id in 0..values.len() {
+            sim.add_timer(0.0, StartTimer { peer_id });
+        }
+        sim.add_timer(
+            0.001,
+            TickTimer {
+                interval: SimDuration::from_millis(1.0),
+            },
+        );
+
+        sim.assert_eventually(60.0, |ctx| {
+            ctx.peers.iter().all(|peer| {
+                peer.downcast_ref::<FlowUpdatingPairwisePeer>()
+                    .is_some_and(|peer| (peer.last_avg - 30.0).abs() < 1e-3)
+            })
+        });
+        sim.assert_delivered::<FlowUpdatingPairwiseMessage>(0, 1)
+            .assert_delivered::<FlowUpdatingPairwiseMessage>(0, 3)
+            .assert_not_delivered::<FlowUpdatingPairwiseMessage>(0, 2);
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::internal::core::{
@@ -7,6 +8,7 @@ use crate::internal::core::{
 
 use super::callbacks;
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FlowUpdatingPairwisePeer {
     pub peer_info: PeerInfo,
     pub value: i32,
@@ -16,7 +18,7 @@ pub struct FlowUpdatingPairwisePeer {
     pub last_avg: f64,
 }
 
-define_custom_peer!(FlowUpdatingPairwisePeer);
+define_custom_peer!(FlowUpdatingPairwisePeer, debug, snapshot);
 
 impl FlowUpdatingPairwisePeer {
     pub fn new(x: f64, y: f64, value: i32) -> Self {
@@ -1,35 +1,50 @@
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{
-    internal::core::{Context, engine, events::Timer, log, macros::get_peer_of_type},
+    internal::core::{
+        Context, engine,
+        events::Timer,
+        log,
+        macros::{get_peer_of_type, impl_snapshot},
+        time::SimDuration,
+    },
     scenarios::flow_updating_pairwise::message::FlowUpdatingPairwiseMessage,
 };
 
 use super::{algorithms, peer::FlowUpdatingPairwisePeer};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TickTimer {
-    pub interval: f64,
+    pub interval: SimDuration,
 }
 
 impl Timer for TickTimer {
+    impl_snapshot!();
+
     fn fire(&self, ctx: &mut Context) {
         log::trace(ctx, "TickTimer fired");
         engine::add_timer(ctx, ctx.clock + self.interval, self.clone());
 
         // call tick for every peer
         for peer_id in 0..ctx.peers.len() {
-            algorithms::tick(ctx, peer_id);
+            log::as_peer(ctx, peer_id, |ctx| algorithms::tick(ctx, peer_id));
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartTimer {
     pub peer_id: usize,
 }
 
 impl Timer for StartTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.peer_id)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         if let Some(neighbors) = engine::get_neighbors_alive(ctx, self.peer_id) {
             for neigh_id in neighbors {
@@ -53,12 +68,14 @@ impl Timer for StartTimer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsTimer {
-    pub interval: f64,
+    pub interval: SimDuration,
 }
 
 impl Timer for MetricsTimer {
+    impl_snapshot!();
+
     fn fire(&self, ctx: &mut Context) {
         engine::add_timer(ctx, ctx.clock + self.interval, self.clone());
 
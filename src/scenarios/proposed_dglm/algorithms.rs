@@ -165,14 +165,9 @@ fn handle_iter(ctx: &mut Context, peer_id: usize, sender: usize, r_remote: Mat<f
 pub fn get_node_ids(ctx: &mut Context, peer_id: usize) {
     log::trace(ctx, format!("peer {peer_id} get node ids"));
 
-    let mut peer_ids_filtered: Vec<usize> = engine::get_neighbors_alive(ctx, peer_id)
-        .map(|neighbors| {
-            neighbors
-                .into_iter()
-                .filter(|&neigh_id| ctx.peers.get(neigh_id).is_some_and(|p| p.is::<PGlmPeer>()))
-                .collect()
-        })
-        .unwrap_or_default();
+    let mut peer_ids_filtered: Vec<usize> = engine::neighbors_alive(ctx, peer_id)
+        .filter(|&neigh_id| ctx.peers.get(neigh_id).is_some_and(|p| p.is::<PGlmPeer>()))
+        .collect();
 
     let peer: &mut PGlmPeer = get_peer_of_type!(ctx, peer_id, PGlmPeer).expect("peer should exist");
 
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProposedDglmConfig {
+    /// Kills peer 0 at 0.1 seconds
+    pub kill_peer: bool,
+    /// Peer 0 starts dead and is revived at 1.0 seconds
+    pub revive_peer: bool,
+}
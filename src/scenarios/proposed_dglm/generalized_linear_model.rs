@@ -2,6 +2,7 @@ use faer::{
     Mat,
     linalg::{solvers::DenseSolveCore, triangular_solve::solve_upper_triangular_in_place},
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     family::{FamilyEnum, binomial, gaussian},
@@ -14,7 +15,7 @@ use super::{
 pub const DEFAULT_MAXIT: usize = 25;
 pub const DEFAULT_TOL: f64 = 1.0e-10;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneralizedLinearModel {
     pub r_local: Mat<f64>,
     pub coefficients: Mat<f64>,
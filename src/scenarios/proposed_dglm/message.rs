@@ -1,20 +1,23 @@
 use faer::Mat;
+use serde::{Deserialize, Serialize};
 
-use crate::internal::core::Message;
+use crate::internal::core::{Message, macros::impl_snapshot};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PGlmSumRowsMessage {
     pub origin: usize,
     pub nrows: usize,
     pub hash: u64,
 }
 impl Message for PGlmSumRowsMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         1 + 1 + 8
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PGlmConcatMessage {
     pub origin: usize,
     pub r_remote: Mat<f64>,
@@ -22,6 +25,8 @@ pub struct PGlmConcatMessage {
     pub hash: u64,
 }
 impl Message for PGlmConcatMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         let (r, c) = self.r_remote.shape();
 
@@ -29,12 +34,14 @@ impl Message for PGlmConcatMessage {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscoveryMessage {
     pub origin: usize,
     pub nodes: Vec<usize>,
 }
 impl Message for DiscoveryMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         let n_nodes = self.nodes.len();
 
@@ -42,24 +49,28 @@ impl Message for DiscoveryMessage {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReqSumRowsMessage {
     pub needs: Vec<usize>,
     pub hash: u64,
 }
 impl Message for ReqSumRowsMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         self.needs.len() as u64 + 8
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReqConcatMessage {
     pub needs: Vec<usize>,
     pub iter: usize,
     pub hash: u64,
 }
 impl Message for ReqConcatMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         self.needs.len() as u64 + 1 + 8
     }
@@ -1,5 +1,6 @@
 mod algorithms;
 mod callbacks;
+mod config;
 mod data;
 mod discovery;
 mod family;
@@ -10,12 +11,14 @@ mod peer;
 mod timers;
 mod utils;
 
+use config::ProposedDglmConfig;
 use data::{ModelData, chunk_nx, model_beta, model_data};
 use faer::Mat;
-use ordered_float::OrderedFloat;
+use message::{
+    DiscoveryMessage, PGlmConcatMessage, PGlmSumRowsMessage, ReqConcatMessage, ReqSumRowsMessage,
+};
 use peer::PGlmPeer;
 use rand::Rng;
-use serde_yaml::Value;
 
 use crate::{
     internal::{
@@ -26,6 +29,9 @@ use crate::{
             macros::get_peer_of_type,
             options::{ExperimentOptions, Scenario},
             peer::CustomPeer,
+            rng::RngStream,
+            snapshot::SnapshotRegistry,
+            time::{SimDuration, SimTime},
         },
     },
     scenarios::proposed_dglm::timers::{KillTimer, ReviveTimer, StartTimer, TimeoutTimer},
@@ -34,6 +40,8 @@ use crate::{
 pub struct ProposedDglm;
 
 impl Scenario for ProposedDglm {
+    type Config = ProposedDglmConfig;
+
     fn name() -> &'static str {
         "proposed_dglm"
     }
@@ -42,7 +50,26 @@ impl Scenario for ProposedDglm {
         "A proposed implementation of the distributed generalized linear model algorithm."
     }
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions) {
+    fn register_snapshot_types(registry: &mut SnapshotRegistry) {
+        registry
+            .register_peer::<PGlmPeer>()
+            .register_message::<PGlmSumRowsMessage>()
+            .register_message::<PGlmConcatMessage>()
+            .register_message::<DiscoveryMessage>()
+            .register_message::<ReqSumRowsMessage>()
+            .register_message::<ReqConcatMessage>()
+            .register_timer::<KillTimer>()
+            .register_timer::<StartTimer>()
+            .register_timer::<TimeoutTimer>()
+            .register_timer::<ReviveTimer>();
+    }
+
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: ProposedDglmConfig,
+    ) {
         let n_peers: usize = opts.topology.n_peers;
 
         let data: ModelData = match model_data("glm") {
@@ -68,13 +95,16 @@ impl Scenario for ProposedDglm {
         let x_chunks = chunk_nx(data.x, n_peers);
         let y_chunks = chunk_nx(data.y, n_peers);
 
-        for (i, (x, y)) in x_chunks.into_iter().zip(y_chunks.into_iter()).enumerate() {
+        for (i, (x, y)) in x_chunks.into_iter().zip(y_chunks).enumerate() {
             let (pos_x, pos_y) = match opts.topology.positions.as_ref().and_then(|v| v.get(i)) {
                 Some(&(px, py, _)) => (px, py),
-                None => (
-                    ctx.rng.random_range(-100.0..=100.0) * 1000.0,
-                    ctx.rng.random_range(-100.0..=100.0) * 1000.0,
-                ),
+                None => {
+                    let rng = ctx.rng_for(RngStream::Topology);
+                    (
+                        rng.random_range(-100.0..=100.0) * 1000.0,
+                        rng.random_range(-100.0..=100.0) * 1000.0,
+                    )
+                }
             };
 
             engine::add_peer(ctx, PGlmPeer::new(pos_x, pos_y, x, y));
@@ -89,24 +119,28 @@ impl Scenario for ProposedDglm {
 
         // init
         for peer_id in 0..ctx.peers.len() {
-            engine::add_timer(ctx, OrderedFloat(0.0), StartTimer { peer_id });
+            engine::add_timer(ctx, SimTime::ZERO, StartTimer { peer_id });
         }
 
         // tick
-        engine::add_timer(ctx, OrderedFloat(0.01), TimeoutTimer { interval: 0.5 });
-
-        if let Some(custom) = opts.extra_args {
-            if let Some(Value::Bool(true)) = custom.get("kill_peer") {
-                engine::add_timer(ctx, OrderedFloat(0.1), KillTimer::new(0));
-            }
-            if let Some(Value::Bool(true)) = custom.get("revive_peer") {
-                let target = 0;
-
-                let peer: &mut PGlmPeer =
-                    get_peer_of_type!(ctx, target, PGlmPeer).expect("peer should exist");
-                peer.kill();
-                engine::add_timer(ctx, OrderedFloat(1.0), ReviveTimer { target });
-            }
+        engine::add_timer(
+            ctx,
+            SimTime::from_secs(0.01),
+            TimeoutTimer {
+                interval: SimDuration::from_secs(0.5),
+            },
+        );
+
+        if config.kill_peer {
+            engine::add_timer(ctx, SimTime::from_secs(0.1), KillTimer::new(0));
+        }
+        if config.revive_peer {
+            let target = 0;
+
+            let peer: &mut PGlmPeer =
+                get_peer_of_type!(ctx, target, PGlmPeer).expect("peer should exist");
+            peer.kill();
+            engine::add_timer(ctx, SimTime::from_secs(1.0), ReviveTimer { target });
         }
 
         let mut hooks = SimulationHooks::default();
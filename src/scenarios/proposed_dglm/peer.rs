@@ -4,6 +4,7 @@ use std::{
 };
 
 use faer::Mat;
+use serde::{Deserialize, Serialize};
 
 use crate::internal::core::{
     macros::define_custom_peer,
@@ -16,6 +17,7 @@ use super::{
     generalized_linear_model::{self, GeneralizedLinearModel},
 };
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PGlmState {
     pub initial_model: GeneralizedLinearModel,
     pub model: GeneralizedLinearModel,
@@ -30,12 +32,13 @@ pub struct PGlmState {
     pub hash: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PGlmPeer {
     pub peer_info: PeerInfo,
     pub state: PGlmState,
 }
 
-define_custom_peer!(PGlmPeer);
+define_custom_peer!(PGlmPeer, debug, snapshot);
 
 impl PGlmPeer {
     pub fn new(pos_x: f64, pos_y: f64, x: Mat<f64>, y: Mat<f64>) -> Self {
@@ -1,11 +1,18 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     internal::core::{
-        Context, engine, events::Timer, log, macros::get_peer_of_type, peer::CustomPeer,
+        Context, engine,
+        events::Timer,
+        log,
+        macros::{get_peer_of_type, impl_snapshot},
+        peer::CustomPeer,
+        time::SimDuration,
     },
     scenarios::proposed_dglm::{algorithms, peer::PGlmPeer},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KillTimer {
     pub target: usize,
 }
@@ -17,6 +24,12 @@ impl KillTimer {
 }
 
 impl Timer for KillTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.target)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         if let Some(target) = ctx.peers.get_mut(self.target) {
             target.kill();
@@ -25,12 +38,18 @@ impl Timer for KillTimer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartTimer {
     pub peer_id: usize,
 }
 
 impl Timer for StartTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.peer_id)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         let peer: &mut PGlmPeer =
             get_peer_of_type!(ctx, self.peer_id, PGlmPeer).expect("peer should exist");
@@ -42,12 +61,14 @@ impl Timer for StartTimer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeoutTimer {
-    pub interval: f64,
+    pub interval: SimDuration,
 }
 
 impl Timer for TimeoutTimer {
+    impl_snapshot!();
+
     fn fire(&self, ctx: &mut Context) {
         for peer_id in 0..ctx.peers.len() {
             if ctx.peers[peer_id].is::<PGlmPeer>() {
@@ -56,7 +77,7 @@ impl Timer for TimeoutTimer {
                 if !peer.is_alive() {
                     continue;
                 }
-                algorithms::timeout(ctx, peer_id);
+                log::as_peer(ctx, peer_id, |ctx| algorithms::timeout(ctx, peer_id));
             }
         }
 
@@ -72,12 +93,18 @@ impl Timer for TimeoutTimer {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReviveTimer {
     pub target: usize,
 }
 
 impl Timer for ReviveTimer {
+    impl_snapshot!();
+
+    fn peer(&self) -> Option<usize> {
+        Some(self.target)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         if let Some(target) = ctx.peers.get_mut(self.target) {
             target.revive();
@@ -48,7 +48,7 @@ pub fn mul_elementwise(a: &Mat<f64>, b: &Mat<f64>) -> Mat<f64> {
         // Scalar broadcast on a
         (1, 1, br, bc) => {
             let val = a.get(0, 0);
-            Mat::from_fn(br, bc, |i, j| a.get(i, j) * val)
+            Mat::from_fn(br, bc, |i, j| b.get(i, j) * val)
         }
         // Incompatible shapes
         _ => panic!("Cannot multiply matrices element-wise due to incompatible shapes"),
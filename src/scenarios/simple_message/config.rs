@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimpleMessageConfig {
+    /// Size in bytes of the message sent by peer 0 to its neighbors
+    pub msg_size: u64,
+}
@@ -1,11 +1,15 @@
-use crate::internal::core::Message;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::internal::core::{Message, macros::impl_snapshot};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmptyMessage {
     pub size: u64,
 }
 
 impl Message for EmptyMessage {
+    impl_snapshot!();
+
     fn size_bytes(&self) -> u64 {
         self.size
     }
@@ -1,3 +1,4 @@
+mod config;
 mod scenario;
 mod peers;
 mod messages;
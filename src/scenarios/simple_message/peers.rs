@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     internal::core::{
         Context, Message, log,
@@ -7,11 +9,12 @@ use crate::{
     scenarios::simple_message::messages::EmptyMessage,
 };
 
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SimplePeer {
     pub peer_info: PeerInfo,
 }
 
-define_custom_peer!(SimplePeer);
+define_custom_peer!(SimplePeer, debug, snapshot);
 
 impl SimplePeer {
     pub fn new(x: f64, y: f64) -> Self {
@@ -1,5 +1,3 @@
-use ordered_float::OrderedFloat;
-
 use crate::{
     internal::{
         Simulator,
@@ -7,16 +5,20 @@ use crate::{
             Context, engine,
             hooks::SimulationHooks,
             options::{ExperimentOptions, Scenario},
+            snapshot::SnapshotRegistry,
+            time::SimTime,
         },
     },
-    scenarios::simple_message::starts::StartTimer,
+    scenarios::simple_message::{messages::EmptyMessage, starts::StartTimer},
 };
 
-use super::peers::SimplePeer;
+use super::{config::SimpleMessageConfig, peers::SimplePeer};
 
 pub struct SimpleMessageScenario {}
 
 impl Scenario for SimpleMessageScenario {
+    type Config = SimpleMessageConfig;
+
     fn name() -> &'static str
     where
         Self: Sized,
@@ -31,7 +33,19 @@ impl Scenario for SimpleMessageScenario {
         "An implementation used to evaluate the simulator."
     }
 
-    fn start(ctx: &mut Context, simulator: &Simulator, opts: ExperimentOptions) {
+    fn register_snapshot_types(registry: &mut SnapshotRegistry) {
+        registry
+            .register_peer::<SimplePeer>()
+            .register_message::<EmptyMessage>()
+            .register_timer::<StartTimer>();
+    }
+
+    fn start(
+        ctx: &mut Context,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: SimpleMessageConfig,
+    ) {
         for i in 0..opts.topology.n_peers {
             let (pos_x, pos_y) = match opts.topology.positions.as_ref().and_then(|v| v.get(i)) {
                 Some(&(px, py, _)) => (px, py),
@@ -48,15 +62,14 @@ impl Scenario for SimpleMessageScenario {
             .arrival_time_registry
             .get_callback(opts.arrival_time);
 
-        let message_size = opts
-            .extra_args
-            .as_ref()
-            .and_then(|custom| custom.get("msg_size"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-
         // start event
-        engine::add_timer(ctx, OrderedFloat(0.0), StartTimer { message_size });
+        engine::add_timer(
+            ctx,
+            SimTime::ZERO,
+            StartTimer {
+                message_size: config.msg_size,
+            },
+        );
 
         engine::run(ctx, &SimulationHooks::default(), opts.deadline);
     }
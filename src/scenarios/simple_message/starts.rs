@@ -1,14 +1,23 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    internal::core::{Context, engine, events::Timer, log},
+    internal::core::{Context, engine, events::Timer, log, macros::impl_snapshot},
     scenarios::simple_message::messages::EmptyMessage,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartTimer {
     pub message_size: u64,
 }
 
 impl Timer for StartTimer {
+    impl_snapshot!();
+
+    // the messages are sent by peer 0
+    fn peer(&self) -> Option<usize> {
+        Some(0)
+    }
+
     fn fire(&self, ctx: &mut Context) {
         if let Some(neighbors) = engine::get_neighbors_alive(ctx, 0) {
             for neigh_id in neighbors {
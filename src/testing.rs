@@ -0,0 +1,319 @@
+//! Helpers to test scenarios. A [`TestSimulation`] has a fixed seed, keeps its trace
+//! and logs in memory and asserts on what happened during the run.
+
+use std::{any, env, fs, path::Path};
+
+use crate::internal::{
+    Simulator,
+    core::{
+        Context, Message, engine,
+        events::Timer,
+        experiment::LinkInfo,
+        hooks::SimulationHooks,
+        macros::define_custom_peer,
+        options::{ExperimentOptions, Scenario, Topology},
+        peer::{CustomPeer, PeerInfo},
+        time::SimTime,
+        trace::{TraceKind, TraceRecord, Tracer},
+        utils,
+    },
+    explore::{self, Exploration, ExploreOptions, ExploredRun},
+};
+
+/// Golden files are written instead of compared when this variable is set.
+pub const UPDATE_GOLDEN_VAR: &str = "TDES_UPDATE_GOLDEN";
+
+/// A peer without behaviour, the messages it receives are only traced.
+#[derive(Debug, Default)]
+pub struct TestPeer {
+    pub peer_info: PeerInfo,
+}
+
+define_custom_peer!(TestPeer, debug);
+
+/// A simulation run by a test.
+pub struct TestSimulation {
+    pub ctx: Context,
+}
+
+impl TestSimulation {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut ctx = Context::new(Some(seed), None, true);
+        ctx.tracer = Some(Tracer::Memory(Vec::new()));
+        ctx.logger.capture();
+        Self { ctx }
+    }
+
+    pub fn add_peer(&mut self, peer: impl CustomPeer + 'static) -> usize {
+        engine::add_peer(&mut self.ctx, peer)
+    }
+
+    /// Adds `n` peers without behaviour.
+    pub fn add_test_peers(&mut self, n: usize) -> &mut Self {
+        for _ in 0..n {
+            self.add_peer(TestPeer::default());
+        }
+        self
+    }
+
+    /// Links two peers in both directions.
+    pub fn link(&mut self, from: usize, to: usize, info: LinkInfo) -> &mut Self {
+        engine::add_twoway_link(&mut self.ctx, from, to, info);
+        self
+    }
+
+    /// Links every peer with a topology.
+    pub fn connect<T: Topology>(&mut self) -> &mut Self {
+        let n_peers = self.ctx.peers.len();
+        T::connect(&mut self.ctx, n_peers, None);
+        self.ctx.links.compact();
+        self
+    }
+
+    /// Adds a timer that fires at `time`, in seconds.
+    pub fn add_timer(&mut self, time: f64, timer: impl Timer + 'static) -> &mut Self {
+        engine::add_timer(&mut self.ctx, SimTime::from_secs(time), timer);
+        self
+    }
+
+    /// Sends a message at the current time, as if `from` had sent it.
+    pub fn send(&mut self, from: usize, to: usize, message: impl Message + 'static) -> &mut Self {
+        engine::send_message_to(&mut self.ctx, from, to, message);
+        self
+    }
+
+    /// Processes every event.
+    pub fn run(&mut self) -> &mut Self {
+        while engine::step(&mut self.ctx, None) {}
+        self
+    }
+
+    /// Processes the events until `time`, the clock is then at `time`.
+    pub fn run_until(&mut self, time: f64) -> &mut Self {
+        let time = SimTime::from_secs(time);
+        while engine::step(&mut self.ctx, Some(time)) {}
+        self.ctx.clock = self.ctx.clock.max(time);
+        self
+    }
+
+    /// Runs the simulation loop like scenarios do, checking the invariants of `hooks`.
+    /// A violation is kept in `ctx.violation`.
+    pub fn run_with_hooks(&mut self, hooks: &SimulationHooks) -> &mut Self {
+        engine::run(&mut self.ctx, hooks, None);
+        self
+    }
+
+    /// Starts a scenario in the simulation, it adds its peers and runs them to the end.
+    pub fn run_scenario<S: Scenario>(
+        &mut self,
+        simulator: &Simulator,
+        opts: ExperimentOptions,
+        config: S::Config,
+    ) -> &mut Self {
+        S::start(&mut self.ctx, simulator, opts, config);
+        self
+    }
+
+    /// The processed events and the dropped messages, in the order they happened.
+    #[must_use]
+    pub fn trace(&self) -> &[TraceRecord] {
+        match &self.ctx.tracer {
+            Some(Tracer::Memory(records)) => records,
+            _ => &[],
+        }
+    }
+
+    /// Every line logged by the simulation, whatever its level.
+    #[must_use]
+    pub fn log(&self) -> &[String] {
+        self.ctx.logger.captured()
+    }
+
+    #[track_caller]
+    #[must_use]
+    pub fn peer<P: CustomPeer>(&self, id: usize) -> &P {
+        self.ctx
+            .peers
+            .get(id)
+            .and_then(|peer| peer.downcast_ref::<P>())
+            .unwrap_or_else(|| panic!("peer {id} is not a {}", any::type_name::<P>()))
+    }
+
+    #[track_caller]
+    pub fn peer_mut<P: CustomPeer>(&mut self, id: usize) -> &mut P {
+        self.ctx
+            .peers
+            .get_mut(id)
+            .and_then(|peer| peer.downcast_mut::<P>())
+            .unwrap_or_else(|| panic!("peer {id} is not a {}", any::type_name::<P>()))
+    }
+
+    /// The deliveries or drops of messages of type `M` from `from` to `to`.
+    pub fn messages<M: Message>(
+        &self,
+        kind: TraceKind,
+        from: usize,
+        to: usize,
+    ) -> impl Iterator<Item = &TraceRecord> {
+        let message_type = utils::short_type_name(any::type_name::<M>());
+        self.trace().iter().filter(move |record| {
+            record.kind == kind
+                && record.sender == Some(from)
+                && record.receiver == Some(to)
+                && record.message_type.as_deref() == Some(message_type)
+        })
+    }
+
+    /// The messages of type `M` from `from` that `to` received.
+    pub fn delivered<M: Message>(
+        &self,
+        from: usize,
+        to: usize,
+    ) -> impl Iterator<Item = &TraceRecord> {
+        self.messages::<M>(TraceKind::MessageDelivery, from, to)
+    }
+
+    #[track_caller]
+    pub fn assert_delivered<M: Message>(&self, from: usize, to: usize) -> &Self {
+        if self.delivered::<M>(from, to).next().is_none() {
+            panic!(
+                "no {} was delivered from peer {from} to peer {to}\n{}",
+                utils::short_type_name(any::type_name::<M>()),
+                self.describe_messages(from, to)
+            );
+        }
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_not_delivered<M: Message>(&self, from: usize, to: usize) -> &Self {
+        if let Some(record) = self.delivered::<M>(from, to).next() {
+            panic!(
+                "expected no {} from peer {from} to peer {to}, got {record}",
+                utils::short_type_name(any::type_name::<M>())
+            );
+        }
+        self
+    }
+
+    /// Asserts that a message was dropped for `reason`, `loss`, `dead`, `not_connected` or `no_latency`.
+    #[track_caller]
+    pub fn assert_dropped<M: Message>(&self, from: usize, to: usize, reason: &str) -> &Self {
+        if !self
+            .messages::<M>(TraceKind::MessageDrop, from, to)
+            .any(|record| record.reason.as_deref() == Some(reason))
+        {
+            panic!(
+                "no {} from peer {from} to peer {to} was dropped ({reason})\n{}",
+                utils::short_type_name(any::type_name::<M>()),
+                self.describe_messages(from, to)
+            );
+        }
+        self
+    }
+
+    /// Processes events until `predicate` holds, failing if it does not by `time`.
+    /// The simulation stops at the first event after which it holds.
+    #[track_caller]
+    pub fn assert_eventually(
+        &mut self,
+        time: f64,
+        predicate: impl Fn(&Context) -> bool,
+    ) -> &mut Self {
+        while !predicate(&self.ctx) {
+            if !engine::step(&mut self.ctx, Some(SimTime::from_secs(time))) {
+                panic!(
+                    "the condition did not hold by {time}, the last event was at {}",
+                    self.ctx.clock
+                );
+            }
+        }
+        self
+    }
+
+    /// Compares the log with a golden file, see [`assert_golden`].
+    #[track_caller]
+    pub fn assert_golden_log(&self, path: impl AsRef<Path>) {
+        assert_golden(path, &self.log().join("\n"));
+    }
+
+    fn describe_messages(&self, from: usize, to: usize) -> String {
+        let records: Vec<String> = self
+            .trace()
+            .iter()
+            .filter(|record| record.sender == Some(from) && record.receiver == Some(to))
+            .map(|record| format!("  {record}"))
+            .collect();
+
+        if records.is_empty() {
+            format!("no messages from peer {from} to peer {to} were traced")
+        } else {
+            format!(
+                "messages from peer {from} to peer {to}:\n{}",
+                records.join("\n")
+            )
+        }
+    }
+}
+
+/// Explores the delivery orders of the simulation that `build` sets up, returning
+/// the hooks with its invariants. Each run starts from a new simulation.
+pub fn explore(
+    options: &ExploreOptions,
+    seed: u64,
+    build: impl Fn(&mut TestSimulation) -> SimulationHooks,
+) -> Exploration {
+    explore::explore(options, |interleaving| {
+        let mut sim = TestSimulation::new(seed);
+        let hooks = build(&mut sim);
+        sim.ctx.interleaving = Some(Box::new(interleaving));
+        sim.run_with_hooks(&hooks);
+
+        ExploredRun {
+            interleaving: *sim
+                .ctx
+                .interleaving
+                .take()
+                .expect("the run should keep its interleaving"),
+            violation: sim.ctx.violation.take(),
+        }
+    })
+}
+
+/// Compares `actual` with the contents of a golden file. The file is written
+/// instead when `TDES_UPDATE_GOLDEN` is set, a missing file fails otherwise.
+#[track_caller]
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("golden file directory should be created");
+        }
+        fs::write(path, actual).expect("golden file should be written");
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "{} does not exist, set {UPDATE_GOLDEN_VAR}=1 to create it",
+        path.display()
+    );
+
+    let expected = fs::read_to_string(path).expect("golden file should be readable");
+    if expected != actual {
+        let line = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(expected, actual)| expected != actual)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "{} differs from the output at line {}, set {UPDATE_GOLDEN_VAR}=1 to update it\nexpected: {:?}\n  actual: {:?}",
+            path.display(),
+            line + 1,
+            expected.lines().nth(line).unwrap_or_default(),
+            actual.lines().nth(line).unwrap_or_default(),
+        );
+    }
+}
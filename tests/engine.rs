@@ -0,0 +1,360 @@
+use std::{env, fs, path::Path, process};
+
+use tdes::{
+    internal::core::{experiment::LinkKind, invariants::EventHistory, trace::TraceKind},
+    prelude::*,
+    testing::{TestPeer, TestSimulation},
+};
+
+#[derive(Debug, Clone)]
+struct Ping {
+    hops: u32,
+}
+
+impl Message for Ping {
+    fn size_bytes(&self) -> u64 {
+        8
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Pong;
+
+impl Message for Pong {
+    fn size_bytes(&self) -> u64 {
+        8
+    }
+}
+
+/// Forwards pings to its next neighbour until they run out of hops.
+#[derive(Debug)]
+struct RelayPeer {
+    peer_info: PeerInfo,
+    received: u32,
+}
+
+define_custom_peer!(RelayPeer, debug);
+
+impl RelayPeer {
+    fn new() -> Self {
+        Self {
+            peer_info: PeerInfo::default().with_on_message_receive(relay),
+            received: 0,
+        }
+    }
+}
+
+fn relay(ctx: &mut Context, _sender: usize, receiver: usize, msg: &dyn Message) {
+    let peer = get_peer_of_type!(ctx, receiver, RelayPeer).expect("peer should exist");
+    peer.received += 1;
+
+    if let Some(ping) = msg.downcast_ref::<Ping>()
+        && ping.hops > 0
+    {
+        let next = (receiver + 1) % ctx.peers.len();
+        engine::send_message_to(
+            ctx,
+            receiver,
+            next,
+            Ping {
+                hops: ping.hops - 1,
+            },
+        );
+    }
+}
+
+fn latency(value: f64) -> LinkInfo {
+    Some(LinkKind::Latency(SimDuration::from_secs(value)))
+}
+
+fn relay_ring(seed: u64, n_peers: usize) -> TestSimulation {
+    let mut sim = TestSimulation::new(seed);
+    for _ in 0..n_peers {
+        sim.add_peer(RelayPeer::new());
+    }
+    sim.connect::<tdes::internal::core::builtins::topologies::RingTopology>();
+    sim
+}
+
+#[test]
+fn delivers_messages_over_links() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(3)
+        .link(0, 1, latency(0.5))
+        .send(0, 1, Ping { hops: 0 })
+        .run();
+
+    sim.assert_delivered::<Ping>(0, 1)
+        .assert_not_delivered::<Pong>(0, 1)
+        .assert_not_delivered::<Ping>(0, 2);
+    assert_eq!(sim.ctx.clock.as_secs(), 0.5);
+    assert_eq!(sim.delivered::<Ping>(0, 1).count(), 1);
+}
+
+#[test]
+fn drops_messages_between_peers_that_are_not_linked() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2).send(0, 1, Ping { hops: 0 }).run();
+
+    sim.assert_dropped::<Ping>(0, 1, "not_connected")
+        .assert_not_delivered::<Ping>(0, 1);
+}
+
+#[test]
+fn drops_every_message_with_a_drop_rate_of_one() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2).link(0, 1, latency(1.0));
+    sim.ctx.set_drop_rate(1.0);
+    sim.send(0, 1, Ping { hops: 0 }).run();
+
+    sim.assert_dropped::<Ping>(0, 1, "loss")
+        .assert_not_delivered::<Ping>(0, 1);
+}
+
+#[test]
+fn drops_messages_to_dead_peers() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2)
+        .link(0, 1, latency(1.0))
+        .send(0, 1, Ping { hops: 0 });
+    sim.ctx.peers[1].kill();
+    sim.run();
+
+    let trace = sim.trace();
+    assert_eq!(trace.len(), 1, "{trace:?}");
+    assert_eq!(trace[0].kind, TraceKind::MessageDrop);
+    assert_eq!(trace[0].reason.as_deref(), Some("dead"));
+    assert!(!sim.peer::<TestPeer>(1).is_alive());
+}
+
+#[test]
+fn drops_messages_whose_latency_cannot_be_calculated() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2).link(0, 1, None);
+    sim.ctx.message_delay_cb = |_, _, _| None;
+    sim.send(0, 1, Ping { hops: 0 }).run();
+
+    sim.assert_dropped::<Ping>(0, 1, "no_latency")
+        .assert_not_delivered::<Ping>(0, 1);
+    let total = sim.ctx.network_stats.total();
+    assert_eq!(total.sent.messages, 1);
+    assert_eq!(total.dropped_no_latency.messages, 1);
+}
+
+#[test]
+fn run_until_stops_at_the_given_time() {
+    let mut sim = relay_ring(3, 4);
+    sim.send(0, 1, Ping { hops: 10 }).run_until(2.5);
+
+    assert_eq!(sim.ctx.clock.as_secs(), 2.5);
+    assert_eq!(sim.trace().len(), 2);
+    assert!(sim.ctx.events_left() > 0);
+
+    sim.run();
+    let received: u32 = (0..4).map(|id| sim.peer::<RelayPeer>(id).received).sum();
+    assert_eq!(received, 11);
+}
+
+#[test]
+fn eventually_stops_once_the_condition_holds() {
+    let mut sim = relay_ring(3, 4);
+    sim.send(0, 1, Ping { hops: 10 })
+        .assert_eventually(20.0, |ctx| {
+            ctx.peers[0]
+                .downcast_ref::<RelayPeer>()
+                .is_some_and(|peer| peer.received == 2)
+        });
+
+    // peer 0 receives the 4th and 8th hop, one second each
+    assert_eq!(sim.ctx.clock.as_secs(), 8.0);
+    sim.assert_delivered::<Ping>(3, 0);
+}
+
+#[test]
+#[should_panic(expected = "did not hold by 5")]
+fn eventually_fails_when_the_condition_never_holds() {
+    let mut sim = relay_ring(3, 4);
+    sim.send(0, 1, Ping { hops: 10 })
+        .assert_eventually(5.0, |ctx| ctx.peers.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "no Pong was delivered from peer 0 to peer 1")]
+fn failed_assertions_describe_the_messages() {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2)
+        .link(0, 1, latency(1.0))
+        .send(0, 1, Ping { hops: 0 })
+        .run()
+        .assert_delivered::<Pong>(0, 1);
+}
+
+#[test]
+fn same_seed_gives_the_same_trace() {
+    let run = |seed| {
+        let mut sim = relay_ring(seed, 5);
+        sim.ctx.message_delay_cb = |ctx, _, _| {
+            use rand::Rng;
+            Some(SimDuration::from_secs(
+                ctx.rng_for(RngStream::Topology).random_range(0.1..1.0),
+            ))
+        };
+        sim.ctx.set_drop_rate(0.2);
+        for peer in 0..5 {
+            sim.send(peer, (peer + 1) % 5, Ping { hops: 20 });
+        }
+        sim.run();
+        sim.trace().to_vec()
+    };
+
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+}
+
+#[test]
+fn violated_invariants_stop_the_run() {
+    let mut sim = relay_ring(3, 4);
+    sim.ctx.event_history = EventHistory::new(3);
+    let mut hooks = SimulationHooks::default();
+    hooks.add_invariant(
+        "peer 0 receives one ping",
+        1,
+        Box::new(|ctx| match ctx.peers[0].downcast_ref::<RelayPeer>() {
+            Some(peer) if peer.received > 1 => Err(format!("it received {}", peer.received)),
+            _ => Ok(()),
+        }),
+    );
+    sim.send(0, 1, Ping { hops: 10 }).run_with_hooks(&hooks);
+
+    let violation = sim
+        .ctx
+        .violation
+        .as_ref()
+        .expect("the invariant should fail");
+    assert_eq!(violation.invariant, "peer 0 receives one ping");
+    assert_eq!(violation.reason, "it received 2");
+    assert_eq!((violation.clock, violation.events_processed), (8.0, 8));
+    assert_eq!(violation.seed, 3);
+
+    let receivers: Vec<Option<usize>> = violation
+        .recent_events
+        .iter()
+        .map(|record| record.receiver)
+        .collect();
+    assert_eq!(receivers, vec![Some(2), Some(3), Some(0)]);
+    // the run stopped at the violation
+    assert_eq!(sim.trace().len(), 8);
+}
+
+#[derive(Debug)]
+struct Noop;
+
+impl Timer for Noop {
+    fn fire(&self, _ctx: &mut Context) {}
+}
+
+// ten hops of 0.1 between two peers, with a timer at 1.0
+fn hops_and_timer(resolution: Option<SimDuration>) -> TestSimulation {
+    let mut sim = TestSimulation::new(1);
+    if let Some(resolution) = resolution {
+        sim.ctx
+            .set_time_resolution(resolution)
+            .expect("the resolution should be valid");
+    }
+    sim.add_peer(RelayPeer::new());
+    sim.add_peer(RelayPeer::new());
+    sim.link(0, 1, latency(0.1))
+        .add_timer(1.0, Noop)
+        .send(0, 1, Ping { hops: 9 })
+        .run();
+    sim
+}
+
+#[test]
+fn ticks_keep_sums_of_intervals_exact() {
+    let last_kinds = |sim: &TestSimulation| -> Vec<TraceKind> {
+        sim.trace()[9..].iter().map(|record| record.kind).collect()
+    };
+
+    // 0.1 added ten times is just under 1.0, so the last hop arrives before the timer
+    let continuous = hops_and_timer(None);
+    assert_eq!(
+        last_kinds(&continuous),
+        vec![TraceKind::MessageDelivery, TraceKind::Timer]
+    );
+    assert!(continuous.trace()[9].timestamp < 1.0);
+
+    // with ticks both are at 1.0 and the timer, added first, goes first
+    let ticks = hops_and_timer(Some("1ns".parse().unwrap()));
+    assert_eq!(
+        last_kinds(&ticks),
+        vec![TraceKind::Timer, TraceKind::MessageDelivery]
+    );
+    assert_eq!(ticks.ctx.clock, SimTime::from_secs(1.0));
+    assert_eq!(ticks.ctx.time_base().ticks(ticks.ctx.clock), 1_000_000_000);
+
+    // a second is not a whole number of 3ms ticks
+    let mut invalid = TestSimulation::new(1);
+    assert!(
+        invalid
+            .ctx
+            .set_time_resolution("3ms".parse().unwrap())
+            .is_err()
+    );
+}
+
+/// Logs a greeting for the peer it acts for, if any.
+#[derive(Debug)]
+struct Greeting {
+    peer: Option<usize>,
+}
+
+impl Timer for Greeting {
+    fn peer(&self) -> Option<usize> {
+        self.peer
+    }
+
+    fn fire(&self, ctx: &mut Context) {
+        log::info(ctx, format!("greeting of {:?}", self.peer));
+    }
+}
+
+// runs a greeting of peer 1 and one of no peer, logging to files in `dir`
+fn greetings(dir: &Path, setup: impl FnOnce(&mut TestSimulation)) {
+    let mut sim = TestSimulation::new(1);
+    sim.add_test_peers(2);
+    sim.ctx.logger.set_log_file(dir.join("run.log")).unwrap();
+    setup(&mut sim);
+    sim.add_timer(1.0, Greeting { peer: Some(1) })
+        .add_timer(2.0, Greeting { peer: None })
+        .run();
+    sim.ctx.logger.close_log_file();
+}
+
+#[test]
+fn timer_logs_go_to_the_file_of_their_peer() {
+    let dir = env::temp_dir().join(format!("tdes_peer_logs_{}", process::id()));
+    greetings(&dir, |sim| {
+        sim.ctx.logger.set_peer_log_dir(dir.join("peers")).unwrap();
+    });
+
+    let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+    assert!(read("peers/peer_1.log").contains("greeting of Some(1)"));
+    let shared = read("run.log");
+    assert!(shared.contains("greeting of None"));
+    assert!(!shared.contains("greeting of Some(1)"));
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn timer_logs_are_filtered_by_their_peer() {
+    let dir = env::temp_dir().join(format!("tdes_filtered_logs_{}", process::id()));
+    greetings(&dir, |sim| {
+        sim.ctx.logger.set_filter("peer=0".parse().unwrap());
+    });
+
+    let log = fs::read_to_string(dir.join("run.log")).unwrap();
+    assert!(log.contains("greeting of None"));
+    assert!(!log.contains("greeting of Some(1)"));
+    fs::remove_dir_all(&dir).ok();
+}
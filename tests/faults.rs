@@ -0,0 +1,196 @@
+use tdes::{
+    internal::{
+        core::{
+            experiment::LinkKind,
+            faults::{Fault, FaultPlan},
+            invariants::InvariantViolation,
+        },
+        shrink,
+    },
+    prelude::*,
+    testing::{TestPeer, TestSimulation},
+};
+
+#[derive(Debug, Clone)]
+struct Numbered(u64);
+
+impl Message for Numbered {
+    fn size_bytes(&self) -> u64 {
+        8
+    }
+}
+
+/// Remembers the numbers of the messages it received.
+#[derive(Debug)]
+struct SequencePeer {
+    peer_info: PeerInfo,
+    heard: Vec<u64>,
+}
+
+define_custom_peer!(SequencePeer, debug);
+
+fn hear(ctx: &mut Context, _sender: usize, receiver: usize, msg: &dyn Message) {
+    let peer = get_peer_of_type!(ctx, receiver, SequencePeer).expect("peer should exist");
+    if let Some(Numbered(n)) = msg.downcast_ref::<Numbered>() {
+        peer.heard.push(*n);
+    }
+}
+
+/// Peer 0 hears from peer 1 over a link of one second, peer 2 is also linked to peer 1.
+fn sequence(seed: u64, faults: &[Fault]) -> TestSimulation {
+    let mut sim = TestSimulation::new(seed);
+    sim.add_peer(SequencePeer {
+        peer_info: PeerInfo::default().with_on_message_receive(hear),
+        heard: Vec::new(),
+    });
+    sim.add_test_peers(2)
+        .link(0, 1, Some(LinkKind::Latency(SimDuration::from_secs(1.0))))
+        .link(1, 2, Some(LinkKind::Latency(SimDuration::from_secs(1.0))));
+    FaultPlan::install(&mut sim.ctx, faults);
+    sim
+}
+
+fn send_numbered(sim: &mut TestSimulation, n: u64) -> &mut TestSimulation {
+    for i in 0..n {
+        sim.send(1, 0, Numbered(i));
+    }
+    sim
+}
+
+fn in_order() -> SimulationHooks {
+    let mut hooks = SimulationHooks::default();
+    hooks.add_invariant(
+        "messages are heard once and in order",
+        1,
+        Box::new(|ctx| {
+            let heard = &ctx.peers[0]
+                .downcast_ref::<SequencePeer>()
+                .expect("peer 0 should be a SequencePeer")
+                .heard;
+            match heard.iter().zip(0..).find(|&(&n, i)| n != i) {
+                Some((n, i)) => Err(format!("message {n} was heard instead of {i}")),
+                None => Ok(()),
+            }
+        }),
+    );
+    hooks
+}
+
+fn replay(seed: u64, faults: &[Fault]) -> Option<InvariantViolation> {
+    let mut sim = sequence(seed, faults);
+    send_numbered(&mut sim, 20).run_with_hooks(&in_order());
+    sim.ctx.violation.take()
+}
+
+#[test]
+fn planned_message_faults_hit_the_nth_message() {
+    let faults = [
+        Fault::Drop {
+            from: 1,
+            to: 0,
+            nth: 1,
+        },
+        Fault::Duplicate {
+            from: 1,
+            to: 0,
+            nth: 3,
+        },
+    ];
+    let mut sim = sequence(1, &faults);
+    send_numbered(&mut sim, 5).run();
+
+    assert_eq!(sim.peer::<SequencePeer>(0).heard, vec![0, 2, 3, 3, 4]);
+    sim.assert_dropped::<Numbered>(1, 0, "loss");
+    let plan = sim.ctx.faults.as_ref().expect("the plan should be kept");
+    assert_eq!(plan.injected(), faults);
+}
+
+#[test]
+fn kills_and_cuts_happen_at_their_time() {
+    let faults = [
+        Fault::Kill {
+            peer: 0,
+            at: SimTime::from_secs(1.5),
+        },
+        Fault::Cut {
+            from: 1,
+            to: 2,
+            at: SimTime::from_secs(0.5),
+        },
+    ];
+    let mut sim = sequence(1, &faults);
+    sim.send(1, 0, Numbered(0)).run_until(1.0);
+    sim.send(1, 0, Numbered(1)).send(1, 2, Numbered(2)).run();
+
+    assert_eq!(sim.peer::<SequencePeer>(0).heard, vec![0]);
+    assert!(!sim.peer::<SequencePeer>(0).is_alive());
+    sim.assert_dropped::<Numbered>(1, 0, "dead")
+        .assert_dropped::<Numbered>(1, 2, "not_connected");
+    assert!(sim.peer::<TestPeer>(2).is_alive());
+
+    let injected = sim
+        .ctx
+        .faults
+        .as_ref()
+        .expect("the plan should be kept")
+        .injected();
+    assert_eq!(injected, [faults[1], faults[0]]);
+}
+
+#[test]
+fn failing_runs_shrink_to_the_fault_that_matters() {
+    let seed = 5;
+    let mut sim = sequence(seed, &[]);
+    sim.ctx.set_drop_rate(0.3);
+    sim.ctx.set_duplicate_rate(0.2);
+    send_numbered(&mut sim, 20).run_with_hooks(&in_order());
+
+    let violation = sim
+        .ctx
+        .violation
+        .take()
+        .expect("the faults should break the order");
+    let injected = sim
+        .ctx
+        .faults
+        .as_ref()
+        .expect("the plan should be kept")
+        .injected()
+        .to_vec();
+    assert!(injected.len() > 1);
+
+    let shrunk = shrink::shrink(&injected, &violation.invariant, 100, |faults| {
+        replay(seed, faults)
+    })
+    .expect("the run should fail again with its faults");
+
+    assert_eq!(shrunk.faults.len(), 1);
+    assert_eq!(shrunk.injected, injected.len());
+    assert!(injected.contains(&shrunk.faults[0]));
+    // the shrunk faults are enough without the rates
+    let replayed = replay(seed, &shrunk.faults).expect("the shrunk faults should fail");
+    assert_eq!(replayed.reason, shrunk.violation.reason);
+}
+
+#[test]
+fn runs_that_fail_without_faults_shrink_to_none() {
+    let faults = [Fault::Drop {
+        from: 1,
+        to: 0,
+        nth: 0,
+    }];
+    let shrunk = shrink::shrink(&faults, "always", 10, |_| {
+        Some(InvariantViolation {
+            invariant: "always".to_string(),
+            reason: String::new(),
+            seed: 0,
+            clock: 0.0,
+            events_processed: 0,
+            recent_events: Vec::new(),
+        })
+    })
+    .expect("the run fails");
+
+    assert!(shrunk.faults.is_empty());
+    assert_eq!(shrunk.runs, 2);
+}
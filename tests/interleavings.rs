@@ -0,0 +1,155 @@
+use tdes::{
+    internal::{
+        core::{experiment::LinkKind, interleaving::Interleaving},
+        explore::{ExploreOptions, ExploreStrategy},
+    },
+    prelude::*,
+    testing::{self, TestSimulation},
+};
+
+#[derive(Debug, Clone)]
+struct Hello;
+
+impl Message for Hello {
+    fn size_bytes(&self) -> u64 {
+        8
+    }
+}
+
+/// Remembers the order in which it heard from the other peers.
+#[derive(Debug)]
+struct ListenerPeer {
+    peer_info: PeerInfo,
+    heard: Vec<usize>,
+}
+
+define_custom_peer!(ListenerPeer, debug);
+
+impl ListenerPeer {
+    fn new() -> Self {
+        Self {
+            peer_info: PeerInfo::default().with_on_message_receive(listen),
+            heard: Vec::new(),
+        }
+    }
+}
+
+fn listen(ctx: &mut Context, sender: usize, receiver: usize, _msg: &dyn Message) {
+    let peer = get_peer_of_type!(ctx, receiver, ListenerPeer).expect("peer should exist");
+    peer.heard.push(sender);
+}
+
+/// Peers `1..=senders` say hello to peer 0 at the same time, and peer 0 must hear
+/// from peer 1 first.
+fn race(sim: &mut TestSimulation, senders: usize) -> SimulationHooks {
+    sim.add_peer(ListenerPeer::new());
+    sim.add_test_peers(senders);
+    for sender in 1..=senders {
+        sim.link(
+            0,
+            sender,
+            Some(LinkKind::Latency(SimDuration::from_secs(1.0))),
+        )
+        .send(sender, 0, Hello);
+    }
+
+    let mut hooks = SimulationHooks::default();
+    hooks.add_invariant(
+        "peer 1 is heard first",
+        1,
+        Box::new(|ctx| match ctx.peers[0].downcast_ref::<ListenerPeer>() {
+            Some(peer) if peer.heard.first().is_some_and(|&first| first != 1) => {
+                Err(format!("peer {} was heard first", peer.heard[0]))
+            }
+            _ => Ok(()),
+        }),
+    );
+    hooks
+}
+
+fn options(strategy: ExploreStrategy) -> ExploreOptions {
+    ExploreOptions {
+        strategy,
+        ..ExploreOptions::default()
+    }
+}
+
+#[test]
+fn default_order_keeps_the_invariant() {
+    let mut sim = TestSimulation::new(1);
+    let hooks = race(&mut sim, 3);
+    sim.run_with_hooks(&hooks);
+
+    assert!(sim.ctx.violation.is_none());
+    assert_eq!(sim.peer::<ListenerPeer>(0).heard, vec![1, 2, 3]);
+}
+
+#[test]
+fn every_strategy_finds_the_failing_order() {
+    for strategy in [
+        ExploreStrategy::Dfs,
+        ExploreStrategy::Delay,
+        ExploreStrategy::Pct,
+    ] {
+        let exploration = testing::explore(&options(strategy), 1, |sim| race(sim, 3));
+        let counterexample = exploration
+            .counterexample
+            .unwrap_or_else(|| panic!("{strategy:?} should find the failing order"));
+
+        assert_eq!(counterexample.violation.invariant, "peer 1 is heard first");
+        // a single reordering at the first delivery is enough
+        assert_eq!(counterexample.reorderings.len(), 1, "{strategy:?}");
+        let reordering = &counterexample.reorderings[0];
+        assert_eq!(reordering.choice_point, 0);
+        assert_eq!(reordering.instead_of.sender, Some(1));
+        assert_ne!(reordering.processed.sender, Some(1));
+    }
+}
+
+#[test]
+fn depth_first_exploration_visits_every_order() {
+    let mut options = options(ExploreStrategy::Dfs);
+    let exploration = testing::explore(&options, 1, |sim| {
+        race(sim, 3);
+        SimulationHooks::default()
+    });
+    assert!(exploration.counterexample.is_none());
+    assert_eq!(exploration.runs, 6);
+
+    options.runs = 4;
+    let exploration = testing::explore(&options, 1, |sim| {
+        race(sim, 3);
+        SimulationHooks::default()
+    });
+    assert_eq!(exploration.runs, 4);
+}
+
+#[test]
+fn no_deviations_are_allowed_with_a_bound_of_zero() {
+    let options = ExploreOptions {
+        bound: 0,
+        ..options(ExploreStrategy::Delay)
+    };
+    let exploration = testing::explore(&options, 1, |sim| race(sim, 3));
+
+    assert!(exploration.counterexample.is_none());
+    assert_eq!(exploration.runs, 1);
+}
+
+#[test]
+fn failing_schedules_can_be_replayed() {
+    let exploration = testing::explore(&options(ExploreStrategy::Dfs), 1, |sim| race(sim, 3));
+    let counterexample = exploration.counterexample.expect("the order should fail");
+
+    let mut sim = TestSimulation::new(1);
+    let hooks = race(&mut sim, 3);
+    sim.ctx.interleaving = Some(Box::new(Interleaving::new(
+        counterexample.window,
+        counterexample.schedule.clone(),
+    )));
+    sim.run_with_hooks(&hooks);
+
+    let violation = sim.ctx.violation.as_ref().expect("the replay should fail");
+    assert_eq!(violation.reason, counterexample.violation.reason);
+    assert_eq!(violation.clock, 1.0);
+}
@@ -0,0 +1,144 @@
+use tdes::{
+    prelude::*,
+    scenarios::{Example, FlowUpdatingPairwise, SimpleMessageScenario},
+    testing::{self, TestSimulation},
+};
+
+fn options(n_peers: usize, topology: &str, deadline: Option<SimTime>) -> ExperimentOptions {
+    ExperimentOptions {
+        topology: TopologyInfo::from_args(Some(n_peers), Some(topology.to_string())),
+        arrival_time: None,
+        deadline,
+    }
+}
+
+#[test]
+fn simple_message_matches_its_golden_log() {
+    let config = serde_yaml::from_str("msg_size: 16").expect("config should be valid");
+
+    let mut sim = TestSimulation::new(42);
+    sim.run_scenario::<SimpleMessageScenario>(
+        &Simulator::default(),
+        options(4, "star", None),
+        config,
+    );
+
+    let deliveries: Vec<(Option<usize>, Option<u64>)> = sim
+        .trace()
+        .iter()
+        .filter(|record| record.message_type.as_deref() == Some("EmptyMessage"))
+        .map(|record| (record.receiver, record.message_size))
+        .collect();
+    assert_eq!(
+        deliveries,
+        vec![
+            (Some(1), Some(16)),
+            (Some(2), Some(16)),
+            (Some(3), Some(16))
+        ]
+    );
+    sim.assert_golden_log(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/golden/simple_message.log"
+    ));
+}
+
+#[test]
+#[should_panic(expected = "does not exist")]
+fn missing_golden_files_fail() {
+    let path = std::env::temp_dir().join("tdes_missing_golden.log");
+    testing::assert_golden(path, "not written");
+}
+
+#[test]
+fn flow_updating_stops_at_the_deadline() {
+    let config =
+        serde_yaml::from_str("values: [10, 20.9, 30, 60]").expect("config should be valid");
+
+    let mut sim = TestSimulation::new(42);
+    sim.run_scenario::<FlowUpdatingPairwise>(
+        &Simulator::default(),
+        options(4, "ring", Some(SimTime::from_secs(0.5))),
+        config,
+    );
+
+    assert_eq!(sim.ctx.clock.as_secs(), 0.5);
+    assert!(sim.trace().iter().all(|record| record.timestamp <= 0.5));
+    assert!(
+        sim.log()
+            .iter()
+            .any(|line| line.ends_with("should_be: 30.0"))
+    );
+}
+
+#[test]
+fn schedulers_process_events_in_the_same_order() {
+    let run = |scheduler| {
+        let config = serde_yaml::from_str("{}").expect("config should be valid");
+        let mut sim = TestSimulation::new(7);
+        sim.ctx.set_scheduler(scheduler);
+        sim.run_scenario::<FlowUpdatingPairwise>(
+            &Simulator::default(),
+            options(30, "ring", Some(SimTime::from_secs(1.5))),
+            config,
+        );
+        sim.trace().to_vec()
+    };
+
+    let heap = run(SchedulerKind::Heap);
+    assert!(heap.len() > 1000);
+    assert!(heap == run(SchedulerKind::Calendar));
+}
+
+#[test]
+fn runs_write_their_statistics_to_the_metrics_without_progress() {
+    let experiment: Experiment = serde_yaml::from_str(
+        "{name: stats, scenario: simple_message, seed: 3, topology: {n_peers: 4, name: star}}",
+    )
+    .expect("experiment should be valid");
+    let dir = std::env::temp_dir().join(format!("tdes_run_stats_{}", std::process::id()));
+    let mut config = SimulationConfig::from_experiments(vec![experiment]);
+    config.dir = Some(dir.to_string_lossy().to_string());
+
+    let mut simulator = Simulator::default();
+    simulator
+        .add_scenario::<SimpleMessageScenario>()
+        .set_options(RunOptions {
+            quiet: true,
+            ..RunOptions::default()
+        });
+    let results = simulator
+        .run_config(&mut config)
+        .expect("the experiment should run");
+
+    let results_dir = results[0].results_dir.as_deref().unwrap();
+    let record = &results[0].repetitions[0].record;
+    let metrics_file = record.metrics_file.as_deref().unwrap();
+    let metrics = std::fs::read_to_string(format!("{results_dir}/{metrics_file}")).unwrap();
+    assert!(metrics.contains("\"title\":\"run_stats\""));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn scenarios_without_options_reject_unknown_fields() {
+    let mut simulator = Simulator::default();
+    simulator.add_scenario::<Example>();
+    let mut ctx = Context::new(Some(1), None, true);
+    let extra_args = serde_yaml::from_str("{speed: 3}").expect("extra_args should be valid");
+
+    let started = simulator.scenario_registry.run_scenario(
+        "example",
+        &mut ctx,
+        &simulator,
+        options(3, "full", None),
+        Some(extra_args),
+    );
+    assert_eq!(
+        started,
+        Err(
+            "Invalid extra_args for scenario 'example': unknown fields [\"speed\"], \
+             accepted options are []"
+                .to_string()
+        )
+    );
+}
@@ -0,0 +1,84 @@
+use std::{env, fs};
+
+use tdes::{
+    internal::core::{
+        distributions::DistributionWrapper,
+        experiment::Jitter,
+        snapshot::{CheckpointPlan, ContextSnapshot, Resume, SnapshotRegistry},
+        trace::TraceRecord,
+    },
+    prelude::*,
+    scenarios::FlowUpdatingPairwise,
+    testing::TestSimulation,
+};
+
+const CHECKPOINT: f64 = 0.2;
+
+// flow updating with jitter and drops, saving a checkpoint to `dir` if given
+fn flow_updating(dir: Option<&str>) -> TestSimulation {
+    let mut sim = TestSimulation::new(11);
+    sim.ctx.set_drop_rate(0.1);
+    sim.ctx.set_jitter(Jitter {
+        distribution: DistributionWrapper::Uniform(0.0, 5.0),
+        multiplier: 1.0e-3,
+        unit: Default::default(),
+    });
+    sim.ctx.checkpoints = Some(CheckpointPlan::new(
+        "flow",
+        0,
+        dir.map(str::to_string),
+        vec![SimTime::from_secs(CHECKPOINT)],
+    ));
+    sim
+}
+
+fn run(sim: &mut TestSimulation) {
+    let opts = ExperimentOptions {
+        topology: TopologyInfo::from_args(Some(6), Some("ring".to_string())),
+        arrival_time: None,
+        deadline: Some(SimTime::from_secs(0.5)),
+    };
+    let config =
+        serde_yaml::from_str("values: [10, 20, 30, 40, 50, 60]").expect("config should be valid");
+    sim.run_scenario::<FlowUpdatingPairwise>(&Simulator::default(), opts, config);
+}
+
+#[test]
+fn resumed_runs_continue_like_the_run_they_were_saved_from() {
+    let mut full = flow_updating(None);
+    run(&mut full);
+
+    let dir = env::temp_dir().join(format!("tdes_checkpoints_{}", std::process::id()));
+    let dir = dir.to_str().expect("the path should be valid");
+    let mut saved = flow_updating(Some(dir));
+    run(&mut saved);
+    assert_eq!(saved.trace(), full.trace());
+
+    let snapshot = ContextSnapshot::read(format!("{dir}/flow_0_checkpoint_{CHECKPOINT}.json"))
+        .expect("the checkpoint should have been saved");
+    let mut registry = SnapshotRegistry::default();
+    FlowUpdatingPairwise::register_snapshot_types(&mut registry);
+    let mut resumed = flow_updating(None);
+    resumed.ctx.resume = Some(Box::new(Resume { snapshot, registry }));
+    run(&mut resumed);
+    fs::remove_dir_all(dir).ok();
+
+    // the resumed trace only has the events processed after the checkpoint
+    let after: Vec<&TraceRecord> = full
+        .trace()
+        .iter()
+        .filter(|record| record.timestamp > CHECKPOINT)
+        .collect();
+    let resumed_trace: Vec<&TraceRecord> = resumed.trace().iter().collect();
+    assert!(!after.is_empty());
+    assert_eq!(resumed_trace, after);
+
+    assert_eq!(
+        resumed.ctx.network_stats.to_json(),
+        full.ctx.network_stats.to_json()
+    );
+    assert_eq!(
+        serde_json::to_value(resumed.ctx.metrics.save()).unwrap(),
+        serde_json::to_value(full.ctx.metrics.save()).unwrap()
+    );
+}